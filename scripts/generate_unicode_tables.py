@@ -0,0 +1,190 @@
+#!/usr/bin/env python3
+"""Generates src/lex/unicode/tables.rs from the Unicode Character Database (UCD).
+
+The tables are generated from the following files of the UCD, all of the same version:
+
+* UnicodeData.txt (general categories)
+* DerivedCoreProperties.txt (XID_Start and XID_Continue)
+* PropList.txt (White_Space)
+
+By default, the files are downloaded from https://www.unicode.org/Public/<version>/ucd/.
+Alternatively, they may be read from a local directory (such as an extracted UCD.zip of the same
+version) with --ucd-dir.
+
+Usage:
+    scripts/generate_unicode_tables.py [--version VERSION] [--ucd-dir DIR] [--output FILE]
+"""
+
+import argparse
+import os
+import re
+import sys
+import urllib.request
+
+UCD_VERSION = "14.0.0"
+UCD_URL = "https://www.unicode.org/Public/{version}/ucd/{file}"
+DEFAULT_OUTPUT = os.path.join(
+    os.path.dirname(os.path.abspath(__file__)), "..", "src", "lex", "unicode", "tables.rs"
+)
+
+MAX_CODE_POINT = 0x10FFFF
+SURROGATES = range(0xD800, 0xE000)
+
+# The general categories, in the order of their tables, by their short names. Surrogates (Cs)
+# aren't characters, so they have no table.
+GENERAL_CATEGORIES = [
+    ("Lu", "UPPERCASE_LETTER"),
+    ("Ll", "LOWERCASE_LETTER"),
+    ("Lt", "TITLECASE_LETTER"),
+    ("Lm", "MODIFIER_LETTER"),
+    ("Lo", "OTHER_LETTER"),
+    ("Mn", "NONSPACING_MARK"),
+    ("Mc", "SPACING_MARK"),
+    ("Me", "ENCLOSING_MARK"),
+    ("Nd", "DECIMAL_NUMBER"),
+    ("Nl", "LETTER_NUMBER"),
+    ("No", "OTHER_NUMBER"),
+    ("Pc", "CONNECTOR_PUNCTUATION"),
+    ("Pd", "DASH_PUNCTUATION"),
+    ("Ps", "OPEN_PUNCTUATION"),
+    ("Pe", "CLOSE_PUNCTUATION"),
+    ("Pi", "INITIAL_PUNCTUATION"),
+    ("Pf", "FINAL_PUNCTUATION"),
+    ("Po", "OTHER_PUNCTUATION"),
+    ("Sm", "MATH_SYMBOL"),
+    ("Sc", "CURRENCY_SYMBOL"),
+    ("Sk", "MODIFIER_SYMBOL"),
+    ("So", "OTHER_SYMBOL"),
+    ("Zs", "SPACE_SEPARATOR"),
+    ("Zl", "LINE_SEPARATOR"),
+    ("Zp", "PARAGRAPH_SEPARATOR"),
+    ("Cc", "CONTROL"),
+    ("Cf", "FORMAT"),
+    ("Co", "PRIVATE_USE"),
+    ("Cn", "UNASSIGNED"),
+]
+
+# The binary properties, in the order of their tables, by the files listing them
+PROPERTIES = [
+    ("DerivedCoreProperties.txt", "XID_Start", "XID_START"),
+    ("DerivedCoreProperties.txt", "XID_Continue", "XID_CONTINUE"),
+    ("PropList.txt", "White_Space", "WHITE_SPACE"),
+]
+
+
+def read_ucd_file(name, version, ucd_dir):
+    if ucd_dir is not None:
+        with open(os.path.join(ucd_dir, name), encoding="utf-8") as file:
+            contents = file.read()
+    else:
+        url = UCD_URL.format(version=version, file=name)
+        with urllib.request.urlopen(url) as response:
+            contents = response.read().decode("utf-8")
+
+    # Files other than UnicodeData.txt start by naming their version
+    if name != "UnicodeData.txt":
+        expected_header = "# {}-{}.txt".format(name[: -len(".txt")], version)
+        if not contents.startswith(expected_header):
+            sys.exit("{} is not of version {}".format(name, version))
+    return contents
+
+
+def parse_general_categories(unicode_data):
+    """Maps each code point to its general category (Cn for code points that aren't listed)."""
+    categories = ["Cn"] * (MAX_CODE_POINT + 1)
+    range_start = None
+    for line in unicode_data.splitlines():
+        fields = line.split(";")
+        code_point, name, category = int(fields[0], 16), fields[1], fields[2]
+
+        # Large ranges of code points are listed by their first and last code points
+        if name.endswith(", First>"):
+            range_start = code_point
+            continue
+        if name.endswith(", Last>"):
+            for listed_code_point in range(range_start, code_point + 1):
+                categories[listed_code_point] = category
+            range_start = None
+            continue
+        categories[code_point] = category
+    return categories
+
+
+def parse_property(property_list, property_name):
+    """Lists the code points having the specified property."""
+    code_points = set()
+    for line in property_list.splitlines():
+        line = line.split("#", 1)[0].strip()
+        if not line:
+            continue
+        code_points_field, name = (field.strip() for field in line.split(";"))
+        if name != property_name:
+            continue
+        match = re.fullmatch(r"([0-9A-F]+)(?:\.\.([0-9A-F]+))?", code_points_field)
+        start = int(match.group(1), 16)
+        end = int(match.group(2), 16) if match.group(2) else start
+        code_points.update(range(start, end + 1))
+    return code_points
+
+
+def to_ranges(code_points):
+    """Compacts a set of code points into sorted, inclusive, maximal ranges of characters."""
+    ranges = []
+    for code_point in sorted(code_points):
+        if code_point in SURROGATES:
+            continue
+        if ranges and ranges[-1][1] == code_point - 1:
+            ranges[-1][1] = code_point
+        else:
+            ranges.append([code_point, code_point])
+    return ranges
+
+
+def emit_table(output, name, doc, ranges):
+    output.write("\n/// {}\npub(super) const {}: &[(char, char)] = &[\n".format(doc, name))
+    for start, end in ranges:
+        output.write("    ('\\u{{{:x}}}', '\\u{{{:x}}}'),\n".format(start, end))
+    output.write("];\n")
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("--version", default=UCD_VERSION, help="the version of the UCD")
+    parser.add_argument("--ucd-dir", help="read the UCD files from this directory")
+    parser.add_argument("--output", default=DEFAULT_OUTPUT, help="the generated file")
+    args = parser.parse_args()
+
+    def read(name):
+        return read_ucd_file(name, args.version, args.ucd_dir)
+
+    categories = parse_general_categories(read("UnicodeData.txt"))
+    property_lists = {name: read(name) for name in {file for file, _, _ in PROPERTIES}}
+
+    with open(args.output, "w", encoding="utf-8", newline="\n") as output:
+        output.write(
+            "//! Character tables, generated from the Unicode Character Database (version {}).\n"
+            "//!\n"
+            "//! Each table lists the (inclusive) ranges of the code points having some property, "
+            "in ascending\n"
+            "//! order. This file is generated by `scripts/generate_unicode_tables.py` from "
+            "`UnicodeData.txt`,\n"
+            "//! `DerivedCoreProperties.txt` and `PropList.txt` of\n"
+            "//! <https://www.unicode.org/Public/{}/ucd/>, and shouldn't be edited manually.\n"
+            .format(args.version, args.version)
+        )
+        for file, property_name, table_name in PROPERTIES:
+            code_points = parse_property(property_lists[file], property_name)
+            doc = "Characters with the `{}` property.".format(property_name)
+            emit_table(output, table_name, doc, to_ranges(code_points))
+        for category, table_name in GENERAL_CATEGORIES:
+            code_points = (
+                code_point
+                for code_point, code_point_category in enumerate(categories)
+                if code_point_category == category
+            )
+            doc = "Characters of the `{}` general category.".format(category)
+            emit_table(output, table_name, doc, to_ranges(code_points))
+
+
+if __name__ == "__main__":
+    main()
@@ -101,6 +101,16 @@ where
         self.states[state].label.as_mut()
     }
 
+    pub fn list_transitions(
+        &self,
+        state: Handle<DfaState<Symbol, Label>>,
+    ) -> impl Iterator<Item=(Handle<Symbol>, Handle<DfaState<Symbol, Label>>)> + '_ {
+        self.states[state]
+            .transitions
+            .iter()
+            .map(|(symbol, &dst)| (symbol, dst))
+    }
+
     pub fn step(
         &self,
         src: Handle<DfaState<Symbol, Label>>,
@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::automata::dfa::{Dfa, DfaState};
+use crate::automata::nfa::{Nfa, NfaState};
+use crate::handles::{Handle, Handled};
+use crate::handles::collections::{HandleBitSet, HandleMap};
+
+type NfaStateSet<Symbol, Label> = HandleBitSet<NfaState<Symbol, Label>>;
+
+impl<Symbol, Label> Nfa<Symbol, Label>
+where
+    Symbol: Handled,
+{
+    /// Wraps the NFA with a DFA whose states are only constructed (by subset construction) when
+    /// they are first visited, and are kept in a cache of at most `cache_capacity` states.
+    pub fn into_lazy_dfa<DfaLabel>(
+        self,
+        label_reduction: fn(Vec<&Label>) -> Option<DfaLabel>,
+        cache_capacity: usize,
+    ) -> LazyDfa<Symbol, Label, DfaLabel>
+    where
+        DfaLabel: Clone,
+    {
+        LazyDfa::new(self, label_reduction, cache_capacity)
+    }
+}
+
+/// A DFA that's constructed on demand from an underlying NFA.
+///
+/// Constructed DFA states are cached, until the cache reaches its capacity - at which point it is
+/// flushed entirely. The cache is shared by all users of the DFA (possibly on different threads),
+/// which scan it through a [LazyDfaScan]. The cache is only locked while a scan steps from one
+/// state to the next, so concurrent scans only exclude each other for the duration of a step.
+/// Handles to states of a [LazyDfa] are only valid until the next flush, which may be caused by any
+/// scan (see [LazyDfaScan::count_flushes]), but each scan keeps track of its own current state
+/// across flushes.
+pub struct LazyDfa<Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+{
+    nfa: Nfa<Symbol, NfaLabel>,
+    label_reduction: fn(Vec<&NfaLabel>) -> Option<DfaLabel>,
+    cache_capacity: usize,
+    cache: Mutex<LazyDfaCache<Symbol, NfaLabel, DfaLabel>>,
+
+    // Only modified while the cache is locked
+    flush_count: AtomicUsize,
+}
+
+/// A scan of a [LazyDfa], which steps from its current state to the next (see [LazyDfa::scan]).
+pub struct LazyDfaScan<'a, Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+{
+    lazy_dfa: &'a LazyDfa<Symbol, NfaLabel, DfaLabel>,
+
+    // Absent until the initial state is obtained
+    current_state: Option<ScannedState<Symbol, NfaLabel, DfaLabel>>,
+
+    // The number of flushes before the current state's handle was obtained
+    flush_count: usize,
+}
+
+// A state of a scan, along with what's needed to reinstall it once it's flushed from the cache
+struct ScannedState<Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+{
+    handle: Handle<DfaState<Symbol, DfaLabel>>,
+    nfa_states: Arc<NfaStateSet<Symbol, NfaLabel>>,
+    label: Option<DfaLabel>,
+}
+
+impl<Symbol, NfaLabel, DfaLabel> LazyDfa<Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+    DfaLabel: Clone,
+{
+    fn new(
+        nfa: Nfa<Symbol, NfaLabel>,
+        label_reduction: fn(Vec<&NfaLabel>) -> Option<DfaLabel>,
+        cache_capacity: usize,
+    ) -> Self {
+        if nfa.initial_state.is_none() {
+            panic!("Cannot lazily compile an NFA with no initial state into a DFA")
+        }
+        if cache_capacity == 0 || cache_capacity > 1 << 16 {
+            panic!(
+                "The capacity of a lazy DFA's cache should be between 1 and {}, but {} was \
+                specified",
+                1 << 16,
+                cache_capacity,
+            )
+        }
+        Self {
+            nfa,
+            label_reduction,
+            cache_capacity,
+            cache: Mutex::new(LazyDfaCache::new()),
+            flush_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the number of times the cache was flushed so far.
+    pub fn count_flushes(&self) -> usize {
+        self.flush_count.load(Ordering::Acquire)
+    }
+
+    /// Starts a new scan of the DFA.
+    pub fn scan(&self) -> LazyDfaScan<'_, Symbol, NfaLabel, DfaLabel> {
+        LazyDfaScan {
+            lazy_dfa: self,
+            current_state: None,
+            flush_count: self.count_flushes(),
+        }
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, LazyDfaCache<Symbol, NfaLabel, DfaLabel>> {
+        // The cache is consistent between operations, so it's still valid if its previous user
+        // panicked
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Also reports whether the cache had to be flushed to make room for the state
+    fn locate_or_install(
+        &self,
+        cache: &mut LazyDfaCache<Symbol, NfaLabel, DfaLabel>,
+        nfa_states: NfaStateSet<Symbol, NfaLabel>,
+    ) -> (Handle<DfaState<Symbol, DfaLabel>>, bool) {
+        if let Some(&state) = cache.dfa_states_map.get(&nfa_states) {
+            return (state, false);
+        }
+
+        let label = (self.label_reduction)(
+            nfa_states
+                .iter()
+                .flat_map(|nfa_state| self.nfa.get_label(nfa_state))
+                .collect(),
+        );
+
+        let is_cache_flushed = cache.size >= self.cache_capacity;
+        if is_cache_flushed {
+            *cache = LazyDfaCache::new();
+            self.flush_count.fetch_add(1, Ordering::Release);
+        }
+
+        let state = cache.dfa.new_state();
+        cache.size += 1;
+        cache.dfa.label(state, label);
+        cache.dfa_states_map.insert(nfa_states.clone(), state);
+        cache.states_info.insert(
+            state,
+            CachedStateInfo {
+                nfa_states: Arc::new(nfa_states),
+                dead_symbols: HandleBitSet::new(),
+            },
+        );
+        (state, is_cache_flushed)
+    }
+}
+
+impl<'a, Symbol, NfaLabel, DfaLabel> LazyDfaScan<'a, Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+    DfaLabel: Clone,
+{
+    /// Get the number of times the cache was flushed before the handle of the scan's current state
+    /// was obtained, so the handles it obtained since that flush are still comparable.
+    pub fn count_flushes(&self) -> usize {
+        self.flush_count
+    }
+
+    /// Moves the scan to the initial state, and returns it.
+    pub fn get_initial_state(&mut self) -> Handle<DfaState<Symbol, DfaLabel>> {
+        let lazy_dfa = self.lazy_dfa;
+        let mut cache = lazy_dfa.lock_cache();
+        let initial_state = match cache.initial_state {
+            Some(initial_state) => initial_state,
+            None => {
+                let nfa = &lazy_dfa.nfa;
+                let initial_nfa_state = nfa
+                    .initial_state
+                    .expect("Lazy DFA's underlying NFA should have an initial state");
+                let nfa_states = nfa.epsilon_closure(&[initial_nfa_state].iter().collect());
+                let (initial_state, _) = lazy_dfa.locate_or_install(&mut cache, nfa_states);
+                cache.initial_state = Some(initial_state);
+                initial_state
+            }
+        };
+        self.move_to(&cache, initial_state)
+    }
+
+    /// Gets the label of the scan's current state.
+    ///
+    /// # Panics
+    ///
+    /// If the scan has no current state yet (see [LazyDfaScan::get_initial_state]).
+    pub fn get_label(&self) -> Option<DfaLabel> {
+        self.current_state
+            .as_ref()
+            .expect("A scan of a lazy DFA should obtain its initial state before its label")
+            .label
+            .clone()
+    }
+
+    /// Steps from the scan's current state by `symbol`, and returns the next state, to which the
+    /// scan moves. If there's no next state, the scan stays at its current state.
+    ///
+    /// # Panics
+    ///
+    /// If the scan has no current state yet (see [LazyDfaScan::get_initial_state]).
+    pub fn step(&mut self, symbol: Handle<Symbol>) -> Option<Handle<DfaState<Symbol, DfaLabel>>> {
+        let lazy_dfa = self.lazy_dfa;
+        let mut cache = lazy_dfa.lock_cache();
+        let src = self.revalidate(&mut cache);
+        if let Some(tar) = cache.dfa.step(src, symbol) {
+            return Some(self.move_to(&cache, tar));
+        }
+        let src_info = cache.get_info(src);
+        if src_info.dead_symbols.contains(symbol) {
+            return None;
+        }
+        let nfa = &lazy_dfa.nfa;
+        let target_nfa_states =
+            nfa.epsilon_closure(&nfa.move_by_symbol(&src_info.nfa_states, symbol));
+
+        if target_nfa_states.is_empty() {
+            cache.get_info_mut(src).dead_symbols.insert(symbol);
+            return None;
+        }
+
+        let (tar, is_cache_flushed) = lazy_dfa.locate_or_install(&mut cache, target_nfa_states);
+        if !is_cache_flushed {
+            cache.dfa.link(src, tar, symbol);
+        }
+        Some(self.move_to(&cache, tar))
+    }
+
+    /// Gets the handle of the scan's current state, which may have changed since it was obtained,
+    /// if the cache was flushed in the meantime.
+    ///
+    /// # Panics
+    ///
+    /// If the scan has no current state yet (see [LazyDfaScan::get_initial_state]).
+    pub fn get_current_state(&self) -> Handle<DfaState<Symbol, DfaLabel>> {
+        self.current_state
+            .as_ref()
+            .expect("A scan of a lazy DFA should obtain its initial state before stepping")
+            .handle
+    }
+
+    // Gets the handle of the current state, which is reinstalled if other scans flushed it
+    fn revalidate(
+        &mut self,
+        cache: &mut LazyDfaCache<Symbol, NfaLabel, DfaLabel>,
+    ) -> Handle<DfaState<Symbol, DfaLabel>> {
+        let current_state = self
+            .current_state
+            .as_ref()
+            .expect("A scan of a lazy DFA should obtain its initial state before stepping");
+        if self.flush_count == self.lazy_dfa.count_flushes() {
+            return current_state.handle;
+        }
+        let nfa_states = (*current_state.nfa_states).clone();
+        let (state, _) = self.lazy_dfa.locate_or_install(cache, nfa_states);
+        self.move_to(cache, state)
+    }
+
+    fn move_to(
+        &mut self,
+        cache: &LazyDfaCache<Symbol, NfaLabel, DfaLabel>,
+        state: Handle<DfaState<Symbol, DfaLabel>>,
+    ) -> Handle<DfaState<Symbol, DfaLabel>> {
+        self.current_state = Some(ScannedState {
+            handle: state,
+            nfa_states: cache.get_info(state).nfa_states.clone(),
+            label: cache.dfa.get_label(state).clone(),
+        });
+        self.flush_count = self.lazy_dfa.count_flushes();
+        state
+    }
+}
+
+struct LazyDfaCache<Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+{
+    dfa: Dfa<Symbol, DfaLabel>,
+    size: usize,
+    initial_state: Option<Handle<DfaState<Symbol, DfaLabel>>>,
+    dfa_states_map: HashMap<NfaStateSet<Symbol, NfaLabel>, Handle<DfaState<Symbol, DfaLabel>>>,
+    states_info: HandleMap<DfaState<Symbol, DfaLabel>, CachedStateInfo<Symbol, NfaLabel>>,
+}
+
+impl<Symbol, NfaLabel, DfaLabel> LazyDfaCache<Symbol, NfaLabel, DfaLabel>
+where
+    Symbol: Handled,
+{
+    fn new() -> Self {
+        Self {
+            dfa: Dfa::new(),
+            size: 0,
+            initial_state: None,
+            dfa_states_map: HashMap::new(),
+            states_info: HandleMap::new(),
+        }
+    }
+
+    fn get_info(
+        &self,
+        state: Handle<DfaState<Symbol, DfaLabel>>,
+    ) -> &CachedStateInfo<Symbol, NfaLabel> {
+        self.states_info
+            .get(state)
+            .expect("Every cached DFA state should have its associated info cached")
+    }
+
+    fn get_info_mut(
+        &mut self,
+        state: Handle<DfaState<Symbol, DfaLabel>>,
+    ) -> &mut CachedStateInfo<Symbol, NfaLabel> {
+        self.states_info
+            .get_mut(state)
+            .expect("Every cached DFA state should have its associated info cached")
+    }
+}
+
+struct CachedStateInfo<Symbol, NfaLabel>
+where
+    Symbol: Handled,
+{
+    // The set of NFA states represented by the DFA state, which is shared with the scans at it
+    nfa_states: Arc<NfaStateSet<Symbol, NfaLabel>>,
+
+    // Symbols for which we already know the DFA state has no outgoing transition
+    dead_symbols: HandleBitSet<Symbol>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::handles::specials::AutomaticallyHandled;
+
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Symbol {
+        Symbol0,
+        Symbol1,
+    }
+    impl AutomaticallyHandled for Symbol {
+        type HandleCoreType = u8;
+        fn serial(&self) -> usize {
+            *self as usize
+        }
+    }
+
+    // Accepts sequences of Symbol0 that end with Symbol1
+    fn build_nfa() -> Nfa<Symbol, u32> {
+        let mut nfa = Nfa::new();
+        let states = vec![nfa.new_state(), nfa.new_state(), nfa.new_state()];
+
+        nfa.link(states[0], states[1], None);
+        nfa.link(states[1], states[1], Some(Symbol::Symbol0.handle()));
+        nfa.link(states[1], states[2], Some(Symbol::Symbol1.handle()));
+
+        nfa.set_initial_state(states[0]);
+        nfa.label(states[2], Some(7));
+        nfa
+    }
+
+    fn reduce_labels(labels: Vec<&u32>) -> Option<u32> {
+        labels.into_iter().min().copied()
+    }
+
+    fn scan(lazy_dfa: &LazyDfa<Symbol, u32, u32>, stream: Vec<Symbol>) -> Option<u32> {
+        let mut scan = lazy_dfa.scan();
+        scan.get_initial_state();
+        for symbol in stream {
+            scan.step(symbol.handle())?;
+        }
+        scan.get_label()
+    }
+
+    #[test]
+    fn test_lazy_dfa() {
+        let lazy_dfa = build_nfa().into_lazy_dfa(reduce_labels, 16);
+        assert_eq!(scan(&lazy_dfa, vec![Symbol::Symbol1]), Some(7));
+        assert_eq!(
+            scan(&lazy_dfa, vec![Symbol::Symbol0, Symbol::Symbol0, Symbol::Symbol1]),
+            Some(7)
+        );
+        assert_eq!(scan(&lazy_dfa, vec![Symbol::Symbol0]), None);
+        assert_eq!(scan(&lazy_dfa, vec![Symbol::Symbol1, Symbol::Symbol1]), None);
+    }
+
+    #[test]
+    fn test_states_are_constructed_on_demand() {
+        let lazy_dfa = build_nfa().into_lazy_dfa(reduce_labels, 16);
+        lazy_dfa.scan().get_initial_state();
+        assert_eq!(lazy_dfa.lock_cache().size, 1);
+        scan(&lazy_dfa, vec![Symbol::Symbol0]);
+        assert_eq!(lazy_dfa.lock_cache().size, 2);
+    }
+
+    #[test]
+    fn test_cache_flush() {
+        let lazy_dfa = build_nfa().into_lazy_dfa(reduce_labels, 1);
+        for _ in 0..3 {
+            assert_eq!(
+                scan(&lazy_dfa, vec![Symbol::Symbol0, Symbol::Symbol1]),
+                Some(7)
+            );
+            assert_eq!(lazy_dfa.lock_cache().size, 1);
+        }
+        assert!(lazy_dfa.count_flushes() > 0);
+    }
+
+    #[test]
+    fn test_scan_across_flushes() {
+        let lazy_dfa = build_nfa().into_lazy_dfa(reduce_labels, 1);
+        let mut first_scan = lazy_dfa.scan();
+        let mut second_scan = lazy_dfa.scan();
+        first_scan.get_initial_state();
+        second_scan.get_initial_state();
+
+        // Each step of a scan flushes the state of the other one
+        for _ in 0..2 {
+            assert!(first_scan.step(Symbol::Symbol0.handle()).is_some());
+            assert!(second_scan.step(Symbol::Symbol0.handle()).is_some());
+        }
+        assert!(lazy_dfa.count_flushes() > 0);
+        assert!(first_scan.step(Symbol::Symbol1.handle()).is_some());
+        assert_eq!(first_scan.get_label(), Some(7));
+        assert!(second_scan.step(Symbol::Symbol1.handle()).is_some());
+        assert_eq!(second_scan.get_label(), Some(7));
+    }
+
+    #[test]
+    fn test_concurrent_scans() {
+        let lazy_dfa = build_nfa().into_lazy_dfa(reduce_labels, 1);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        assert_eq!(
+                            scan(&lazy_dfa, vec![Symbol::Symbol0, Symbol::Symbol1]),
+                            Some(7)
+                        );
+                    }
+                });
+            }
+        });
+    }
+}
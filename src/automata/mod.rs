@@ -3,5 +3,6 @@
 
 pub mod dfa;
 mod dfa_minimize;
+pub mod lazy_dfa;
 pub mod nfa;
 mod nfa_to_dfa;
@@ -58,6 +58,10 @@ where
         self.initial_state = Some(initial_state);
     }
 
+    pub fn get_initial_state(&self) -> Option<Handle<NfaState<Symbol, Label>>> {
+        self.initial_state
+    }
+
     pub fn link(
         &mut self,
         src: Handle<NfaState<Symbol, Label>>,
@@ -82,6 +86,10 @@ where
         };
     }
 
+    pub fn list_states(&self) -> impl Iterator<Item=Handle<NfaState<Symbol, Label>>> {
+        self.states.list_handles()
+    }
+
     pub fn label(&mut self, state: Handle<NfaState<Symbol, Label>>, label: Option<Label>) {
         self.states[state].label = label
     }
@@ -90,6 +98,24 @@ where
         &self.states[state].label
     }
 
+    // Epsilon transitions are listed with no symbol
+    pub fn list_transitions(
+        &self,
+        state: Handle<NfaState<Symbol, Label>>,
+    ) -> impl Iterator<Item=(Option<Handle<Symbol>>, Handle<NfaState<Symbol, Label>>)> + '_ {
+        let epsilon_transitions = self.states[state]
+            .epsilon_transitions
+            .iter()
+            .map(|&dst| (None, dst));
+        let symbol_transitions = self.states[state]
+            .symbol_transitions
+            .iter()
+            .flat_map(|(symbol, destinations)| {
+                destinations.iter().map(move |&dst| (Some(symbol), dst))
+            });
+        epsilon_transitions.chain(symbol_transitions)
+    }
+
     pub(super) fn epsilon_closure(
         &self,
         states: &HandleBitSet<NfaState<Symbol, Label>>,
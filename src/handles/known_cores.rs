@@ -1,5 +1,15 @@
 use crate::handles::HandleCore;
 
+impl HandleCore for u32 {
+    fn into_index(self) -> usize {
+        self as usize
+    }
+
+    fn from_index(index: usize) -> Self {
+        index as Self // Possible type confusion
+    }
+}
+
 impl HandleCore for u16 {
     fn into_index(self) -> usize {
         self as usize
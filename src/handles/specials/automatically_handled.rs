@@ -10,7 +10,8 @@ use crate::handles::{Handle, HandleCore, Handled};
 /// `enum`s. Such data types can be so easily enumerated, that there's no need to manage an
 /// auxiliary collection (e.g. [HandledVec](crate::handles::collections::HandledVec),
 /// [HandledHashMap](crate::handles::collections::HandledHashMap)) to associate enumerated handles
-/// with their known instances.
+/// with their known instances. Such `enum`s may be defined along with this trait's implementation
+/// by the [handled_enum](crate::handled_enum) macro.
 ///
 /// # Example
 /// ```rust
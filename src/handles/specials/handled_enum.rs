@@ -0,0 +1,125 @@
+use crate::handles::HandleCore;
+
+/// Defines a fieldless `enum`, and implements
+/// [AutomaticallyHandled](crate::handles::specials::AutomaticallyHandled) for it.
+///
+/// The variants' serial numbers are their indices in the definition, and the handles' core type
+/// is the narrowest one that fits all variants ([u8] for up to 256 variants, [u16] otherwise).
+/// The `enum` also gets a table of its variants' names, `NAMES`, and a `name` method, which are
+/// useful for diagnostics. Attributes (such as `derive`s and doc comments) are kept on the `enum`
+/// and its variants. Variants may not specify explicit discriminants.
+///
+/// This is most commonly used to define lexeme types, which serve as the terminal symbols of a
+/// [SyntaxDirectedTranslator](crate::parsing::SyntaxDirectedTranslator).
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::handled_enum;
+/// # use syntax_parser_generator::handles::specials::AutomaticallyHandled;
+/// handled_enum! {
+///     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+///     pub enum LexemeType {
+///         Integer,
+///         /// The addition operator.
+///         Plus,
+///         Star,
+///     }
+/// }
+///
+/// assert_eq!(LexemeType::Plus.serial(), 1);
+/// assert_eq!(LexemeType::Star.name(), "Star");
+/// assert_eq!(LexemeType::NAMES, ["Integer", "Plus", "Star"]);
+/// ```
+#[macro_export]
+macro_rules! handled_enum {
+    (
+        $(#[$meta:meta])*
+        $visibility:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $visibility enum $name {
+            $($(#[$variant_meta])* $variant),*
+        }
+
+        impl $name {
+            /// The names of the variants, indexed by their serial numbers.
+            #[allow(dead_code)]
+            $visibility const NAMES: &'static [&'static str] = &[$(stringify!($variant)),*];
+
+            /// The name of the variant.
+            #[allow(dead_code)]
+            $visibility fn name(&self) -> &'static str {
+                Self::NAMES[$crate::handles::specials::AutomaticallyHandled::serial(self)]
+            }
+        }
+
+        impl $crate::handles::specials::AutomaticallyHandled for $name {
+            type HandleCoreType = <$crate::handles::specials::HandleCoreSelector<
+                { $name::NAMES.len() <= 1 << u8::BITS },
+            > as $crate::handles::specials::SelectHandleCore>::HandleCoreType;
+
+            fn serial(&self) -> usize {
+                match self {
+                    $($name::$variant => $name::$variant as usize),*
+                }
+            }
+        }
+    };
+}
+
+// Selects the core type of the handles of enums defined by `handled_enum!`, by whether their
+// variants fit in a byte
+#[doc(hidden)]
+pub struct HandleCoreSelector<const FITS_IN_BYTE: bool>;
+
+#[doc(hidden)]
+pub trait SelectHandleCore {
+    type HandleCoreType: HandleCore;
+}
+
+impl SelectHandleCore for HandleCoreSelector<true> {
+    type HandleCoreType = u8;
+}
+
+impl SelectHandleCore for HandleCoreSelector<false> {
+    type HandleCoreType = u16;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use crate::handles::Handle;
+    use crate::handles::specials::AutomaticallyHandled;
+
+    use super::*;
+
+    handled_enum! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+    }
+
+    #[test]
+    fn test_handled_enum() {
+        assert_eq!(Color::NAMES, ["Red", "Green", "Blue"]);
+        assert_eq!(Color::Blue.serial(), 2);
+        assert_eq!(Color::Green.name(), "Green");
+        assert_eq!(Color::Red.handle(), Handle::from(0));
+        assert_eq!(size_of::<Handle<Color>>(), 1);
+    }
+
+    #[test]
+    fn test_handle_core_selection() {
+        type Core<const FITS_IN_BYTE: bool> =
+            <HandleCoreSelector<FITS_IN_BYTE> as SelectHandleCore>::HandleCoreType;
+        assert_eq!(size_of::<Core<true>>(), 1);
+        assert_eq!(size_of::<Core<false>>(), 2);
+    }
+}
@@ -1,7 +1,10 @@
 //! Traits for [Handled](super::Handled) types with special properties.
 
 pub use automatically_handled::AutomaticallyHandled;
+#[doc(hidden)]
+pub use handled_enum::{HandleCoreSelector, SelectHandleCore};
 pub use orderly_handled::OrderlyHandled;
 
 mod automatically_handled;
+mod handled_enum;
 mod orderly_handled;
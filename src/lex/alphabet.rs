@@ -0,0 +1,175 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::handles::specials::AutomaticallyHandled;
+
+/// A type of symbols that lexical analyzers can operate on.
+///
+/// By default, lexical analyzers operate on bytes (UTF-8 encoded text). Any other finite alphabet
+/// can be used instead, such as Unicode characters (`char`), DNA bases, or the lexemes extracted
+/// by a previous lexical analyzer. The symbols are identified by their distinct
+/// [serials](AutomaticallyHandled::serial).
+///
+/// The analyzer's automata don't store a transition for each symbol of the alphabet, but for each
+/// class of symbols that its patterns don't distinguish (such as all the letters of a character
+/// range), so large alphabets are supported as long as the patterns distinguish at most
+/// 2<sup>16</sup> - 4 classes. Still, the alphabet is listed by
+/// [Regex::any_symbol](crate::lex::Regex::any_symbol), and whenever an analyzer is built - unless
+/// [Alphabet::find_symbol] is overridden - so listing it should be reasonably fast.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::handles::specials::AutomaticallyHandled;
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::AddressBasedReader;
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// enum Base { A, C, G, T }
+///
+/// impl AutomaticallyHandled for Base {
+///     type HandleCoreType = u8;
+///     fn serial(&self) -> usize { *self as usize }
+/// }
+///
+/// impl Alphabet for Base {
+///     type Contents = Vec<Base>;
+///     fn list_symbols() -> Vec<Self> { vec![Base::A, Base::C, Base::G, Base::T] }
+///     fn to_contents(symbols: Vec<Self>) -> Vec<Base> { symbols }
+/// }
+///
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { StartCodon, Base }
+/// let lexical_analyzer = LexicalAnalyzer::new(vec![
+///     LexemeDescriptor::new(
+///         MyLexemeType::StartCodon,
+///         Regex::concat([Base::A, Base::T, Base::G].map(Regex::symbol).to_vec()),
+///     ),
+///     LexemeDescriptor::new(MyLexemeType::Base, Regex::any_symbol()),
+/// ]);
+///
+/// let input = &mut AddressBasedReader::raw_new(vec![Base::C, Base::A, Base::T, Base::G]);
+/// let lexemes: Vec<(MyLexemeType, Vec<Base>)> = lexical_analyzer
+///     .analyze(input)
+///     .map(|lexeme| (lexeme.lexeme_type, lexeme.contents))
+///     .collect();
+/// assert_eq!(
+///     lexemes,
+///     vec![
+///         (MyLexemeType::Base, vec![Base::C]),
+///         (MyLexemeType::StartCodon, vec![Base::A, Base::T, Base::G]),
+///     ],
+/// );
+/// ```
+pub trait Alphabet: AutomaticallyHandled + Copy + Ord + Hash + Debug {
+    /// The representation of the contents of lexemes made of these symbols.
+    type Contents: Debug + Clone + PartialEq + Eq + Hash;
+
+    /// Lists all the symbols of the alphabet.
+    fn list_symbols() -> Vec<Self>;
+
+    /// Finds the symbol of the smallest serial in `serials`, if any.
+    ///
+    /// Defaults to a search of [Alphabet::list_symbols], which alphabets that are expensive to list
+    /// should avoid.
+    fn find_symbol(serials: Range<usize>) -> Option<Self> {
+        Self::list_symbols()
+            .into_iter()
+            .filter(|symbol| serials.contains(&symbol.serial()))
+            .min_by_key(Self::serial)
+    }
+
+    /// Converts the sequence of symbols that a lexeme consists of into its contents.
+    fn to_contents(symbols: Vec<Self>) -> Self::Contents;
+
+    /// Checks whether the symbol breaks lines, which determines where line anchors (such as
+    /// [Regex::line_start](crate::lex::Regex::line_start)) hold. Defaults to `false`.
+    fn is_line_break(&self) -> bool {
+        false
+    }
+
+    /// Describes the symbol in diagnostics, such as the visualizations of a lexical analyzer's
+    /// automata (see [LexicalAnalyzer::nfa_to_dot](crate::lex::LexicalAnalyzer::nfa_to_dot)).
+    /// Defaults to its [Debug] representation.
+    fn describe(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl AutomaticallyHandled for u8 {
+    type HandleCoreType = u8;
+    fn serial(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Alphabet for u8 {
+    type Contents = String;
+
+    fn list_symbols() -> Vec<Self> {
+        (u8::MIN..=u8::MAX).collect()
+    }
+
+    fn find_symbol(serials: Range<usize>) -> Option<Self> {
+        u8::try_from(serials.start)
+            .ok()
+            .filter(|_| !serials.is_empty())
+    }
+
+    fn to_contents(symbols: Vec<Self>) -> String {
+        String::from_utf8(symbols)
+            .expect("Tokens from lexically-analyzed Reader<u8> are expected to be UTF-8 encoded")
+    }
+
+    fn is_line_break(&self) -> bool {
+        *self == b'\n'
+    }
+
+    // Printable ASCII characters are shown as such, and other bytes in hexadecimal
+    fn describe(&self) -> String {
+        if self.is_ascii_graphic() {
+            (*self as char).to_string()
+        } else {
+            format!("\\x{:02X}", self)
+        }
+    }
+}
+
+impl AutomaticallyHandled for char {
+    type HandleCoreType = u32;
+    fn serial(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Alphabet for char {
+    type Contents = String;
+
+    fn list_symbols() -> Vec<Self> {
+        ('\0'..=char::MAX).collect()
+    }
+
+    // Only the surrogates' serials in the range may have no character
+    fn find_symbol(serials: Range<usize>) -> Option<Self> {
+        serials
+            .take_while(|&serial| serial <= char::MAX as usize)
+            .find_map(|serial| char::from_u32(serial as u32))
+    }
+
+    fn to_contents(symbols: Vec<Self>) -> String {
+        symbols.into_iter().collect()
+    }
+
+    fn is_line_break(&self) -> bool {
+        *self == '\n'
+    }
+
+    // Control and white-space characters are shown by their escapes, and others as such
+    fn describe(&self) -> String {
+        if self.is_control() || self.is_whitespace() {
+            self.escape_default().to_string()
+        } else {
+            self.to_string()
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::automata::dfa::{Dfa, DfaState};
+use crate::automata::lazy_dfa::{LazyDfa, LazyDfaScan};
+use crate::handles::Handle;
+use crate::handles::specials::AutomaticallyHandled;
+use crate::lex::Alphabet;
+use crate::lex::regex::Anchor;
+use crate::lex::symbol_classes::{SymbolClass, SymbolClasses};
+
+// The number of distinct anchors, whose serials precede the ones of the input's symbols' classes
+const ANCHORS_COUNT: usize = 3;
+
+/// The maximal number of classes of symbols that an automaton may distinguish, as its symbols are
+/// handled by `u16`s, along with the anchors.
+pub(super) const MAX_SYMBOL_CLASSES: usize = u16::MAX as usize - ANCHORS_COUNT;
+
+/// The symbols consumed by the automata that drive lexical analyzers.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum LexerSymbol<Symbol> {
+    /// The class of a symbol of the input (see [SymbolClasses]).
+    Input(SymbolClass<Symbol>),
+
+    /// A virtual symbol, fed to the automaton at input positions where the anchor's assertion
+    /// holds.
+    ///
+    /// Every state of an automaton with transitions by an anchor has such a transition, leading to
+    /// a state that also keeps track of the original one. Automata with no transitions by an anchor
+    /// are simply not fed with it.
+    Anchor(Anchor),
+}
+
+impl<Symbol> AutomaticallyHandled for LexerSymbol<Symbol>
+where
+    Symbol: Alphabet,
+{
+    type HandleCoreType = u16;
+    fn serial(&self) -> usize {
+        match self {
+            LexerSymbol::Input(class) => ANCHORS_COUNT + class.index(),
+            LexerSymbol::Anchor(anchor) => *anchor as usize,
+        }
+    }
+}
+
+impl<Symbol> LexerSymbol<Symbol> {
+    /// Gets the symbol of the specified serial.
+    pub(super) fn from_serial(serial: usize) -> Self {
+        match serial {
+            0 => LexerSymbol::Anchor(Anchor::LineStart),
+            1 => LexerSymbol::Anchor(Anchor::LineEnd),
+            2 => LexerSymbol::Anchor(Anchor::InputEnd),
+            serial => LexerSymbol::Input(SymbolClass::from_index(serial - ANCHORS_COUNT)),
+        }
+    }
+}
+
+// The automata used by lexical analyzers are labeled with the indices of the lexeme descriptors
+// their accepting states match
+pub(super) type LexerState<Symbol> = Handle<DfaState<LexerSymbol<Symbol>, usize>>;
+
+/// The DFA that drives a lexical analyzer, constructed according to a
+/// [DfaConstruction](crate::lex::DfaConstruction), along with the classes of symbols it consumes.
+pub(super) struct LexerAutomaton<Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) dfa: LexerDfa<Symbol>,
+    pub(super) symbol_classes: SymbolClasses<Symbol>,
+}
+
+pub(super) enum LexerDfa<Symbol>
+where
+    Symbol: Alphabet,
+{
+    Eager(Dfa<LexerSymbol<Symbol>, usize>),
+    Lazy(LazyDfa<LexerSymbol<Symbol>, usize, usize>),
+}
+
+impl<Symbol> LexerAutomaton<Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Starts a scan of the automaton, whose states' handles remain comparable as long as its flush
+    /// count doesn't change (see [LazyDfa]).
+    pub(super) fn scan(&self) -> LexerAutomatonScan<'_, Symbol> {
+        let dfa = match &self.dfa {
+            LexerDfa::Eager(dfa) => LexerDfaScan::Eager(dfa),
+            LexerDfa::Lazy(lazy_dfa) => LexerDfaScan::Lazy(lazy_dfa.scan()),
+        };
+        LexerAutomatonScan {
+            dfa,
+            symbol_classes: &self.symbol_classes,
+        }
+    }
+
+    // Handles to states are invalidated whenever this changes
+    pub(super) fn count_flushes(&self) -> usize {
+        match &self.dfa {
+            LexerDfa::Eager(_) => 0,
+            LexerDfa::Lazy(lazy_dfa) => lazy_dfa.count_flushes(),
+        }
+    }
+}
+
+/// A scan of a [LexerAutomaton], which steps from the state it last obtained to the next.
+pub(super) struct LexerAutomatonScan<'a, Symbol>
+where
+    Symbol: Alphabet,
+{
+    dfa: LexerDfaScan<'a, Symbol>,
+    symbol_classes: &'a SymbolClasses<Symbol>,
+}
+
+enum LexerDfaScan<'a, Symbol>
+where
+    Symbol: Alphabet,
+{
+    Eager(&'a Dfa<LexerSymbol<Symbol>, usize>),
+    Lazy(LazyDfaScan<'a, LexerSymbol<Symbol>, usize, usize>),
+}
+
+impl<Symbol> LexerAutomatonScan<'_, Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) fn get_initial_state(&mut self) -> Option<LexerState<Symbol>> {
+        match &mut self.dfa {
+            LexerDfaScan::Eager(dfa) => dfa.get_initial_state(),
+            LexerDfaScan::Lazy(lazy_dfa) => Some(lazy_dfa.get_initial_state()),
+        }
+    }
+
+    /// Steps from `state`, which should be the state the scan last obtained.
+    pub(super) fn step(
+        &mut self,
+        state: LexerState<Symbol>,
+        symbol: Symbol,
+    ) -> Option<LexerState<Symbol>> {
+        let class = self.symbol_classes.classify(symbol);
+        self.step_by_symbol(state, LexerSymbol::Input(class))
+    }
+
+    /// Feeds the automaton with an anchor whose assertion holds at the current input position.
+    pub(super) fn feed(&mut self, state: LexerState<Symbol>, anchor: Anchor) -> LexerState<Symbol> {
+        match &mut self.dfa {
+            LexerDfaScan::Eager(dfa) => dfa
+                .step(state, LexerSymbol::Anchor(anchor).handle())
+                .unwrap_or(state),
+            // The handle of the current state may change if it's reinstalled after a flush
+            LexerDfaScan::Lazy(lazy_dfa) => lazy_dfa
+                .step(LexerSymbol::Anchor(anchor).handle())
+                .unwrap_or_else(|| lazy_dfa.get_current_state()),
+        }
+    }
+
+    fn step_by_symbol(
+        &mut self,
+        state: LexerState<Symbol>,
+        symbol: LexerSymbol<Symbol>,
+    ) -> Option<LexerState<Symbol>> {
+        match &mut self.dfa {
+            LexerDfaScan::Eager(dfa) => dfa.step(state, symbol.handle()),
+            LexerDfaScan::Lazy(lazy_dfa) => lazy_dfa.step(symbol.handle()),
+        }
+    }
+
+    /// Gets the label of `state`, which should be the state the scan last obtained.
+    pub(super) fn get_label(&self, state: LexerState<Symbol>) -> Option<usize> {
+        match &self.dfa {
+            LexerDfaScan::Eager(dfa) => *dfa.get_label(state),
+            LexerDfaScan::Lazy(lazy_dfa) => lazy_dfa.get_label(),
+        }
+    }
+
+    // Handles to states obtained by the scan are invalidated whenever this changes
+    pub(super) fn count_flushes(&self) -> usize {
+        match &self.dfa {
+            LexerDfaScan::Eager(_) => 0,
+            LexerDfaScan::Lazy(lazy_dfa) => lazy_dfa.count_flushes(),
+        }
+    }
+}
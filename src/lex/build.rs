@@ -0,0 +1,661 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+use crate::automata::nfa::Nfa;
+use crate::handles::specials::AutomaticallyHandled;
+use crate::lex::{Alphabet, Lexeme, LexemeDescriptor, LexicalAnalyzer, Regex};
+use crate::lex::{LexerMode, ModeAction};
+use crate::lex::automaton::{LexerAutomaton, LexerDfa, LexerSymbol};
+use crate::lex::captures::CaptureAutomaton;
+use crate::lex::end_of_input::{EndOfInput, EndOfInputAction};
+use crate::lex::lexical_analyzer::AnalyzerMode;
+use crate::lex::trace::{TraceEvent, TraceHook};
+use crate::lex::derivatives::build_dfa_by_derivatives;
+use crate::lex::symbol_classes::SymbolClasses;
+
+pub(super) const NESTED_REGION_CLOSING: usize = 0;
+pub(super) const NESTED_REGION_OPENING: usize = 1;
+
+/// The strategy by which the DFA underlying a [LexicalAnalyzer] is constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DfaConstruction {
+    /// The entire DFA is compiled and minimized when the analyzer is built.
+    ///
+    /// This is the default strategy, and the most efficient one during analysis.
+    Eager,
+
+    /// DFA states are only compiled when they are first visited during analysis.
+    ///
+    /// This makes building the analyzer instant, and makes its memory usage scale with the input
+    /// actually analyzed, rather than with the size of the lexemes' specifications. This is useful
+    /// for analyzers with very large specifications (thousands of keywords, large character
+    /// classes), most of whose DFA states are never visited in practice.
+    ///
+    /// The compiled states are cached in each of the analyzer's modes, and the cache is shared by
+    /// all of its analyses. The analyzer may still be shared between threads, but a mode's cache
+    /// is locked whenever an analysis steps between its states, so concurrent analyses in the same
+    /// mode contend for it on every input symbol, and may flush the cache for each other. Analyses
+    /// that run concurrently are better served by the other strategies.
+    Lazy {
+        /// The maximal number of DFA states to keep at once.
+        ///
+        /// When exceeded, all cached states are dropped, and compilation starts over. Should be
+        /// between 1 and 2<sup>16</sup>.
+        cache_capacity: usize,
+    },
+
+    /// The entire DFA is compiled directly from the lexemes' patterns using
+    /// [Brzozowski derivatives](https://en.wikipedia.org/wiki/Brzozowski_derivative), and
+    /// minimized, when the analyzer is built.
+    ///
+    /// This skips the intermediate NFA used by the other strategies, and is the only strategy
+    /// supporting [Regex::intersection](crate::lex::Regex::intersection) and
+    /// [Regex::complement](crate::lex::Regex::complement) patterns.
+    Derivatives,
+}
+
+/// An interface for specifying and compiling a [LexicalAnalyzer].
+///
+/// [LexicalAnalyzer::new] suffices for building analyzers with the default settings. This
+/// builder can be used to fine-tune the analyzer's construction.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { Integer, Addition }
+/// let mut builder = LexicalAnalyzerBuilder::new(vec![
+///     LexemeDescriptor::new(
+///         MyLexemeType::Integer,
+///         Regex::plus_from(Regex::character_range('0', '9')),
+///     ),
+///     LexemeDescriptor::special_char(MyLexemeType::Addition, '+'),
+/// ]);
+/// builder.set_dfa_construction(DfaConstruction::Lazy { cache_capacity: 1024 });
+/// let lexical_analyzer = builder.build();
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("12+345");
+/// assert_eq!(
+///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+///     vec![
+///         Lexeme::new(MyLexemeType::Integer, "12"),
+///         Lexeme::new(MyLexemeType::Addition, "+"),
+///         Lexeme::new(MyLexemeType::Integer, "345"),
+///     ],
+/// );
+/// ```
+pub struct LexicalAnalyzerBuilder<LexemeType, Symbol = u8>
+where
+    Symbol: Alphabet,
+{
+    // Indexed by the modes' identifiers
+    modes_lexeme_descriptors: Vec<Vec<LexemeDescriptor<LexemeType, Symbol>>>,
+
+    mode_actions: Vec<(LexerMode, LexemeType, ModeAction)>,
+    dfa_construction: DfaConstruction,
+    is_linear_time: bool,
+    is_strict_priorities: bool,
+    end_of_input_lexeme_type: Option<LexemeType>,
+    end_of_input_action: Option<EndOfInputAction<LexemeType, Symbol>>,
+    interned_lexeme_types: Vec<LexemeType>,
+    skipped_lexeme_types: Vec<LexemeType>,
+    trace_hook: Option<TraceHook<LexemeType, Symbol>>,
+}
+
+impl<LexemeType, Symbol> LexicalAnalyzerBuilder<LexemeType, Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Create a new builder for an analyzer of the lexemes described by `lexeme_descriptors`.
+    ///
+    /// See [LexicalAnalyzer::new] for more details.
+    pub fn new(lexeme_descriptors: Vec<LexemeDescriptor<LexemeType, Symbol>>) -> Self {
+        Self {
+            modes_lexeme_descriptors: vec![lexeme_descriptors],
+            mode_actions: Vec::new(),
+            dfa_construction: DfaConstruction::Eager,
+            is_linear_time: false,
+            is_strict_priorities: false,
+            end_of_input_lexeme_type: None,
+            end_of_input_action: None,
+            interned_lexeme_types: Vec::new(),
+            skipped_lexeme_types: Vec::new(),
+            trace_hook: None,
+        }
+    }
+
+    /// Create a new [LexerMode], in which the analyzer recognizes the lexemes described by
+    /// `lexeme_descriptors`.
+    ///
+    /// The analyzer starts in [LexerMode::INITIAL], whose lexemes are described by the descriptors
+    /// given to [LexicalAnalyzerBuilder::new]. Other modes are entered and exited by
+    /// [ModeAction]s (see [LexicalAnalyzerBuilder::set_mode_action]).
+    ///
+    /// # Example
+    ///
+    /// Interpolated strings, such as `"a${b}c"`, can be analyzed with a string mode, entered
+    /// when a `"` is found, and exited at the closing `"`. Inside the string, `${` enters an
+    /// expression mode, which is exited at `}`:
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Name, Quote, StringPart, InterpolationStart, InterpolationEnd }
+    /// let name = || LexemeDescriptor::new(
+    ///     MyLexemeType::Name,
+    ///     Regex::plus_from(Regex::character_range('a', 'z')),
+    /// );
+    /// let quote = || LexemeDescriptor::special_char(MyLexemeType::Quote, '"');
+    ///
+    /// let mut builder = LexicalAnalyzerBuilder::new(vec![name(), quote()]);
+    /// let string_mode = builder.new_mode(vec![
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::StringPart,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    ///     LexemeDescriptor::keyword(MyLexemeType::InterpolationStart, "${"),
+    ///     quote(),
+    /// ]);
+    /// let expression_mode = builder.new_mode(vec![
+    ///     name(),
+    ///     quote(),
+    ///     LexemeDescriptor::special_char(MyLexemeType::InterpolationEnd, '}'),
+    /// ]);
+    /// let push_string_mode = ModeAction::Push(string_mode);
+    /// builder.set_mode_action(LexerMode::INITIAL, MyLexemeType::Quote, push_string_mode);
+    /// builder.set_mode_action(string_mode, MyLexemeType::Quote, ModeAction::Pop);
+    /// builder.set_mode_action(
+    ///     string_mode,
+    ///     MyLexemeType::InterpolationStart,
+    ///     ModeAction::Push(expression_mode),
+    /// );
+    /// builder.set_mode_action(expression_mode, MyLexemeType::Quote, push_string_mode);
+    /// builder.set_mode_action(expression_mode, MyLexemeType::InterpolationEnd, ModeAction::Pop);
+    /// let lexical_analyzer = builder.build();
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("\"a${b}c\"");
+    /// assert_eq!(
+    ///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+    ///     vec![
+    ///         Lexeme::new(MyLexemeType::Quote, "\""),
+    ///         Lexeme::new(MyLexemeType::StringPart, "a"),
+    ///         Lexeme::new(MyLexemeType::InterpolationStart, "${"),
+    ///         Lexeme::new(MyLexemeType::Name, "b"),
+    ///         Lexeme::new(MyLexemeType::InterpolationEnd, "}"),
+    ///         Lexeme::new(MyLexemeType::StringPart, "c"),
+    ///         Lexeme::new(MyLexemeType::Quote, "\""),
+    ///     ],
+    /// );
+    /// ```
+    pub fn new_mode(
+        &mut self,
+        lexeme_descriptors: Vec<LexemeDescriptor<LexemeType, Symbol>>,
+    ) -> LexerMode {
+        self.modes_lexeme_descriptors.push(lexeme_descriptors);
+        LexerMode(self.modes_lexeme_descriptors.len() - 1)
+    }
+
+    /// Set the action taken on the analyzer's mode stack whenever a lexeme of type `lexeme_type`
+    /// is found while in `mode`, replacing any action previously set for them.
+    pub fn set_mode_action(
+        &mut self,
+        mode: LexerMode,
+        lexeme_type: LexemeType,
+        action: ModeAction,
+    ) {
+        self.mode_actions.push((mode, lexeme_type, action));
+    }
+
+    /// Set the strategy by which the analyzer's underlying DFA is constructed.
+    pub fn set_dfa_construction(&mut self, dfa_construction: DfaConstruction) {
+        self.dfa_construction = dfa_construction;
+    }
+
+    /// Set whether the analyzer should guarantee tokenization in time linear in the length of the
+    /// input.
+    ///
+    /// Finding the longest lexeme may require reading past its end, and rewinding back to it.
+    /// Some specifications make the analysis quadratic in the length of the input (for example,
+    /// lexemes `a` and `a*b` on input `aaa...a`), which makes the analyzer vulnerable to
+    /// denial-of-service attacks if its input is untrusted.
+    ///
+    /// When enabled, the analyzer memoizes the (DFA state, input position) pairs found to lead to
+    /// no lexeme, and never scans past them twice. This requires some additional memory while
+    /// analyzing, proportional to the distance the analyzer reads past the end of lexemes. Disabled
+    /// by default.
+    ///
+    /// Note that with [DfaConstruction::Lazy], the memoized pairs are dropped whenever the DFA's
+    /// cache is flushed. They are also dropped whenever the analyzer's [LexerMode] changes.
+    pub fn set_linear_time_tokenization(&mut self, is_linear_time: bool) {
+        self.is_linear_time = is_linear_time;
+    }
+
+    /// Set whether lexemes that are matched by descriptors of equal
+    /// [priority](LexemeDescriptor::priority), but of different lexeme types, should be considered
+    /// ambiguous, instead of being resolved by the descriptors' order.
+    ///
+    /// When enabled, [LexicalAnalyzerBuilder::build] panics if such a lexeme may be encountered.
+    /// This is useful when descriptors are collected from several independent sources, where their
+    /// order is not meaningful. Note that with anchors, the check is conservative: it may report
+    /// conflicts that only occur when anchors are asserted in combinations that never hold
+    /// together. Disabled by default.
+    ///
+    /// With [DfaConstruction::Lazy], the check is skipped, since it requires constructing the
+    /// entire DFA - which lazy construction is meant to avoid, for patterns whose DFA is too large.
+    pub fn set_strict_priorities(&mut self, is_strict_priorities: bool) {
+        self.is_strict_priorities = is_strict_priorities;
+    }
+
+    /// Set a lexeme type, of which an empty lexeme is yielded when the input is exhausted.
+    ///
+    /// This lexeme is yielded last, after any lexemes returned by the end-of-input action (see
+    /// [LexicalAnalyzerBuilder::set_end_of_input_action]). By default, no such lexeme is yielded.
+    pub fn set_end_of_input_lexeme(&mut self, lexeme_type: LexemeType) {
+        self.end_of_input_lexeme_type = Some(lexeme_type);
+    }
+
+    /// Set an action to be taken when the input is exhausted, which returns additional lexemes to
+    /// be yielded at the end of the analysis.
+    ///
+    /// The action is given the analyzer's [EndOfInput] state, which can be used to detect input
+    /// that ends inside a [LexerMode] other than the initial one (such as an unterminated string
+    /// literal), or to close any blocks left open. To abort the analysis, the action may panic.
+    /// The action must be [Send] and [Sync], so that the analyzer can be shared between threads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Quote, StringPart, UnterminatedString, EndOfInput }
+    /// let quote = || LexemeDescriptor::special_char(MyLexemeType::Quote, '"');
+    /// let mut builder = LexicalAnalyzerBuilder::new(vec![quote()]);
+    /// let string_mode = builder.new_mode(vec![
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::StringPart,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    ///     quote(),
+    /// ]);
+    /// let push_string_mode = ModeAction::Push(string_mode);
+    /// builder.set_mode_action(LexerMode::INITIAL, MyLexemeType::Quote, push_string_mode);
+    /// builder.set_mode_action(string_mode, MyLexemeType::Quote, ModeAction::Pop);
+    /// builder.set_end_of_input_action(|end_of_input| {
+    ///     if end_of_input.current_mode() == LexerMode::INITIAL {
+    ///         vec![]
+    ///     } else {
+    ///         vec![Lexeme::new(MyLexemeType::UnterminatedString, "")]
+    ///     }
+    /// });
+    /// builder.set_end_of_input_lexeme(MyLexemeType::EndOfInput);
+    /// let lexical_analyzer = builder.build();
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("\"ab");
+    /// assert_eq!(
+    ///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+    ///     vec![
+    ///         Lexeme::new(MyLexemeType::Quote, "\""),
+    ///         Lexeme::new(MyLexemeType::StringPart, "ab"),
+    ///         Lexeme::new(MyLexemeType::UnterminatedString, ""),
+    ///         Lexeme::new(MyLexemeType::EndOfInput, ""),
+    ///     ],
+    /// );
+    /// ```
+    pub fn set_end_of_input_action<F>(&mut self, action: F)
+    where
+        F: Fn(&EndOfInput) -> Vec<Lexeme<LexemeType, Symbol>> + Send + Sync + 'static,
+    {
+        self.end_of_input_action = Some(Box::new(action));
+    }
+
+    /// Set the types of lexemes whose contents are interned by
+    /// [LexicalAnalyzer::analyze_interned], replacing any types previously set.
+    ///
+    /// Lexemes of these types are yielded along with the [Atom](crate::lex::Atom) identifying
+    /// their contents in the analysis' [Interner](crate::lex::Interner), so that duplicate
+    /// contents are only stored once, and compared in constant time. By default, no lexemes are
+    /// interned. See [Interner](crate::lex::Interner) for an example.
+    pub fn set_interned_lexeme_types(&mut self, lexeme_types: Vec<LexemeType>) {
+        self.interned_lexeme_types = lexeme_types;
+    }
+
+    /// Set the types of lexemes that are skipped: analyzed as usual, but not yielded (such as
+    /// white space, or comments), replacing any types previously set.
+    ///
+    /// Skipped lexemes are still highlighted by [Highlighter](crate::lex::Highlighter), as it
+    /// accounts for the entire input. By default, no lexemes are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Name, WhiteSpace }
+    /// let mut builder = LexicalAnalyzerBuilder::new(vec![
+    ///     LexemeDescriptor::new(MyLexemeType::Name, patterns::identifier()),
+    ///     LexemeDescriptor::new(MyLexemeType::WhiteSpace, Regex::white_space()),
+    /// ]);
+    /// builder.set_skipped_lexeme_types(vec![MyLexemeType::WhiteSpace]);
+    /// let lexical_analyzer = builder.build();
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("a b");
+    /// assert_eq!(
+    ///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+    ///     vec![Lexeme::new(MyLexemeType::Name, "a"), Lexeme::new(MyLexemeType::Name, "b")],
+    /// );
+    /// ```
+    pub fn set_skipped_lexeme_types(&mut self, lexeme_types: Vec<LexemeType>) {
+        self.skipped_lexeme_types = lexeme_types;
+    }
+
+    /// Set a hook to be called with each step of the analysis, replacing any hook previously set.
+    ///
+    /// The hook is given a [TraceEvent] whenever the analyzer starts scanning for a lexeme, its
+    /// DFA moves by a symbol or an anchor, reaches an accepting state, and when the input is
+    /// rewound back to the end of the longest lexeme found. This is useful for diagnosing which
+    /// lexemes are found, and why (for example, why a shorter lexeme was preferred). By default,
+    /// no hook is set, and the analysis isn't traced. The hook must be [Send] and [Sync], so that
+    /// the analyzer can be shared between threads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::sync::{Arc, Mutex};
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Dot, Ellipsis }
+    /// let mut builder = LexicalAnalyzerBuilder::new(vec![
+    ///     LexemeDescriptor::special_char(MyLexemeType::Dot, '.'),
+    ///     LexemeDescriptor::keyword(MyLexemeType::Ellipsis, "..."),
+    /// ]);
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let trace = events.clone();
+    /// builder.set_trace_hook(move |event| trace.lock().unwrap().push(event.clone()));
+    /// let lexical_analyzer = builder.build();
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("..");
+    /// let lexemes: Vec<Lexeme<MyLexemeType>> = lexical_analyzer.analyze(input_text).collect();
+    /// assert_eq!(lexemes[0], Lexeme::new(MyLexemeType::Dot, "."));
+    ///
+    /// // The first scan read both dots, looking for an ellipsis
+    /// let events = events.lock().unwrap();
+    /// assert!(matches!(events[0], TraceEvent::ScanStart { position: 0, .. }));
+    /// assert!(matches!(
+    ///     events[2],
+    ///     TraceEvent::Accept { lexeme_type: MyLexemeType::Dot, length: 1, .. },
+    /// ));
+    /// assert!(events.contains(&TraceEvent::Rewind { scanned_length: 2, accepted_length: 1 }));
+    /// ```
+    pub fn set_trace_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&TraceEvent<LexemeType, Symbol>) + Send + Sync + 'static,
+    {
+        self.trace_hook = Some(Box::new(hook));
+    }
+
+    /// Compile the set specifications into a functioning [LexicalAnalyzer].
+    ///
+    /// # Panics
+    ///
+    /// If some lexeme type is associated with a regex accepting the empty string, if intersection
+    /// or complement patterns are used without [DfaConstruction::Derivatives], or if strict
+    /// priorities are set and some lexeme is ambiguous (see
+    /// [LexicalAnalyzerBuilder::set_strict_priorities], which isn't checked with
+    /// [DfaConstruction::Lazy]). Also, if the patterns distinguish more classes of symbols than the
+    /// analyzer's automata support (see [Alphabet]), or if some lexeme type is associated with a
+    /// regex containing both capture groups and intersection or complement patterns (see
+    /// [Regex::capture]).
+    pub fn build(self) -> LexicalAnalyzer<LexemeType, Symbol>
+    where
+        LexemeType: Eq,
+    {
+        let modes = self
+            .modes_lexeme_descriptors
+            .into_iter()
+            .enumerate()
+            .map(|(mode_index, lexeme_descriptors)| {
+                let mode_actions = self
+                    .mode_actions
+                    .iter()
+                    .filter(|(mode, _, _)| *mode == LexerMode(mode_index))
+                    .map(|(_, lexeme_type, action)| (lexeme_type, *action))
+                    .collect();
+                Self::build_mode(
+                    lexeme_descriptors,
+                    mode_actions,
+                    self.dfa_construction,
+                    self.is_strict_priorities,
+                )
+            })
+            .collect();
+
+        LexicalAnalyzer {
+            modes,
+            is_linear_time: self.is_linear_time,
+            end_of_input_lexeme_type: self.end_of_input_lexeme_type,
+            end_of_input_action: self.end_of_input_action,
+            interned_lexeme_types: self.interned_lexeme_types,
+            skipped_lexeme_types: self.skipped_lexeme_types,
+            trace_hook: self.trace_hook,
+        }
+    }
+
+    fn build_mode(
+        lexeme_descriptors: Vec<LexemeDescriptor<LexemeType, Symbol>>,
+        mode_actions: Vec<(&LexemeType, ModeAction)>,
+        dfa_construction: DfaConstruction,
+        is_strict_priorities: bool,
+    ) -> AnalyzerMode<LexemeType, Symbol>
+    where
+        LexemeType: Eq,
+    {
+        // Descriptors are ranked by their priority, so the DFA states are labeled by the minimal
+        // rank they match
+        let mut lexeme_descriptors = lexeme_descriptors;
+        lexeme_descriptors.sort_by_key(|descriptor| Reverse(descriptor.priority));
+
+        let mut lexeme_types = Vec::new();
+        let mut priorities = Vec::new();
+        let mut patterns = Vec::new();
+        let mut nested_region_automata = Vec::new();
+        let mut actions = Vec::new();
+        for descriptor in lexeme_descriptors {
+            nested_region_automata.push(descriptor.closing_pattern.map(|closing_pattern| {
+                Self::build_nested_region_automaton(
+                    descriptor.pattern.clone(),
+                    closing_pattern,
+                    dfa_construction,
+                )
+            }));
+            actions.push(
+                mode_actions
+                    .iter()
+                    .rev()
+                    .find(|(lexeme_type, _)| **lexeme_type == descriptor.lexeme_type)
+                    .map(|(_, action)| *action),
+            );
+            lexeme_types.push(descriptor.lexeme_type);
+            priorities.push(descriptor.priority);
+            patterns.push(descriptor.pattern);
+        }
+
+        let symbol_classes = SymbolClasses::new(&patterns);
+
+        // The captures of nested regions aren't recorded, as their contents extend beyond the
+        // match of their pattern
+        let capture_patterns: Vec<Option<&Regex<Symbol>>> = patterns
+            .iter()
+            .zip(&nested_region_automata)
+            .map(|(pattern, region_automaton)| region_automaton.is_none().then_some(pattern))
+            .collect();
+        let capture_automaton = CaptureAutomaton::build(&capture_patterns, &symbol_classes);
+
+        if is_strict_priorities {
+            Self::check_priority_conflicts(
+                &lexeme_types,
+                &priorities,
+                &patterns,
+                &symbol_classes,
+                dfa_construction,
+            );
+        }
+
+        let automaton = Self::build_automaton(patterns.clone(), symbol_classes, dfa_construction);
+
+        // Make initial state is unlabeled, so we won't get stuck on epsilon when input is exhausted
+        let mut automaton_scan = automaton.scan();
+        let initial_state = automaton_scan.get_initial_state().expect(
+            "Lexical analyzer's DFA should have an initial state, as the associated NFA had one",
+        );
+        if automaton_scan.get_label(initial_state).is_some() {
+            panic!(
+                "Tried to create a lexical analyzer where some lexeme type is associated with a \
+                regex accepting the empty string, which will make it get stuck on input exhaustion"
+            )
+        }
+        drop(automaton_scan);
+
+        AnalyzerMode {
+            automaton,
+            lexeme_types,
+            nested_region_automata,
+            capture_automaton,
+            actions,
+            patterns,
+        }
+    }
+
+    fn build_automaton(
+        patterns: Vec<Regex<Symbol>>,
+        symbol_classes: SymbolClasses<Symbol>,
+        dfa_construction: DfaConstruction,
+    ) -> LexerAutomaton<Symbol> {
+        let dfa = match dfa_construction {
+            DfaConstruction::Eager => LexerDfa::Eager(
+                Self::build_nfa(&patterns, &symbol_classes)
+                    .compile_to_dfa(Self::reduce_labels)
+                    .minimize(),
+            ),
+            DfaConstruction::Lazy { cache_capacity } => LexerDfa::Lazy(
+                Self::build_nfa(&patterns, &symbol_classes)
+                    .into_lazy_dfa(Self::reduce_labels, cache_capacity),
+            ),
+            DfaConstruction::Derivatives => LexerDfa::Eager(
+                build_dfa_by_derivatives(patterns, &symbol_classes, Self::reduce_labels)
+                    .minimize(),
+            ),
+        };
+        LexerAutomaton {
+            dfa,
+            symbol_classes,
+        }
+    }
+
+    // The automaton scanning the contents of a nested region, whose states are labeled by
+    // NESTED_REGION_CLOSING, NESTED_REGION_OPENING, or (for any other symbol) a lower-priority
+    // label
+    fn build_nested_region_automaton(
+        opening_pattern: Regex<Symbol>,
+        closing_pattern: Regex<Symbol>,
+        dfa_construction: DfaConstruction,
+    ) -> LexerAutomaton<Symbol> {
+        // Matching any symbol doesn't distinguish any symbols, so it's matched by the classes of
+        // the other patterns, rather than by listing the entire alphabet
+        let mut patterns = vec![closing_pattern, opening_pattern];
+        let symbol_classes = SymbolClasses::new(&patterns);
+        patterns.push(symbol_classes.any_symbol());
+        Self::build_automaton(patterns, symbol_classes, dfa_construction)
+    }
+
+    // The NFA's accepting states are labeled by the indices of the patterns they match
+    pub(super) fn build_nfa(
+        patterns: &[Regex<Symbol>],
+        symbol_classes: &SymbolClasses<Symbol>,
+    ) -> Nfa<LexerSymbol<Symbol>, usize> {
+        let mut nfa = Nfa::new();
+        let global_start_state = nfa.new_state();
+        nfa.set_initial_state(global_start_state);
+
+        for (index, pattern) in patterns.iter().enumerate() {
+            let (pattern_start_state, pattern_end_state) =
+                pattern.build_into_nfa(&mut nfa, symbol_classes);
+            nfa.link(global_start_state, pattern_start_state, None);
+            nfa.label(pattern_end_state, Some(index));
+        }
+
+        // Feeding an anchor should not discard the states that don't assert it
+        let mut anchors = BTreeSet::new();
+        for pattern in patterns {
+            pattern.collect_anchors(&mut anchors);
+        }
+        let states: Vec<_> = nfa.list_states().collect();
+        for anchor in anchors {
+            for &state in &states {
+                nfa.link(state, state, Some(LexerSymbol::Anchor(anchor).handle()));
+            }
+        }
+
+        nfa
+    }
+
+    // Lower-ranked lexeme descriptors are prioritized
+    pub(super) fn reduce_labels(descriptor_ranks: Vec<&usize>) -> Option<usize> {
+        descriptor_ranks.into_iter().min().copied()
+    }
+
+    // Labels each DFA state with all the descriptors it matches
+    fn collect_labels(descriptor_ranks: Vec<&usize>) -> Option<Vec<usize>> {
+        let mut descriptor_ranks: Vec<usize> = descriptor_ranks.into_iter().copied().collect();
+        descriptor_ranks.sort();
+        Some(descriptor_ranks)
+    }
+
+    fn check_priority_conflicts(
+        lexeme_types: &[LexemeType],
+        priorities: &[i32],
+        patterns: &[Regex<Symbol>],
+        symbol_classes: &SymbolClasses<Symbol>,
+        dfa_construction: DfaConstruction,
+    ) where
+        LexemeType: Eq,
+    {
+        let dfa = match dfa_construction {
+            DfaConstruction::Eager => {
+                Self::build_nfa(patterns, symbol_classes).compile_to_dfa(Self::collect_labels)
+            }
+            DfaConstruction::Derivatives => {
+                build_dfa_by_derivatives(patterns.to_vec(), symbol_classes, Self::collect_labels)
+            }
+            // The check requires constructing every state of the DFA, which is what lazy
+            // construction avoids
+            DfaConstruction::Lazy { .. } => return,
+        };
+
+        for state in dfa.list_states() {
+            let Some(descriptor_ranks) = dfa.get_label(state) else {
+                continue;
+            };
+            let Some(&winner) = descriptor_ranks.first() else {
+                continue;
+            };
+            for &rank in &descriptor_ranks[1..] {
+                if priorities[rank] == priorities[winner]
+                    && lexeme_types[rank] != lexeme_types[winner]
+                {
+                    panic!(
+                        "Tried to create a lexical analyzer with strict priorities, where some \
+                        lexeme is matched by lexeme descriptors of different lexeme types, but of \
+                        the same priority ({})",
+                        priorities[winner],
+                    )
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,525 @@
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use crate::lex::{Alphabet, Regex};
+use crate::lex::regex::Anchor;
+use crate::lex::symbol_classes::SymbolClasses;
+
+/// The ranges of a lexeme's symbols (bytes, for text) matched by the named capture groups of its
+/// pattern (see [Regex::capture]).
+///
+/// Captures are reported by
+/// [LexicalAnalyzer::analyze_captured](crate::lex::LexicalAnalyzer::analyze_captured). Groups
+/// that did not participate in the match are absent, and captures are not recorded for nested
+/// regions (see [LexemeDescriptor::nested_region](crate::lex::LexemeDescriptor::nested_region)).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Captures {
+    ranges: BTreeMap<String, Range<usize>>,
+}
+
+impl Captures {
+    /// Gets the range of the lexeme's symbols matched by the capture group named `name`, if it
+    /// participated in the match.
+    pub fn get(&self, name: &str) -> Option<Range<usize>> {
+        self.ranges.get(name).cloned()
+    }
+
+    /// Iterates over the names of the groups that participated in the match, along with the
+    /// ranges they matched, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.ranges
+            .iter()
+            .map(|(name, range)| (name.as_str(), range.clone()))
+    }
+}
+
+enum Instruction {
+    // Consume a symbol of the specified class
+    Class(usize),
+
+    Assert(Anchor),
+
+    // Continue at both targets, prioritizing the first
+    Split(usize, usize),
+
+    Jump(usize),
+
+    // Record the current position in the specified slot
+    Save(usize),
+
+    Fail,
+
+    // The pattern of the specified descriptor was matched
+    Match(usize),
+}
+
+/// A tagged DFA, that records the boundaries of the named capture groups of a mode's patterns while
+/// the mode's automaton scans the input.
+///
+/// The patterns that contain capture groups are compiled into a single tagged NFA, whose threads
+/// carry the positions of the tags (group boundaries) they passed. Threads are prioritized so that
+/// unions prefer their earlier options, and repetitions are greedy. The NFA is determinized when
+/// the analyzer is built: each state stands for the prioritized list of the threads at some
+/// position, and each transition lists the thread of its source that each thread of its target
+/// continues, along with the tags it passed. So scanning a symbol only copies and sets the threads'
+/// tags. Only the modes with patterns that contain captures pay for this.
+pub(super) struct CaptureAutomaton<Symbol> {
+    states: Vec<TaggedState>,
+    initial_transition: TaggedTransition,
+
+    // By the indices of the mode's descriptors, the names of their capture groups, along with the
+    // first of the pair of slots that record each group's boundaries
+    groups: Vec<Vec<(String, usize)>>,
+
+    slots_count: usize,
+    phantom: PhantomData<Symbol>,
+}
+
+struct TaggedState {
+    // Missing anchor transitions leave the state as is, while missing transitions by classes of
+    // symbols lead to no state
+    transitions: HashMap<TaggedInput, TaggedTransition>,
+
+    // The descriptors whose patterns were matched, along with their first matching thread
+    matches: Vec<(usize, usize)>,
+}
+
+// What the automaton is fed with: the classes of the input's symbols, or anchors whose assertions
+// hold at the current position
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum TaggedInput {
+    Class(usize),
+    Anchor(Anchor),
+}
+
+struct TaggedTransition {
+    target: usize,
+
+    // By priority, the origins of the target's threads
+    threads: Vec<ThreadOrigin>,
+}
+
+#[derive(Clone)]
+struct ThreadOrigin {
+    // The thread of the transition's source whose tags are copied (absent for new threads)
+    source: Option<usize>,
+
+    // The slots that record the current position
+    saved_slots: Vec<usize>,
+}
+
+impl<Symbol> CaptureAutomaton<Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Builds the automaton that records the captures of `patterns` (indexed as the mode's
+    /// descriptors, with the patterns whose captures aren't recorded absent), unless none of them
+    /// contain captures.
+    ///
+    /// # Panics
+    ///
+    /// If some pattern contains both captures and intersection or complement patterns.
+    pub(super) fn build(
+        patterns: &[Option<&Regex<Symbol>>],
+        symbol_classes: &SymbolClasses<Symbol>,
+    ) -> Option<Self> {
+        let mut compiler = ProgramCompiler {
+            instructions: Vec::new(),
+            symbol_classes,
+            groups: Vec::new(),
+            slots_count: 0,
+        };
+        let mut starts = Vec::new();
+        for (descriptor_index, pattern) in patterns.iter().enumerate() {
+            compiler.groups.push(Vec::new());
+            if let Some(pattern) = pattern.filter(|pattern| pattern.has_captures()) {
+                starts.push(compiler.instructions.len());
+                compiler.compile_pattern(pattern, descriptor_index);
+                compiler
+                    .instructions
+                    .push(Instruction::Match(descriptor_index));
+            }
+        }
+        if starts.is_empty() {
+            return None;
+        }
+
+        let mut determinizer = Determinizer {
+            instructions: &compiler.instructions,
+            states: Vec::new(),
+            state_indices: HashMap::new(),
+            unprocessed_states: Vec::new(),
+        };
+        let mut threads = ThreadList::new(&compiler.instructions);
+        for start in starts {
+            let origin = ThreadOrigin {
+                source: None,
+                saved_slots: Vec::new(),
+            };
+            determinizer.add_thread(&mut threads, start, AnchorSet::default(), origin);
+        }
+        let initial_transition = determinizer.install_transition(AnchorSet::default(), threads);
+        while let Some((key, state)) = determinizer.unprocessed_states.pop() {
+            determinizer.process_state(key, state);
+        }
+
+        Some(Self {
+            states: determinizer.states,
+            initial_transition,
+            groups: compiler.groups,
+            slots_count: compiler.slots_count,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Gets the captures of the descriptor's pattern, from the tags recorded when it was matched
+    /// (see [CaptureScan::record]).
+    pub(super) fn captures(&self, descriptor_index: usize, tags: &[Option<usize>]) -> Captures {
+        let mut ranges = BTreeMap::new();
+        if !tags.is_empty() {
+            for (name, slot) in &self.groups[descriptor_index] {
+                if let (Some(start), Some(end)) = (tags[*slot], tags[slot + 1]) {
+                    ranges.insert(name.clone(), start..end);
+                }
+            }
+        }
+        Captures { ranges }
+    }
+}
+
+/// The progress of a [CaptureAutomaton] along a scan of the input, which is fed with the same
+/// symbols as the scanning automaton.
+pub(super) struct CaptureScan<'a, Symbol> {
+    automaton: &'a CaptureAutomaton<Symbol>,
+    symbol_classes: &'a SymbolClasses<Symbol>,
+
+    // Absent once no pattern with captures can match
+    state: Option<usize>,
+
+    // The slots of the state's threads, by priority, where each thread has all the slots
+    registers: Vec<Option<usize>>,
+    next_registers: Vec<Option<usize>>,
+}
+
+impl<'a, Symbol> CaptureScan<'a, Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) fn new(
+        automaton: &'a CaptureAutomaton<Symbol>,
+        symbol_classes: &'a SymbolClasses<Symbol>,
+    ) -> Self {
+        let mut scan = Self {
+            automaton,
+            symbol_classes,
+            state: None,
+            registers: Vec::new(),
+            next_registers: Vec::new(),
+        };
+        scan.apply(&automaton.initial_transition, 0);
+        scan
+    }
+
+    /// Feeds the automaton with the next symbol of the input, which ends at `position`.
+    pub(super) fn step(&mut self, symbol: Symbol, position: usize) {
+        let class = self.symbol_classes.classify(symbol);
+        if let Some(state) = self.state {
+            let input = TaggedInput::Class(class.index());
+            match self.automaton.states[state].transitions.get(&input) {
+                Some(transition) => self.apply(transition, position),
+                None => self.state = None,
+            }
+        }
+    }
+
+    /// Feeds the automaton with an anchor whose assertion holds at `position`.
+    pub(super) fn feed(&mut self, anchor: Anchor, position: usize) {
+        if let Some(state) = self.state {
+            let input = TaggedInput::Anchor(anchor);
+            if let Some(transition) = self.automaton.states[state].transitions.get(&input) {
+                self.apply(transition, position);
+            }
+        }
+    }
+
+    fn apply(&mut self, transition: &TaggedTransition, position: usize) {
+        let slots_count = self.automaton.slots_count;
+        self.next_registers.clear();
+        for origin in &transition.threads {
+            match origin.source {
+                Some(source) => self.next_registers.extend_from_slice(
+                    &self.registers[source * slots_count..(source + 1) * slots_count],
+                ),
+                None => self
+                    .next_registers
+                    .extend(std::iter::repeat(None).take(slots_count)),
+            }
+            let thread_start = self.next_registers.len() - slots_count;
+            for slot in &origin.saved_slots {
+                self.next_registers[thread_start + slot] = Some(position);
+            }
+        }
+        std::mem::swap(&mut self.registers, &mut self.next_registers);
+        self.state = Some(transition.target);
+    }
+
+    /// Records the tags of the descriptor's pattern into `tags`, if it matches the input scanned
+    /// so far, and clears them otherwise.
+    pub(super) fn record(&self, descriptor_index: usize, tags: &mut Vec<Option<usize>>) {
+        tags.clear();
+        let Some(state) = self.state else {
+            return;
+        };
+        let slots_count = self.automaton.slots_count;
+        if let Some((_, thread)) = self.automaton.states[state]
+            .matches
+            .iter()
+            .find(|(matched_descriptor, _)| *matched_descriptor == descriptor_index)
+        {
+            tags.extend_from_slice(
+                &self.registers[thread * slots_count..(thread + 1) * slots_count],
+            );
+        }
+    }
+}
+
+struct ProgramCompiler<'a, Symbol> {
+    instructions: Vec<Instruction>,
+    symbol_classes: &'a SymbolClasses<Symbol>,
+    groups: Vec<Vec<(String, usize)>>,
+    slots_count: usize,
+}
+
+impl<Symbol> ProgramCompiler<'_, Symbol>
+where
+    Symbol: Alphabet,
+{
+    fn compile_pattern(&mut self, pattern: &Regex<Symbol>, descriptor_index: usize) {
+        match pattern {
+            Regex::SingleCharacter { value } => {
+                let class = self.symbol_classes.classify(*value);
+                self.instructions.push(Instruction::Class(class.index()))
+            }
+            Regex::Anchor { anchor } => self.instructions.push(Instruction::Assert(*anchor)),
+            Regex::Concat { parts } => {
+                for part in parts {
+                    self.compile_pattern(part, descriptor_index);
+                }
+            }
+            Regex::Union { options } => match options.split_last() {
+                None => self.instructions.push(Instruction::Fail),
+                Some((last_option, prioritized_options)) => {
+                    let mut jumps_to_end = Vec::new();
+                    for option in prioritized_options {
+                        let split = self.placeholder();
+                        self.compile_pattern(option, descriptor_index);
+                        jumps_to_end.push(self.placeholder());
+                        self.instructions[split] = Instruction::Split(split + 1, self.next());
+                    }
+                    self.compile_pattern(last_option, descriptor_index);
+                    for jump in jumps_to_end {
+                        self.instructions[jump] = Instruction::Jump(self.next());
+                    }
+                }
+            },
+            Regex::Star { repeated_pattern } => {
+                let split = self.placeholder();
+                self.compile_pattern(repeated_pattern, descriptor_index);
+                self.instructions.push(Instruction::Jump(split));
+                self.instructions[split] = Instruction::Split(split + 1, self.next());
+            }
+            Regex::Capture { name, pattern } => {
+                let slot = self.slots_count;
+                self.slots_count += 2;
+                self.groups[descriptor_index].push((name.clone(), slot));
+                self.instructions.push(Instruction::Save(slot));
+                self.compile_pattern(pattern, descriptor_index);
+                self.instructions.push(Instruction::Save(slot + 1));
+            }
+            Regex::Intersection { .. } | Regex::Complement { .. } => panic!(
+                "Tried to create a lexical analyzer where some lexeme type is associated with a \
+                regex containing both capture groups and intersection or complement patterns, \
+                whose captures cannot be recorded"
+            ),
+        }
+    }
+
+    fn placeholder(&mut self) -> usize {
+        self.instructions.push(Instruction::Fail);
+        self.instructions.len() - 1
+    }
+
+    fn next(&self) -> usize {
+        self.instructions.len()
+    }
+}
+
+// The anchors whose assertions hold at some position, by their serials
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct AnchorSet(u8);
+
+impl AnchorSet {
+    fn contains(self, anchor: Anchor) -> bool {
+        self.0 & (1 << anchor as u8) != 0
+    }
+
+    fn with(self, anchor: Anchor) -> Self {
+        Self(self.0 | (1 << anchor as u8))
+    }
+}
+
+// A state of the tagged DFA: the anchors that hold at its position, along with the instructions
+// that its threads wait at, by priority
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct StateKey {
+    held_anchors: AnchorSet,
+    pcs: Vec<usize>,
+}
+
+struct Determinizer<'a> {
+    instructions: &'a [Instruction],
+    states: Vec<TaggedState>,
+    state_indices: HashMap<StateKey, usize>,
+    unprocessed_states: Vec<(StateKey, usize)>,
+}
+
+impl Determinizer<'_> {
+    fn process_state(&mut self, key: StateKey, state: usize) {
+        let instructions = self.instructions;
+        let mut classes: Vec<usize> = key
+            .pcs
+            .iter()
+            .filter_map(|&pc| match instructions[pc] {
+                Instruction::Class(class) => Some(class),
+                _ => None,
+            })
+            .collect();
+        classes.sort_unstable();
+        classes.dedup();
+
+        // Threads either consume the symbol's class, or are dropped
+        for class in classes {
+            let mut threads = ThreadList::new(instructions);
+            for (source, &pc) in key.pcs.iter().enumerate() {
+                if let Instruction::Class(thread_class) = instructions[pc] {
+                    if thread_class == class {
+                        let origin = ThreadOrigin {
+                            source: Some(source),
+                            saved_slots: Vec::new(),
+                        };
+                        self.add_thread(&mut threads, pc + 1, AnchorSet::default(), origin);
+                    }
+                }
+            }
+            let transition = self.install_transition(AnchorSet::default(), threads);
+            self.states[state]
+                .transitions
+                .insert(TaggedInput::Class(class), transition);
+        }
+
+        // Threads that wait for an anchor proceed once it holds, and the rest are kept
+        for anchor in [Anchor::LineStart, Anchor::LineEnd, Anchor::InputEnd] {
+            if key.held_anchors.contains(anchor) {
+                continue;
+            }
+            let held_anchors = key.held_anchors.with(anchor);
+            let mut threads = ThreadList::new(instructions);
+            for (source, &pc) in key.pcs.iter().enumerate() {
+                let origin = ThreadOrigin {
+                    source: Some(source),
+                    saved_slots: Vec::new(),
+                };
+                self.add_thread(&mut threads, pc, held_anchors, origin);
+            }
+            let transition = self.install_transition(held_anchors, threads);
+            self.states[state]
+                .transitions
+                .insert(TaggedInput::Anchor(anchor), transition);
+        }
+    }
+
+    // Follows the non-consuming instructions from `pc`, and adds the reached threads by priority
+    fn add_thread(
+        &self,
+        threads: &mut ThreadList,
+        pc: usize,
+        held_anchors: AnchorSet,
+        mut origin: ThreadOrigin,
+    ) {
+        if threads.visited[pc] {
+            return;
+        }
+        threads.visited[pc] = true;
+        match self.instructions[pc] {
+            Instruction::Class(_) | Instruction::Match(_) => threads.threads.push((pc, origin)),
+            Instruction::Fail => {}
+            Instruction::Jump(target) => self.add_thread(threads, target, held_anchors, origin),
+            Instruction::Split(first, second) => {
+                self.add_thread(threads, first, held_anchors, origin.clone());
+                self.add_thread(threads, second, held_anchors, origin);
+            }
+            Instruction::Save(slot) => {
+                origin.saved_slots.push(slot);
+                self.add_thread(threads, pc + 1, held_anchors, origin);
+            }
+            Instruction::Assert(anchor) => {
+                if held_anchors.contains(anchor) {
+                    self.add_thread(threads, pc + 1, held_anchors, origin);
+                } else {
+                    // The thread waits until the anchor holds, or a symbol is consumed
+                    threads.threads.push((pc, origin));
+                }
+            }
+        }
+    }
+
+    // Creates the transition into the state of the specified threads, which is installed if it's
+    // new
+    fn install_transition(
+        &mut self,
+        held_anchors: AnchorSet,
+        threads: ThreadList,
+    ) -> TaggedTransition {
+        let (pcs, threads): (Vec<usize>, Vec<ThreadOrigin>) = threads.threads.into_iter().unzip();
+        let key = StateKey { held_anchors, pcs };
+        let target = match self.state_indices.get(&key) {
+            Some(&state) => state,
+            None => {
+                let mut matches = Vec::new();
+                for (thread, &pc) in key.pcs.iter().enumerate() {
+                    if let Instruction::Match(descriptor_index) = self.instructions[pc] {
+                        matches.push((descriptor_index, thread));
+                    }
+                }
+                self.states.push(TaggedState {
+                    transitions: HashMap::new(),
+                    matches,
+                });
+                let state = self.states.len() - 1;
+                self.state_indices.insert(key.clone(), state);
+                self.unprocessed_states.push((key, state));
+                state
+            }
+        };
+        TaggedTransition { target, threads }
+    }
+}
+
+// The threads of the NFA at some position, ordered by priority
+struct ThreadList {
+    // The instruction each thread waits at, along with its origin
+    threads: Vec<(usize, ThreadOrigin)>,
+
+    // Whether each instruction was already reached by a higher-priority thread
+    visited: Vec<bool>,
+}
+
+impl ThreadList {
+    fn new(instructions: &[Instruction]) -> Self {
+        Self {
+            threads: Vec::new(),
+            visited: vec![false; instructions.len()],
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::automata::dfa::{Dfa, DfaState};
+use crate::handles::Handle;
+use crate::handles::specials::AutomaticallyHandled;
+use crate::lex::{Alphabet, Regex};
+use crate::lex::automaton::LexerSymbol;
+use crate::lex::regex::Anchor;
+use crate::lex::symbol_classes::{SymbolClass, SymbolClasses};
+
+// Smart constructors, used to keep derivatives in a canonical form. This guarantees that a regex
+// has only finitely many distinct derivatives, so their DFA is finite.
+impl<Symbol> Regex<Symbol>
+where
+    Symbol: Alphabet,
+{
+    fn nothing() -> Regex<Symbol> {
+        Regex::union(vec![])
+    }
+
+    fn is_nothing(&self) -> bool {
+        matches!(self, Regex::Union { options } if options.is_empty())
+    }
+
+    fn is_epsilon(&self) -> bool {
+        matches!(self, Regex::Concat { parts } if parts.is_empty())
+    }
+
+    fn canonical_union(options: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        let mut flattened_options = BTreeSet::new();
+        for option in options {
+            match option {
+                Regex::Union { options } => flattened_options.extend(options),
+                option => {
+                    flattened_options.insert(option);
+                }
+            }
+        }
+        Self::collapse_singleton(flattened_options.into_iter().collect(), Regex::union)
+    }
+
+    fn canonical_concat(parts: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        let mut flattened_parts = Vec::new();
+        for part in parts {
+            match part {
+                part if part.is_nothing() => return Regex::nothing(),
+                Regex::Concat { parts } => flattened_parts.extend(parts),
+                part => flattened_parts.push(part),
+            }
+        }
+        Self::collapse_singleton(flattened_parts, Regex::concat)
+    }
+
+    fn canonical_star(repeated_pattern: Regex<Symbol>) -> Regex<Symbol> {
+        match repeated_pattern {
+            pattern if pattern.is_nothing() || pattern.is_epsilon() => Regex::epsilon(),
+            pattern @ Regex::Star { .. } => pattern,
+            pattern => Regex::star_from(pattern),
+        }
+    }
+
+    fn canonical_intersection(parts: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        let mut flattened_parts = BTreeSet::new();
+        for part in parts {
+            match part {
+                part if part.is_nothing() => return Regex::nothing(),
+                Regex::Intersection { parts } => flattened_parts.extend(parts),
+                part => {
+                    flattened_parts.insert(part);
+                }
+            }
+        }
+        Self::collapse_singleton(flattened_parts.into_iter().collect(), Regex::intersection)
+    }
+
+    fn canonical_complement(complemented_pattern: Regex<Symbol>) -> Regex<Symbol> {
+        match complemented_pattern {
+            Regex::Complement {
+                complemented_pattern,
+            } => *complemented_pattern,
+            pattern => Regex::complement(pattern),
+        }
+    }
+
+    fn collapse_singleton(
+        mut regexes: Vec<Regex<Symbol>>,
+        compose: fn(Vec<Regex<Symbol>>) -> Regex<Symbol>,
+    ) -> Regex<Symbol> {
+        if regexes.len() == 1 {
+            regexes
+                .pop()
+                .expect("Vector with a single regex should not be empty")
+        } else {
+            compose(regexes)
+        }
+    }
+
+    /// Checks whether the pattern matches the empty sequence.
+    pub(super) fn is_nullable(&self) -> bool {
+        match self {
+            Regex::SingleCharacter { .. } | Regex::Anchor { .. } => false,
+            Regex::Union { options } => options.iter().any(Regex::is_nullable),
+            Regex::Concat { parts } => parts.iter().all(Regex::is_nullable),
+            Regex::Star { .. } => true,
+            Regex::Intersection { parts } => parts.iter().all(Regex::is_nullable),
+            Regex::Complement {
+                complemented_pattern,
+            } => !complemented_pattern.is_nullable(),
+            Regex::Capture { pattern, .. } => pattern.is_nullable(),
+        }
+    }
+
+    /// Calculates the pattern's Brzozowski derivative by `symbol`: a pattern that matches the
+    /// sequences `s` such that the original pattern matches `symbol` followed by `s`.
+    pub(super) fn derivative(&self, symbol: Symbol) -> Regex<Symbol> {
+        match self {
+            Regex::SingleCharacter { value } => {
+                if *value == symbol {
+                    Regex::epsilon()
+                } else {
+                    Regex::nothing()
+                }
+            }
+            Regex::Anchor { .. } => Regex::nothing(),
+            Regex::Union { options } => Self::canonical_union(
+                options
+                    .iter()
+                    .map(|option| option.derivative(symbol))
+                    .collect(),
+            ),
+            Regex::Concat { parts } => match parts.split_first() {
+                None => Regex::nothing(),
+                Some((first, rest)) => {
+                    let rest = Self::canonical_concat(rest.to_vec());
+                    let first_consumes_symbol =
+                        Self::canonical_concat(vec![first.derivative(symbol), rest.clone()]);
+                    if first.is_nullable() {
+                        Self::canonical_union(vec![first_consumes_symbol, rest.derivative(symbol)])
+                    } else {
+                        first_consumes_symbol
+                    }
+                }
+            },
+            Regex::Star { repeated_pattern } => Self::canonical_concat(vec![
+                repeated_pattern.derivative(symbol),
+                Self::canonical_star((**repeated_pattern).clone()),
+            ]),
+            Regex::Intersection { parts } => Self::canonical_intersection(
+                parts.iter().map(|part| part.derivative(symbol)).collect(),
+            ),
+            Regex::Complement {
+                complemented_pattern,
+            } => Self::canonical_complement(complemented_pattern.derivative(symbol)),
+            Regex::Capture { pattern, .. } => pattern.derivative(symbol),
+        }
+    }
+
+    /// Calculates the pattern's derivative by a virtual symbol fed at a position where `anchor`
+    /// holds: a pattern that matches the sequences `s` such that the original pattern matches `s`,
+    /// either asserting `anchor` at its beginning or not asserting anything there.
+    pub(super) fn anchor_derivative(&self, anchor: Anchor) -> Regex<Symbol> {
+        match self {
+            Regex::SingleCharacter { .. } => self.clone(),
+            Regex::Anchor {
+                anchor: asserted_anchor,
+            } => {
+                if *asserted_anchor == anchor {
+                    Self::canonical_union(vec![self.clone(), Regex::epsilon()])
+                } else {
+                    self.clone()
+                }
+            }
+            Regex::Union { options } => Self::canonical_union(
+                options
+                    .iter()
+                    .map(|option| option.anchor_derivative(anchor))
+                    .collect(),
+            ),
+            Regex::Concat { parts } => match parts.split_first() {
+                None => Regex::epsilon(),
+                Some((first, rest)) => {
+                    let rest = Self::canonical_concat(rest.to_vec());
+                    let first_asserts_anchor = Self::canonical_concat(vec![
+                        first.anchor_derivative(anchor),
+                        rest.clone(),
+                    ]);
+                    if first.is_nullable() {
+                        Self::canonical_union(vec![
+                            first_asserts_anchor,
+                            rest.anchor_derivative(anchor),
+                        ])
+                    } else {
+                        first_asserts_anchor
+                    }
+                }
+            },
+            Regex::Star { repeated_pattern } => Self::canonical_union(vec![
+                Regex::epsilon(),
+                Self::canonical_concat(vec![
+                    repeated_pattern.anchor_derivative(anchor),
+                    Self::canonical_star((**repeated_pattern).clone()),
+                ]),
+            ]),
+            Regex::Intersection { parts } => Self::canonical_intersection(
+                parts
+                    .iter()
+                    .map(|part| part.anchor_derivative(anchor))
+                    .collect(),
+            ),
+            Regex::Complement {
+                complemented_pattern,
+            } => Self::canonical_complement(complemented_pattern.anchor_derivative(anchor)),
+            Regex::Capture { pattern, .. } => pattern.anchor_derivative(anchor),
+        }
+    }
+}
+
+/// Compiles a list of patterns directly into a DFA, whose states are the tuples of the patterns'
+/// derivatives, and whose transitions consume the symbols' classes.
+///
+/// Each state is labeled by applying `label_reduction` to the indices of the patterns whose
+/// derivatives in the tuple are nullable.
+pub(super) fn build_dfa_by_derivatives<Symbol, Label>(
+    patterns: Vec<Regex<Symbol>>,
+    symbol_classes: &SymbolClasses<Symbol>,
+    label_reduction: fn(Vec<&usize>) -> Option<Label>,
+) -> Dfa<LexerSymbol<Symbol>, Label>
+where
+    Symbol: Alphabet,
+{
+    DerivativesDfaBuilder::new(patterns, symbol_classes, label_reduction).build()
+}
+
+type DerivativesDfaState<Symbol, Label> = Handle<DfaState<LexerSymbol<Symbol>, Label>>;
+
+// The tuple of the patterns' derivatives that a DFA state stands for
+type Derivatives<Symbol> = Vec<Regex<Symbol>>;
+
+struct DerivativesDfaBuilder<'a, Symbol, Label>
+where
+    Symbol: Alphabet,
+{
+    dfa: Dfa<LexerSymbol<Symbol>, Label>,
+    label_reduction: fn(Vec<&usize>) -> Option<Label>,
+    dfa_states_map: HashMap<Derivatives<Symbol>, DerivativesDfaState<Symbol, Label>>,
+    unprocessed_new_states: Vec<(Derivatives<Symbol>, DerivativesDfaState<Symbol, Label>)>,
+
+    // The classes of symbols consumed by the DFA
+    symbol_classes: &'a SymbolClasses<Symbol>,
+
+    // Anchors that appear in the patterns, by which the DFA should have transitions
+    mentioned_anchors: Vec<Anchor>,
+}
+
+impl<'a, Symbol, Label> DerivativesDfaBuilder<'a, Symbol, Label>
+where
+    Symbol: Alphabet,
+{
+    fn new(
+        patterns: Vec<Regex<Symbol>>,
+        symbol_classes: &'a SymbolClasses<Symbol>,
+        label_reduction: fn(Vec<&usize>) -> Option<Label>,
+    ) -> Self {
+        let mut mentioned_anchors = BTreeSet::new();
+        for pattern in &patterns {
+            pattern.collect_anchors(&mut mentioned_anchors);
+        }
+
+        let mut builder = Self {
+            dfa: Dfa::new(),
+            label_reduction,
+            dfa_states_map: HashMap::new(),
+            unprocessed_new_states: Vec::new(),
+            symbol_classes,
+            mentioned_anchors: mentioned_anchors.into_iter().collect(),
+        };
+        let initial_state = builder.install_new_state(patterns);
+        builder.dfa.set_initial_state(initial_state);
+        builder
+    }
+
+    fn build(mut self) -> Dfa<LexerSymbol<Symbol>, Label> {
+        while let Some((derivatives, dfa_state)) = self.unprocessed_new_states.pop() {
+            self.process_new_state(derivatives, dfa_state);
+        }
+        self.dfa
+    }
+
+    fn install_new_state(
+        &mut self,
+        derivatives: Derivatives<Symbol>,
+    ) -> DerivativesDfaState<Symbol, Label> {
+        let dfa_state = self.dfa.new_state();
+        let nullable_indices: Vec<usize> = (0..derivatives.len())
+            .filter(|&index| derivatives[index].is_nullable())
+            .collect();
+        let label = (self.label_reduction)(nullable_indices.iter().collect());
+        self.dfa.label(dfa_state, label);
+        self.dfa_states_map.insert(derivatives.clone(), dfa_state);
+        self.unprocessed_new_states.push((derivatives, dfa_state));
+        dfa_state
+    }
+
+    fn process_new_state(
+        &mut self,
+        derivatives: Derivatives<Symbol>,
+        dfa_state: DerivativesDfaState<Symbol, Label>,
+    ) {
+        // All symbols of a class have the same derivatives, so we only calculate them once - by
+        // some representative symbol of the class (unless it's empty)
+        for class_index in 0..self.symbol_classes.count() {
+            let class = SymbolClass::from_index(class_index);
+            let Some(representative) = self.symbol_classes.get_representative(class) else {
+                continue;
+            };
+            let target_derivatives = derivatives
+                .iter()
+                .map(|derivative| derivative.derivative(representative))
+                .collect();
+            if let Some(target_dfa_state) = self.locate_target_state(target_derivatives) {
+                self.dfa
+                    .link(dfa_state, target_dfa_state, LexerSymbol::Input(class).handle());
+            }
+        }
+
+        for anchor in self.mentioned_anchors.clone() {
+            let target_derivatives = derivatives
+                .iter()
+                .map(|derivative| derivative.anchor_derivative(anchor))
+                .collect();
+            if let Some(target_dfa_state) = self.locate_target_state(target_derivatives) {
+                self.dfa
+                    .link(dfa_state, target_dfa_state, LexerSymbol::Anchor(anchor).handle());
+            }
+        }
+    }
+
+    // Returns None if the target state is trivially dead
+    fn locate_target_state(
+        &mut self,
+        target_derivatives: Derivatives<Symbol>,
+    ) -> Option<DerivativesDfaState<Symbol, Label>> {
+        if target_derivatives.iter().all(Regex::is_nothing) {
+            None
+        } else if let Some(&target_dfa_state) = self.dfa_states_map.get(&target_derivatives) {
+            Some(target_dfa_state)
+        } else {
+            Some(self.install_new_state(target_derivatives))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reduce_labels(indices: Vec<&usize>) -> Option<usize> {
+        indices.into_iter().min().copied()
+    }
+
+    // The DFA consumes the classes of the symbols
+    type ClassifiedDfa = (Dfa<LexerSymbol<u8>, usize>, SymbolClasses<u8>);
+
+    fn create_dfa_for_regex(pattern: Regex) -> ClassifiedDfa {
+        let symbol_classes = SymbolClasses::new(&[pattern.clone()]);
+        let dfa = build_dfa_by_derivatives(vec![pattern], &symbol_classes, reduce_labels);
+        (dfa.minimize(), symbol_classes)
+    }
+
+    fn is_string_in((dfa, symbol_classes): &ClassifiedDfa, data: &str) -> bool {
+        let classes = data.bytes().map(|x| symbol_classes.classify(x));
+        match dfa.scan(classes.map(|class| LexerSymbol::Input(class).handle())) {
+            None => false,
+            Some(end_state) => dfa.get_label(end_state).is_some(),
+        }
+    }
+
+    fn identifier_regex() -> Regex {
+        Regex::concat(vec![
+            Regex::union(vec![
+                Regex::character_range('a', 'z'),
+                Regex::single_char('_'),
+            ]),
+            Regex::star_from(Regex::union(vec![
+                Regex::character_range('a', 'z'),
+                Regex::character_range('0', '9'),
+                Regex::single_char('_'),
+            ])),
+        ])
+    }
+
+    #[test]
+    fn test_complex() {
+        let dfa = create_dfa_for_regex(identifier_regex());
+
+        assert_eq!(is_string_in(&dfa, "our_thing_12"), true);
+        assert_eq!(is_string_in(&dfa, "i"), true);
+        assert_eq!(is_string_in(&dfa, ""), false);
+        assert_eq!(is_string_in(&dfa, "mine()"), false);
+        assert_eq!(is_string_in(&dfa, "1ours"), false);
+    }
+
+    #[test]
+    fn test_intersection() {
+        // Identifiers of exactly 3 characters
+        let any_byte = Regex::union(
+            (u8::MIN..=u8::MAX)
+                .map(|value| Regex::SingleCharacter { value })
+                .collect(),
+        );
+        let pattern = Regex::intersection(vec![
+            identifier_regex(),
+            Regex::concat(vec![any_byte.clone(), any_byte.clone(), any_byte]),
+        ]);
+        let dfa = create_dfa_for_regex(pattern);
+
+        assert_eq!(is_string_in(&dfa, "abc"), true);
+        assert_eq!(is_string_in(&dfa, "a_1"), true);
+        assert_eq!(is_string_in(&dfa, "ab"), false);
+        assert_eq!(is_string_in(&dfa, "abcd"), false);
+        assert_eq!(is_string_in(&dfa, "1bc"), false);
+    }
+
+    #[test]
+    fn test_complement() {
+        let anything = Regex::complement(Regex::nothing());
+        let comment = Regex::concat(vec![
+            Regex::constant_string("/*"),
+            Regex::complement(Regex::concat(vec![
+                anything.clone(),
+                Regex::constant_string("*/"),
+                anything,
+            ])),
+            Regex::constant_string("*/"),
+        ]);
+        let dfa = create_dfa_for_regex(comment);
+
+        assert_eq!(is_string_in(&dfa, "/**/"), true);
+        assert_eq!(is_string_in(&dfa, "/* a * comment / */"), true);
+        assert_eq!(is_string_in(&dfa, "/***/"), true);
+        assert_eq!(is_string_in(&dfa, "/* a */ b */"), false);
+        assert_eq!(is_string_in(&dfa, "/* a"), false);
+        assert_eq!(is_string_in(&dfa, "/*/"), false);
+    }
+
+    #[test]
+    fn test_labels_prioritize_earlier_patterns() {
+        let patterns = vec![Regex::constant_string("if"), identifier_regex()];
+        let symbol_classes = SymbolClasses::new(&patterns);
+        let dfa = build_dfa_by_derivatives(patterns, &symbol_classes, reduce_labels).minimize();
+        let label_of = |data: &str| {
+            let classes = data.bytes().map(|x| symbol_classes.classify(x));
+            *dfa.get_label(
+                dfa.scan(classes.map(|class| LexerSymbol::Input(class).handle()))
+                    .unwrap(),
+            )
+        };
+
+        assert_eq!(label_of("if"), Some(0));
+        assert_eq!(label_of("iff"), Some(1));
+        assert_eq!(label_of("i"), Some(1));
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write};
+
+use crate::automata::dfa::Dfa;
+use crate::automata::nfa::Nfa;
+use crate::lex::Alphabet;
+use crate::lex::automaton::LexerSymbol;
+use crate::lex::regex::Anchor;
+use crate::lex::symbol_classes::SymbolClasses;
+
+// Input symbols of consecutive serials are compacted into a range, once there are this many
+const MIN_RANGE_LENGTH: usize = 3;
+
+/// An automaton of a lexical analyzer, as drawn in a graph, where states are identified by their
+/// indices.
+struct AutomatonGraph {
+    states_count: usize,
+    initial_state: Option<usize>,
+
+    // By the states' indices, describing the lexeme types they match
+    accepting_states: BTreeMap<usize, String>,
+
+    // The serials of the symbols (anchors and classes of input symbols) by which each state moves
+    // to each other state, where epsilon transitions have no serial
+    transitions: BTreeMap<(usize, usize), Vec<Option<usize>>>,
+}
+
+pub(super) fn nfa_to_dot<Symbol, LexemeType>(
+    nfa: &Nfa<LexerSymbol<Symbol>, usize>,
+    symbol_classes: &SymbolClasses<Symbol>,
+    lexeme_types: &[LexemeType],
+) -> String
+where
+    Symbol: Alphabet,
+    LexemeType: Debug,
+{
+    let mut graph = AutomatonGraph::new(nfa.get_initial_state().map(Into::into));
+    for state in nfa.list_states() {
+        let src = state.into();
+        graph.add_state(src, nfa.get_label(state).map(|index| &lexeme_types[index]));
+        for (symbol, dst) in nfa.list_transitions(state) {
+            graph.add_transition(src, dst.into(), symbol.map(Into::into));
+        }
+    }
+    graph.render(symbol_classes)
+}
+
+pub(super) fn dfa_to_dot<Symbol, LexemeType>(
+    dfa: &Dfa<LexerSymbol<Symbol>, usize>,
+    symbol_classes: &SymbolClasses<Symbol>,
+    lexeme_types: &[LexemeType],
+) -> String
+where
+    Symbol: Alphabet,
+    LexemeType: Debug,
+{
+    let mut graph = AutomatonGraph::new(dfa.get_initial_state().map(Into::into));
+    for state in dfa.list_states() {
+        let src = state.into();
+        graph.add_state(src, dfa.get_label(state).map(|index| &lexeme_types[index]));
+        for (symbol, dst) in dfa.list_transitions(state) {
+            graph.add_transition(src, dst.into(), Some(symbol.into()));
+        }
+    }
+    graph.render(symbol_classes)
+}
+
+impl AutomatonGraph {
+    fn new(initial_state: Option<usize>) -> Self {
+        Self {
+            states_count: 0,
+            initial_state,
+            accepting_states: BTreeMap::new(),
+            transitions: BTreeMap::new(),
+        }
+    }
+
+    fn add_state(&mut self, state: usize, lexeme_type: Option<&impl Debug>) {
+        self.states_count = self.states_count.max(state + 1);
+        if let Some(lexeme_type) = lexeme_type {
+            self.accepting_states
+                .insert(state, format!("{:?}", lexeme_type));
+        }
+    }
+
+    fn add_transition(&mut self, src: usize, dst: usize, symbol: Option<usize>) {
+        self.transitions.entry((src, dst)).or_default().push(symbol);
+    }
+
+    fn render<Symbol>(mut self, symbol_classes: &SymbolClasses<Symbol>) -> String
+    where
+        Symbol: Alphabet,
+    {
+        let class_ranges = list_class_ranges(symbol_classes);
+
+        let mut dot = String::from("digraph {\n    rankdir=LR;\n    node [shape=circle];\n");
+        if let Some(initial_state) = self.initial_state {
+            dot.push_str("    start [shape=point];\n");
+            writeln!(dot, "    start -> {};", initial_state).unwrap();
+        }
+        for state in 0..self.states_count {
+            match self.accepting_states.get(&state) {
+                None => writeln!(dot, "    {};", state).unwrap(),
+                Some(lexeme_type) => writeln!(
+                    dot,
+                    "    {} [shape=doublecircle, label=\"{}\\n{}\"];",
+                    state,
+                    state,
+                    escape(lexeme_type),
+                )
+                .unwrap(),
+            }
+        }
+        let is_anchor = |serial: &Option<usize>| {
+            matches!(
+                serial.map(LexerSymbol::<Symbol>::from_serial),
+                Some(LexerSymbol::Anchor(_))
+            )
+        };
+        for ((src, dst), serials) in &mut self.transitions {
+            // Every state loops by the anchors, so that feeding them won't discard the states
+            // that don't assert them
+            serials.retain(|serial| src != dst || !is_anchor(serial));
+            if serials.is_empty() {
+                continue;
+            }
+            writeln!(
+                dot,
+                "    {} -> {} [label=\"{}\"];",
+                src,
+                dst,
+                escape(&describe_symbols(serials, &class_ranges)),
+            )
+            .unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// A range of symbols of consecutive serials, as its first and last symbols, and its length
+type SymbolRange<Symbol> = (Symbol, Symbol, usize);
+
+// Lists the maximal ranges of the symbols of each class, by the classes' indices
+fn list_class_ranges<Symbol>(
+    symbol_classes: &SymbolClasses<Symbol>,
+) -> Vec<Vec<SymbolRange<Symbol>>>
+where
+    Symbol: Alphabet,
+{
+    let mut symbols = Symbol::list_symbols();
+    symbols.sort_by_key(Symbol::serial);
+    let mut class_ranges = vec![Vec::new(); symbol_classes.count()];
+    for symbol in symbols {
+        let ranges: &mut Vec<SymbolRange<Symbol>> =
+            &mut class_ranges[symbol_classes.classify(symbol).index()];
+        match ranges.last_mut() {
+            Some((_, last, length)) if last.serial() + 1 == symbol.serial() => {
+                *last = symbol;
+                *length += 1;
+            }
+            _ => ranges.push((symbol, symbol, 1)),
+        }
+    }
+    class_ranges
+}
+
+// Lists the symbols of a transition, with input symbols of consecutive serials compacted into
+// ranges
+fn describe_symbols<Symbol>(
+    serials: &mut [Option<usize>],
+    class_ranges: &[Vec<SymbolRange<Symbol>>],
+) -> String
+where
+    Symbol: Alphabet,
+{
+    serials.sort();
+    let mut descriptions = Vec::new();
+    let mut input_ranges = Vec::new();
+    for serial in serials.iter() {
+        match serial.map(LexerSymbol::<Symbol>::from_serial) {
+            None => descriptions.push(String::from("ε")),
+            Some(LexerSymbol::Anchor(anchor)) => {
+                descriptions.push(describe_anchor(anchor).to_string())
+            }
+            Some(LexerSymbol::Input(class)) => {
+                input_ranges.extend_from_slice(&class_ranges[class.index()])
+            }
+        }
+    }
+
+    // The ranges of different classes are merged where they're adjacent
+    input_ranges.sort_by_key(|(first, _, _)| first.serial());
+    let mut range: Option<SymbolRange<Symbol>> = None;
+    for (first, last, length) in input_ranges {
+        match &mut range {
+            Some((_, range_last, range_length)) if range_last.serial() + 1 == first.serial() => {
+                *range_last = last;
+                *range_length += length;
+            }
+            _ => {
+                if let Some(range) = range.replace((first, last, length)) {
+                    descriptions.extend(describe_range(range));
+                }
+            }
+        }
+    }
+    if let Some(range) = range {
+        descriptions.extend(describe_range(range));
+    }
+    descriptions.join(", ")
+}
+
+fn describe_range<Symbol>((first, last, length): SymbolRange<Symbol>) -> Vec<String>
+where
+    Symbol: Alphabet,
+{
+    if length >= MIN_RANGE_LENGTH {
+        vec![format!("{}-{}", first.describe(), last.describe())]
+    } else if length == 2 {
+        vec![first.describe(), last.describe()]
+    } else {
+        vec![first.describe()]
+    }
+}
+
+fn describe_anchor(anchor: Anchor) -> &'static str {
+    match anchor {
+        Anchor::LineStart => "^",
+        Anchor::LineEnd => "$",
+        Anchor::InputEnd => "\\z",
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
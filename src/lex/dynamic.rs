@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::handles::specials::AutomaticallyHandled;
+
+/// A lexeme type defined at runtime, identified by its name in a [LexemeTypeRegistry].
+///
+/// This serves lexical analyzers and syntax-directed translators whose lexeme types are only
+/// known at runtime (for example, when they're loaded from a [LexerSpec](crate::lex::LexerSpec)),
+/// in place of a hand-written `enum`. Dynamic lexeme types are cheap to clone, and are compared
+/// and hashed by their registry and their serial numbers in it, so types of the same name from
+/// different registries are distinct. Still, they should not be mixed in the same analyzer or
+/// translator, as their serial numbers (and thus their handles) may collide.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::parsing::SyntaxDirectedTranslatorBuilder;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// let mut registry = LexemeTypeRegistry::new();
+/// let spec = LexerSpec::parse(r#"
+///     [0-9]+ => INTEGER
+///     \+ => PLUS
+/// "#).unwrap();
+/// let lexical_analyzer = spec
+///     .map_lexeme_types(|name| registry.intern(&name))
+///     .into_builder()
+///     .build();
+///
+/// let mut builder = SyntaxDirectedTranslatorBuilder::new();
+/// builder.dub_lexeme_type(registry.get("INTEGER").unwrap(), "INTEGER");
+/// builder.dub_lexeme_type(registry.get("PLUS").unwrap(), "+");
+/// builder.new_nonterminal("sum");
+/// builder.set_start_nonterminal("sum");
+/// builder.set_leaf_satellite_builder("INTEGER", |_, contents| contents.parse().unwrap());
+/// builder.set_default_leaf_satellite_builder(|_, _| 0);
+/// builder.register_identity_rule("sum", "INTEGER");
+/// builder.register_rule("sum", vec!["sum", "+", "INTEGER"], |_, values: Vec<i32>| {
+///     values[0] + values[2]
+/// });
+/// let translator = builder.build();
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("1+2+3");
+/// let lexemes = lexical_analyzer.analyze(input_text);
+/// assert_eq!(translator.translate(&mut (), lexemes), Some(6));
+/// ```
+#[derive(Clone)]
+pub struct DynamicLexemeType {
+    registry_id: usize,
+    serial: u16,
+    name: Arc<str>,
+}
+
+impl DynamicLexemeType {
+    /// The name of the lexeme type.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq for DynamicLexemeType {
+    fn eq(&self, other: &Self) -> bool {
+        (self.registry_id, self.serial) == (other.registry_id, other.serial)
+    }
+}
+
+impl Eq for DynamicLexemeType {}
+
+impl Hash for DynamicLexemeType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.registry_id.hash(state);
+        self.serial.hash(state);
+    }
+}
+
+impl Debug for DynamicLexemeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl Display for DynamicLexemeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl AutomaticallyHandled for DynamicLexemeType {
+    type HandleCoreType = u16;
+
+    fn serial(&self) -> usize {
+        self.serial as usize
+    }
+}
+
+/// A table of [DynamicLexemeType]s, each identified by a distinct name.
+///
+/// Lexeme types are numbered sequentially (starting from 0), in the order in which their names
+/// were first interned.
+#[derive(Debug)]
+pub struct LexemeTypeRegistry {
+    // Distinguishes the lexeme types of this registry from those of other registries
+    id: usize,
+
+    lexeme_types: HashMap<Arc<str>, DynamicLexemeType>,
+
+    // Indexed by the lexeme types' serial numbers
+    names: Vec<Arc<str>>,
+}
+
+impl LexemeTypeRegistry {
+    /// Creates a new, empty, [LexemeTypeRegistry].
+    pub fn new() -> Self {
+        static REGISTRIES_COUNT: AtomicUsize = AtomicUsize::new(0);
+        Self {
+            id: REGISTRIES_COUNT.fetch_add(1, Ordering::Relaxed),
+            lexeme_types: HashMap::new(),
+            names: Vec::new(),
+        }
+    }
+
+    /// Gets the lexeme type of the specified name, registering it if it's not already registered.
+    ///
+    /// # Panics
+    ///
+    /// If more than 2<sup>16</sup> distinct lexeme types are registered.
+    pub fn intern(&mut self, name: &str) -> DynamicLexemeType {
+        if let Some(lexeme_type) = self.lexeme_types.get(name) {
+            return lexeme_type.clone();
+        }
+        let lexeme_type = DynamicLexemeType {
+            registry_id: self.id,
+            serial: self
+                .names
+                .len()
+                .try_into()
+                .expect("Registries cannot hold more than 2^16 distinct lexeme types"),
+            name: Arc::from(name),
+        };
+        self.lexeme_types
+            .insert(lexeme_type.name.clone(), lexeme_type.clone());
+        self.names.push(lexeme_type.name.clone());
+        lexeme_type
+    }
+
+    /// Gets the lexeme type of the specified name, if it was registered.
+    pub fn get(&self, name: &str) -> Option<DynamicLexemeType> {
+        self.lexeme_types.get(name).cloned()
+    }
+
+    /// Gets the lexeme type of the specified serial number, if there's one.
+    pub fn get_by_serial(&self, serial: usize) -> Option<DynamicLexemeType> {
+        self.names
+            .get(serial)
+            .and_then(|name| self.lexeme_types.get(name))
+            .cloned()
+    }
+
+    /// The number of registered lexeme types.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Checks whether no lexeme types were registered yet.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+impl Default for LexemeTypeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,27 @@
+use crate::lex::{Lexeme, LexerMode};
+
+/// The state of a [LexicalAnalyzer](crate::lex::LexicalAnalyzer) when its input is exhausted.
+///
+/// This is given to the analyzer's end-of-input action, set by
+/// [LexicalAnalyzerBuilder](crate::lex::LexicalAnalyzerBuilder::set_end_of_input_action).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndOfInput {
+    /// The analyzer's mode stack, from the bottom ([LexerMode::INITIAL]) to the current mode.
+    pub mode_stack: Vec<LexerMode>,
+
+    /// The length of the analyzed input, in symbols (bytes, for text).
+    pub position: usize,
+}
+
+impl EndOfInput {
+    /// Gets the mode the analyzer was in when its input was exhausted.
+    pub fn current_mode(&self) -> LexerMode {
+        *self
+            .mode_stack
+            .last()
+            .expect("The mode stack should always hold the initial mode")
+    }
+}
+
+pub(super) type EndOfInputAction<LexemeType, Symbol> =
+Box<dyn Fn(&EndOfInput) -> Vec<Lexeme<LexemeType, Symbol>> + Send + Sync>;
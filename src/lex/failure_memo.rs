@@ -0,0 +1,79 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::lex::Alphabet;
+use crate::lex::automaton::{LexerAutomaton, LexerState, LexerAutomatonScan};
+
+/// Memoization of the (DFA state, input position) pairs from which no lexeme can be accepted.
+///
+/// This implements the linear-time maximal-munch tokenization algorithm, described by Thomas Reps
+/// in "Maximal-Munch Tokenization in Linear Time" (1998). When the scan of a lexeme reads past the
+/// end of the longest lexeme it could accept, the pairs visited beyond that point are known to
+/// lead to no accepting state. Since later scans (starting from that end) never need to revisit
+/// these pairs, each pair is visited at most once, and the entire input is scanned in linear time.
+pub(super) struct FailureMemo<Symbol>
+where
+    Symbol: Alphabet,
+{
+    // Indexed by positions relative to the beginning of the currently scanned lexeme
+    failed_states: VecDeque<HashSet<LexerState<Symbol>>>,
+    visited_states: Vec<(usize, LexerState<Symbol>)>,
+
+    // States of lazily-constructed automata are invalidated when their cache is flushed
+    automaton_flush_count: usize,
+}
+
+impl<Symbol> FailureMemo<Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) fn new(automaton: &LexerAutomaton<Symbol>) -> Self {
+        Self {
+            failed_states: VecDeque::new(),
+            visited_states: Vec::new(),
+            automaton_flush_count: automaton.count_flushes(),
+        }
+    }
+
+    /// Drops all knowledge about states that were invalidated by the automaton.
+    pub(super) fn sync(&mut self, automaton: &LexerAutomatonScan<Symbol>) {
+        if automaton.count_flushes() != self.automaton_flush_count {
+            self.failed_states.iter_mut().for_each(HashSet::clear);
+            self.visited_states.clear();
+            self.automaton_flush_count = automaton.count_flushes();
+        }
+    }
+
+    /// Checks whether `state`, reached at `position` (relative to the beginning of the currently
+    /// scanned lexeme), is known to lead to no accepting state.
+    pub(super) fn has_failed(&self, state: LexerState<Symbol>, position: usize) -> bool {
+        self.failed_states
+            .get(position)
+            .is_some_and(|states| states.contains(&state))
+    }
+
+    /// Records that `state` was visited at `position` during the current scan.
+    pub(super) fn visit(&mut self, state: LexerState<Symbol>, position: usize) {
+        self.visited_states.push((position, state));
+    }
+
+    /// Concludes the current scan, whose longest accepted lexeme was of length `accepted_length`.
+    ///
+    /// The next scan is expected to start right after the accepted lexeme.
+    pub(super) fn conclude_scan(&mut self, accepted_length: usize) {
+        for (position, state) in self.visited_states.drain(..) {
+            if position > accepted_length {
+                if self.failed_states.len() <= position {
+                    self.failed_states.resize_with(position + 1, HashSet::new);
+                }
+                self.failed_states[position].insert(state);
+            }
+        }
+        self.skip(accepted_length);
+    }
+
+    /// Moves the beginning of the next scan `length` positions forward.
+    pub(super) fn skip(&mut self, length: usize) {
+        let skipped_positions = length.min(self.failed_states.len());
+        self.failed_states.drain(..skipped_positions);
+    }
+}
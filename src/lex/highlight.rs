@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::lex::LexicalAnalyzer;
+use crate::lex::lexical_analyzer::{AnalysisState, LexicalError};
+use crate::readers::ByteArrayReader;
+
+/// Renders text with its lexemes highlighted, as HTML or as ANSI-colored text for terminals.
+///
+/// The text is separated into lexemes by a [LexicalAnalyzer], and each lexeme type may be mapped
+/// to a style class (such as `keyword`, or `comment`). Lexemes of types that are mapped to no class
+/// (such as white space) are kept verbatim, and so is any part of the text that matches no
+/// lexeme, or is an unterminated nested region, which doesn't stop the analysis (unlike
+/// [LexicalAnalyzer::analyze]). Lexemes yielded once the input is exhausted (see
+/// [LexicalAnalyzer::analyze]) are not part of the text, and are ignored. Lexemes are found in the
+/// UTF-8 encoding of the text, so a character split between lexemes is rendered as a whole, in the
+/// style of the first of them.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { Let, Name, Integer, WhiteSpace }
+/// let lexical_analyzer = LexicalAnalyzer::new(vec![
+///     LexemeDescriptor::keyword(MyLexemeType::Let, "let"),
+///     LexemeDescriptor::new(MyLexemeType::Name, patterns::identifier()),
+///     LexemeDescriptor::new(MyLexemeType::Integer, patterns::decimal_integer(None)),
+///     LexemeDescriptor::new(MyLexemeType::WhiteSpace, Regex::white_space()),
+/// ]);
+/// let mut highlighter = Highlighter::new(&lexical_analyzer);
+/// highlighter.set_class(MyLexemeType::Let, "keyword");
+/// highlighter.set_class(MyLexemeType::Integer, "number");
+/// highlighter.set_ansi_style("keyword", "1;34");
+///
+/// assert_eq!(
+///     highlighter.to_html("let x = 1"),
+///     "<span class=\"keyword\">let</span> x = <span class=\"number\">1</span>",
+/// );
+/// assert_eq!(highlighter.to_ansi("let x = 1"), "\x1b[1;34mlet\x1b[0m x = 1");
+/// ```
+pub struct Highlighter<'a, LexemeType> {
+    lexical_analyzer: &'a LexicalAnalyzer<LexemeType>,
+    classes: HashMap<LexemeType, String>,
+
+    // By classes, the parameters of the ANSI escape sequences that style them
+    ansi_styles: HashMap<String, String>,
+}
+
+// A part of the highlighted text, along with the class it's styled by, if any
+struct Segment<'a> {
+    range: Range<usize>,
+    class: Option<&'a str>,
+}
+
+impl<'a, LexemeType> Highlighter<'a, LexemeType>
+where
+    LexemeType: Hash + Eq + Clone,
+{
+    /// Creates a new [Highlighter] of the lexemes found by the specified analyzer, with no lexeme
+    /// types mapped to style classes.
+    pub fn new(lexical_analyzer: &'a LexicalAnalyzer<LexemeType>) -> Self {
+        Self {
+            lexical_analyzer,
+            classes: HashMap::new(),
+            ansi_styles: HashMap::new(),
+        }
+    }
+
+    /// Set the style class of lexemes of the specified type, replacing any class previously set
+    /// for it.
+    pub fn set_class(&mut self, lexeme_type: LexemeType, class: &str) {
+        self.classes.insert(lexeme_type, String::from(class));
+    }
+
+    /// Set the style of the specified class in ANSI-colored text, replacing any style previously
+    /// set for it.
+    ///
+    /// The style is given by the parameters of an ANSI "Select Graphic Rendition" escape sequence,
+    /// such as `31` for red, or `1;34` for bold blue. Classes with no ANSI style are rendered
+    /// verbatim in ANSI-colored text.
+    pub fn set_ansi_style(&mut self, class: &str, style: &str) {
+        self.ansi_styles
+            .insert(String::from(class), String::from(style));
+    }
+
+    /// Renders the text as HTML, where each lexeme with a style class is wrapped by a `span` of
+    /// that class.
+    ///
+    /// The text is HTML-escaped, but is otherwise kept as is, so it may be wrapped by a `pre`
+    /// element.
+    pub fn to_html(&self, text: &str) -> String {
+        let mut html = String::new();
+        for segment in self.segments(text) {
+            let contents = escape_html(&text[segment.range]);
+            match segment.class {
+                Some(class) => html.push_str(&format!(
+                    "<span class=\"{}\">{}</span>",
+                    escape_html(class),
+                    contents
+                )),
+                None => html.push_str(&contents),
+            }
+        }
+        html
+    }
+
+    /// Renders the text with ANSI escape sequences, where each lexeme is styled by the ANSI style
+    /// of its class (see [Highlighter::set_ansi_style]).
+    pub fn to_ansi(&self, text: &str) -> String {
+        let mut ansi = String::new();
+        for segment in self.segments(text) {
+            let contents = &text[segment.range];
+            match segment.class.and_then(|class| self.ansi_styles.get(class)) {
+                Some(style) => ansi.push_str(&format!("\x1b[{}m{}\x1b[0m", style, contents)),
+                None => ansi.push_str(contents),
+            }
+        }
+        ansi
+    }
+
+    // Separates the text into lexemes, and runs of symbols that match no lexeme, at character
+    // boundaries
+    fn segments(&self, text: &str) -> Vec<Segment<'_>> {
+        let mut reader = ByteArrayReader::from_string_slice(text);
+        let mut analysis_state = AnalysisState::new(self.lexical_analyzer);
+        let mut segments: Vec<Segment> = Vec::new();
+        loop {
+            let start = analysis_state.position;
+            match self
+                .lexical_analyzer
+                .try_identify_next_lexeme_type(&mut reader, &mut analysis_state)
+            {
+                Ok(None) => break,
+                Ok(Some(lexeme_type)) => segments.push(Segment {
+                    range: start..analysis_state.position,
+                    class: self.classes.get(&lexeme_type).map(String::as_str),
+                }),
+                Err(error) => {
+                    if let LexicalError::UnrecognizedInput = error {
+                        self.lexical_analyzer
+                            .skip_unrecognized_symbol(&mut reader, &mut analysis_state);
+                    }
+                    let end = analysis_state.position;
+                    match segments.last_mut() {
+                        Some(Segment { range, class: None }) if range.end == start => {
+                            range.end = end;
+                        }
+                        _ => segments.push(Segment {
+                            range: start..end,
+                            class: None,
+                        }),
+                    }
+                }
+            }
+        }
+
+        // A segment starting in the middle of a character is merged into the preceding one
+        let mut merged_segments: Vec<Segment> = Vec::with_capacity(segments.len());
+        for segment in segments {
+            match merged_segments.last_mut() {
+                Some(last_segment) if !text.is_char_boundary(segment.range.start) => {
+                    last_segment.range.end = segment.range.end;
+                }
+                _ => merged_segments.push(segment),
+            }
+        }
+        merged_segments
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use crate::lex::{Alphabet, Captures, Lexeme, SourceId, Span};
+use crate::lex::lexical_analyzer::{AnalysisState, LexicalAnalyzer};
+use crate::readers::Reader;
+
+/// The stream of lexemes extracted by a [LexicalAnalyzer] from a stack of included sources (see
+/// [LexicalAnalyzer::analyze_with_includes]).
+///
+/// Each lexeme is yielded along with its [Span] in the source it was found in.
+pub struct IncludingLexemeIterator<'a, LexemeType, ReaderType, IncludeAction, Symbol = u8>
+where
+    Symbol: Alphabet,
+{
+    lexical_analyzer: &'a LexicalAnalyzer<LexemeType, Symbol>,
+    include_action: IncludeAction,
+
+    // The sources currently being analyzed, from the outermost to the innermost
+    sources: Vec<IncludedSource<ReaderType, Symbol>>,
+
+    included_sources_count: usize,
+
+    // The lexemes yielded after the root source was exhausted, once it is
+    trailing_lexemes: Option<VecDeque<Lexeme<LexemeType, Symbol>>>,
+    root_length: usize,
+}
+
+struct IncludedSource<ReaderType, Symbol>
+where
+    Symbol: Alphabet,
+{
+    id: SourceId,
+    reader: ReaderType,
+    analysis_state: AnalysisState<Symbol>,
+}
+
+impl<'a, LexemeType, ReaderType, IncludeAction, Symbol>
+IncludingLexemeIterator<'a, LexemeType, ReaderType, IncludeAction, Symbol>
+where
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
+{
+    pub(super) fn new(
+        lexical_analyzer: &'a LexicalAnalyzer<LexemeType, Symbol>,
+        reader: ReaderType,
+        include_action: IncludeAction,
+    ) -> Self {
+        Self {
+            lexical_analyzer,
+            include_action,
+            sources: vec![IncludedSource {
+                id: SourceId::ROOT,
+                reader,
+                analysis_state: AnalysisState::new(lexical_analyzer),
+            }],
+            included_sources_count: 0,
+            trailing_lexemes: None,
+            root_length: 0,
+        }
+    }
+}
+
+impl<'a, LexemeType, ReaderType, IncludeAction, Symbol> Iterator
+for IncludingLexemeIterator<'a, LexemeType, ReaderType, IncludeAction, Symbol>
+where
+    LexemeType: Hash + Clone + Eq,
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
+    IncludeAction: FnMut(&Lexeme<LexemeType, Symbol>, &Captures) -> Option<ReaderType>,
+{
+    type Item = (Lexeme<LexemeType, Symbol>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(trailing_lexemes) = &mut self.trailing_lexemes {
+                let span = Span {
+                    source: SourceId::ROOT,
+                    start: self.root_length,
+                    end: self.root_length,
+                };
+                return trailing_lexemes.pop_front().map(|lexeme| (lexeme, span));
+            }
+
+            let source = self
+                .sources
+                .last_mut()
+                .expect("The root source should be analyzed until it is exhausted");
+            let start = source.analysis_state.position;
+            match self
+                .lexical_analyzer
+                .collect_next_lexeme(&mut source.reader, &mut source.analysis_state)
+            {
+                Some((lexeme, _)) if self.lexical_analyzer.is_skipped(&lexeme.lexeme_type) => {}
+                Some((lexeme, captures)) => {
+                    let span = Span {
+                        source: source.id,
+                        start,
+                        end: source.analysis_state.position,
+                    };
+                    if let Some(reader) = (self.include_action)(&lexeme, &captures) {
+                        self.included_sources_count += 1;
+                        self.sources.push(IncludedSource {
+                            id: SourceId(self.included_sources_count),
+                            reader,
+                            analysis_state: AnalysisState::new(self.lexical_analyzer),
+                        });
+                    }
+                    return Some((lexeme, span));
+                }
+                None => {
+                    // Resume the including source, or conclude the analysis
+                    let source = self
+                        .sources
+                        .pop()
+                        .expect("The exhausted source should be on the stack");
+                    if self.sources.is_empty() {
+                        self.root_length = source.analysis_state.position;
+                        self.trailing_lexemes = Some(
+                            self.lexical_analyzer
+                                .conclude_analysis(&source.analysis_state)
+                                .into(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use std::hash::Hash;
+use std::ops::Range;
+
+use crate::lex::{Alphabet, Lexeme, LexicalAnalyzer};
+use crate::lex::lexical_analyzer::{AnalysisState, LexicalError, ResumePoint};
+use crate::readers::Reader;
+
+/// The lexemes of some input, along with what's needed to update them efficiently once the input
+/// is edited.
+///
+/// Created by [LexicalAnalyzer::analyze_for_relexing], and updated by [LexicalAnalyzer::relex].
+/// Lexemes are located by the range of offsets (in symbols, bytes for text) they span in the
+/// input. As with [LexicalAnalyzer::analyze], the lexemes of skipped types are not listed (see
+/// [set_skipped_lexeme_types](crate::lex::LexicalAnalyzerBuilder::set_skipped_lexeme_types)).
+///
+/// Unlike [LexicalAnalyzer::analyze], the analysis doesn't stop at input that matches no lexeme,
+/// which is skipped a symbol at a time, or at a nested region that the input ends inside of, which
+/// is skipped up to the end of the input. Such input is not listed as lexemes, and is located by
+/// [LexemeList::unrecognized_ranges] instead.
+pub struct LexemeList<LexemeType, Symbol = u8>
+where
+    Symbol: Alphabet,
+{
+    // Excluding the lexemes yielded once the input is exhausted, but including skipped lexemes
+    // (which are needed for resynchronization)
+    records: Vec<LexemeRecord<LexemeType, Symbol>>,
+
+    // The lexemes yielded once the input is exhausted
+    trailing_lexemes: Vec<Lexeme<LexemeType, Symbol>>,
+
+    // The state of the analysis once the input was exhausted
+    final_resume_point: ResumePoint<Symbol>,
+
+    input_length: usize,
+}
+
+struct LexemeRecord<LexemeType, Symbol>
+where
+    Symbol: Alphabet,
+{
+    // Absent for unrecognized input
+    lexeme: Option<Lexeme<LexemeType, Symbol>>,
+    span: Range<usize>,
+    is_skipped: bool,
+
+    // The offset following the last symbol read while identifying the lexeme (which is past the
+    // end of the input if its end was reached). The lexeme only depends on the input before it.
+    read_end: usize,
+
+    // The state of the analysis right before the lexeme
+    resume_point: ResumePoint<Symbol>,
+}
+
+enum ScanEnd<Symbol>
+where
+    Symbol: Alphabet,
+{
+    // The scan reached the previously found lexeme of the specified index, in the same state
+    Resynchronized(usize),
+
+    Exhausted(AnalysisState<Symbol>),
+}
+
+impl<LexemeType, Symbol> LexemeList<LexemeType, Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Iterates over the lexemes, along with the ranges of the input they span.
+    ///
+    /// The lexemes yielded once the input is exhausted (see
+    /// [LexicalAnalyzer::analyze](crate::lex::LexicalAnalyzer::analyze)) are last, with empty
+    /// ranges at the end of the input.
+    pub fn iter(&self) -> impl Iterator<Item = (&Lexeme<LexemeType, Symbol>, Range<usize>)> {
+        let input_end = self.input_length..self.input_length;
+        self.records
+            .iter()
+            .filter(|record| !record.is_skipped)
+            .filter_map(|record| Some((record.lexeme.as_ref()?, record.span.clone())))
+            .chain(
+                self.trailing_lexemes
+                    .iter()
+                    .map(move |lexeme| (lexeme, input_end.clone())),
+            )
+    }
+
+    /// Gets the lexeme of the specified index, along with the range of the input it spans.
+    pub fn get(&self, index: usize) -> Option<(&Lexeme<LexemeType, Symbol>, Range<usize>)> {
+        self.iter().nth(index)
+    }
+
+    /// The number of lexemes.
+    pub fn len(&self) -> usize {
+        count_listed(&self.records) + self.trailing_lexemes.len()
+    }
+
+    /// Checks whether there are no lexemes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the maximal ranges of the input that are not spanned by any lexeme, since
+    /// they match no lexeme, or are nested regions that the input ends inside of.
+    pub fn unrecognized_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let mut records = self
+            .records
+            .iter()
+            .filter(|record| record.lexeme.is_none())
+            .peekable();
+        std::iter::from_fn(move || {
+            let mut range = records.next()?.span.clone();
+            while let Some(record) = records.next_if(|record| record.span.start == range.end) {
+                range.end = record.span.end;
+            }
+            Some(range)
+        })
+    }
+
+    /// The length of the analyzed input, in symbols (bytes, for text).
+    pub fn input_length(&self) -> usize {
+        self.input_length
+    }
+}
+
+impl<LexemeType, Symbol> LexemeList<LexemeType, Symbol>
+where
+    LexemeType: Hash + Eq + Clone,
+    Symbol: Alphabet,
+{
+    pub(super) fn analyze(
+        lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+        input: &[Symbol],
+    ) -> Self {
+        let resume_point = AnalysisState::new(lexical_analyzer).resume_point();
+        let (records, scan_end) = scan(lexical_analyzer, input, 0, &resume_point, |_, _| None);
+        let ScanEnd::Exhausted(final_state) = scan_end else {
+            unreachable!("A scan that never resynchronizes only ends when the input is exhausted")
+        };
+        Self {
+            records,
+            trailing_lexemes: lexical_analyzer.conclude_analysis(&final_state),
+            final_resume_point: final_state.resume_point(),
+            input_length: input.len(),
+        }
+    }
+
+    pub(super) fn relex(
+        &mut self,
+        lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+        edited_range: Range<usize>,
+        input: &[Symbol],
+    ) -> Range<usize> {
+        let old_length = self.input_length;
+        if edited_range.start > edited_range.end || edited_range.end > old_length {
+            panic!(
+                "Edited range {:?} is out of the bounds of the previous input, of length {}",
+                edited_range, old_length,
+            )
+        }
+        let unedited_length = old_length - edited_range.len();
+        if input.len() < unedited_length {
+            panic!(
+                "Edited input is shorter ({}) than the previous input without its edited range \
+                ({})",
+                input.len(),
+                unedited_length,
+            )
+        }
+
+        // Offsets following the edit are shifted by the change in the input's length
+        let new_edited_end = input.len() - (old_length - edited_range.end);
+        let shift = |offset: usize| offset - edited_range.end + new_edited_end;
+
+        // The lexemes that didn't read the edited range are unaffected by it
+        let first_affected = self
+            .records
+            .iter()
+            .position(|record| record.read_end > edited_range.start)
+            .unwrap_or(self.records.len());
+        let (restart_position, resume_point) = match self.records.get(first_affected) {
+            Some(record) => (record.span.start, record.resume_point.clone()),
+            None => (old_length, self.final_resume_point.clone()),
+        };
+
+        // Once a lexeme past the edit starts where a previous one did, in the same state, the
+        // rest of the lexemes would be the same as before
+        let records = &self.records;
+        let mut old_index = first_affected;
+        let (new_records, scan_end) = scan(
+            lexical_analyzer,
+            input,
+            restart_position,
+            &resume_point,
+            |position, resume_point| {
+                if position < new_edited_end {
+                    return None;
+                }
+                while records.get(old_index).is_some_and(|record| {
+                    record.span.start < edited_range.end || shift(record.span.start) < position
+                }) {
+                    old_index += 1;
+                }
+                records
+                    .get(old_index)
+                    .filter(|record| {
+                        shift(record.span.start) == position
+                            && record.resume_point == *resume_point
+                    })
+                    .map(|_| old_index)
+            },
+        );
+
+        let relexed_start = count_listed(&self.records[..first_affected]);
+        let relexed_range = relexed_start..relexed_start + count_listed(&new_records);
+        match scan_end {
+            ScanEnd::Resynchronized(old_index) => {
+                for record in &mut self.records[old_index..] {
+                    record.span = shift(record.span.start)..shift(record.span.end);
+                    record.read_end = shift(record.read_end);
+                }
+                self.records.splice(first_affected..old_index, new_records);
+            }
+            ScanEnd::Exhausted(final_state) => {
+                self.records.truncate(first_affected);
+                self.records.extend(new_records);
+                self.final_resume_point = final_state.resume_point();
+            }
+        }
+
+        // The trailing lexemes may depend on the input's length, so they're always recollected
+        self.input_length = input.len();
+        let final_state =
+            AnalysisState::resume(lexical_analyzer, &self.final_resume_point, input.len());
+        self.trailing_lexemes = lexical_analyzer.conclude_analysis(&final_state);
+
+        relexed_range
+    }
+}
+
+// Analyzes the input from the specified offset and resume point, until it's exhausted, or until
+// `resynchronize` returns the index of a previously found lexeme at the beginning of some lexeme
+fn scan<LexemeType, Symbol>(
+    lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+    input: &[Symbol],
+    start: usize,
+    resume_point: &ResumePoint<Symbol>,
+    mut resynchronize: impl FnMut(usize, &ResumePoint<Symbol>) -> Option<usize>,
+) -> (Vec<LexemeRecord<LexemeType, Symbol>>, ScanEnd<Symbol>)
+where
+    LexemeType: Hash + Eq + Clone,
+    Symbol: Alphabet,
+{
+    let mut reader = SliceReader::new(input, start);
+    let mut analysis_state = AnalysisState::resume(lexical_analyzer, resume_point, start);
+    let mut records = Vec::new();
+
+    loop {
+        let lexeme_start = analysis_state.position;
+        let resume_point = analysis_state.resume_point();
+        if let Some(old_index) = resynchronize(lexeme_start, &resume_point) {
+            return (records, ScanEnd::Resynchronized(old_index));
+        }
+
+        reader.read_end = lexeme_start;
+        let lexeme =
+            match lexical_analyzer.try_collect_next_lexeme(&mut reader, &mut analysis_state) {
+                Ok(Some((lexeme, _))) => Some(lexeme),
+                Ok(None) => return (records, ScanEnd::Exhausted(analysis_state)),
+                Err(LexicalError::UnrecognizedInput) => {
+                    lexical_analyzer.skip_unrecognized_symbol(&mut reader, &mut analysis_state);
+                    None
+                }
+                Err(LexicalError::UnterminatedNestedRegion { .. }) => None,
+            };
+        records.push(LexemeRecord {
+            is_skipped: lexeme
+                .as_ref()
+                .is_some_and(|lexeme| lexical_analyzer.is_skipped(&lexeme.lexeme_type)),
+            lexeme,
+            span: lexeme_start..analysis_state.position,
+            read_end: reader.read_end,
+            resume_point,
+        });
+    }
+}
+
+// The number of records of lexemes that aren't skipped
+fn count_listed<LexemeType, Symbol>(records: &[LexemeRecord<LexemeType, Symbol>]) -> usize
+where
+    Symbol: Alphabet,
+{
+    records
+        .iter()
+        .filter(|record| record.lexeme.is_some() && !record.is_skipped)
+        .count()
+}
+
+// Reads a slice from some offset, while keeping track of how far it was read
+struct SliceReader<'a, Symbol> {
+    input: &'a [Symbol],
+    head: usize,
+    tail: usize,
+    cursor: usize,
+
+    // The offset following the furthest symbol read (or past the end of the input, if its end
+    // was reached)
+    read_end: usize,
+}
+
+impl<'a, Symbol> SliceReader<'a, Symbol> {
+    fn new(input: &'a [Symbol], start: usize) -> Self {
+        Self {
+            input,
+            head: start,
+            tail: start,
+            cursor: start,
+            read_end: start,
+        }
+    }
+}
+
+impl<'a, Symbol> Reader<Symbol> for SliceReader<'a, Symbol>
+where
+    Symbol: Copy,
+{
+    fn read_next(&mut self) -> Option<Symbol> {
+        self.read_end = self.read_end.max(self.cursor + 1);
+        let symbol = *self.input.get(self.cursor)?;
+        self.cursor += 1;
+        Some(symbol)
+    }
+
+    fn set_head(&mut self) {
+        self.head = self.cursor;
+    }
+
+    fn set_tail(&mut self) {
+        self.tail = self.cursor;
+    }
+
+    fn move_cursor_to_tail(&mut self) {
+        self.cursor = self.tail;
+    }
+
+    fn get_sequence(&self) -> impl Iterator<Item = Symbol> {
+        self.input[self.head..self.tail].iter().copied()
+    }
+}
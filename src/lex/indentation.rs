@@ -0,0 +1,377 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use crate::lex::Lexeme;
+
+/// Specifies how the indentation of lines is translated into lexemes, for indentation-sensitive
+/// languages (such as Python).
+///
+/// Given a stream of lexemes (usually extracted by a
+/// [LexicalAnalyzer](crate::lex::LexicalAnalyzer) that also yields white space and line breaks),
+/// the rules can be [applied](IndentationRules::apply) to it to get a new stream, where:
+///
+/// - An increase in indentation (the width of a line's leading white space, where tabs advance to
+///   the next [tab stop](IndentationRules::set_tab_stop)) opens a new block, which is marked by an
+///   _indent_ lexeme.
+/// - A decrease in indentation back to that of an enclosing block is marked by a _dedent_ lexeme
+///   for each closed block.
+/// - Each non-blank logical line is terminated by a _newline_ lexeme.
+/// - White space, blank lines, and line breaks inside brackets are dropped.
+///
+/// The resulting stream can be fed directly to
+/// [SyntaxDirectedTranslator::translate](crate::parsing::SyntaxDirectedTranslator::translate).
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { Name, Colon, WhiteSpace, Newline, Indent, Dedent }
+/// let lexical_analyzer = LexicalAnalyzer::new(vec![
+///     LexemeDescriptor::new(
+///         MyLexemeType::Name,
+///         Regex::plus_from(Regex::character_range('a', 'z')),
+///     ),
+///     LexemeDescriptor::special_char(MyLexemeType::Colon, ':'),
+///     LexemeDescriptor::new(
+///         MyLexemeType::WhiteSpace,
+///         Regex::plus_from(Regex::single_char(' ')),
+///     ),
+///     LexemeDescriptor::special_char(MyLexemeType::Newline, '\n'),
+/// ]);
+/// let indentation_rules = IndentationRules::new(
+///     MyLexemeType::WhiteSpace,
+///     MyLexemeType::Newline,
+///     MyLexemeType::Indent,
+///     MyLexemeType::Dedent,
+/// );
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("a:\n  b\nc\n");
+/// let mut lexemes = indentation_rules.apply(lexical_analyzer.analyze(input_text));
+/// assert_eq!(
+///     lexemes.by_ref().map(|lexeme| lexeme.lexeme_type).collect::<Vec<MyLexemeType>>(),
+///     vec![
+///         MyLexemeType::Name,
+///         MyLexemeType::Colon,
+///         MyLexemeType::Newline,
+///         MyLexemeType::Indent,
+///         MyLexemeType::Name,
+///         MyLexemeType::Newline,
+///         MyLexemeType::Dedent,
+///         MyLexemeType::Name,
+///         MyLexemeType::Newline,
+///     ],
+/// );
+/// assert_eq!(lexemes.error(), None);
+/// ```
+pub struct IndentationRules<LexemeType> {
+    white_space: LexemeType,
+    newline: LexemeType,
+    indent: LexemeType,
+    dedent: LexemeType,
+    ignored: Vec<LexemeType>,
+    bracket_pairs: Vec<(LexemeType, LexemeType)>,
+    end_of_input: Option<LexemeType>,
+    tab_stop: Option<usize>,
+}
+
+impl<LexemeType> IndentationRules<LexemeType>
+where
+    LexemeType: Eq + Clone,
+{
+    /// Creates new indentation rules.
+    ///
+    /// The input lexemes of type `white_space` are used to measure the indentation of lines, and
+    /// the ones of type `newline` separate lines. The output stream marks changes in indentation
+    /// with lexemes of type `indent` and `dedent`.
+    pub fn new(
+        white_space: LexemeType,
+        newline: LexemeType,
+        indent: LexemeType,
+        dedent: LexemeType,
+    ) -> Self {
+        Self {
+            white_space,
+            newline,
+            indent,
+            dedent,
+            ignored: Vec::new(),
+            bracket_pairs: Vec::new(),
+            end_of_input: None,
+            tab_stop: Some(8),
+        }
+    }
+
+    /// Sets lexemes of type `lexeme_type` (such as comments) to be dropped, as if they were white
+    /// space.
+    pub fn add_ignored(&mut self, lexeme_type: LexemeType) {
+        self.ignored.push(lexeme_type);
+    }
+
+    /// Registers a pair of bracket lexeme types, between which line breaks and indentation are
+    /// ignored.
+    pub fn add_bracket_pair(&mut self, open: LexemeType, close: LexemeType) {
+        self.bracket_pairs.push((open, close));
+    }
+
+    /// Sets lexemes of type `lexeme_type` to mark the end of the input, so the final newline and
+    /// dedent lexemes are placed before them (see the end-of-input lexeme of
+    /// [LexicalAnalyzerBuilder](crate::lex::LexicalAnalyzerBuilder::set_end_of_input_lexeme)).
+    pub fn set_end_of_input(&mut self, lexeme_type: LexemeType) {
+        self.end_of_input = Some(lexeme_type);
+    }
+
+    /// Sets the distance between the tab stops, to which tabs advance when measuring the width of
+    /// indentation. Defaults to 8.
+    ///
+    /// If `None`, tabs and spaces aren't considered interchangeable: each line's indentation must
+    /// either extend the one of its enclosing block, or exactly match the one of some enclosing
+    /// block, otherwise an [IndentationError::InconsistentTabs] is reported.
+    ///
+    /// # Panics
+    ///
+    /// If `tab_stop` is `Some(0)`.
+    pub fn set_tab_stop(&mut self, tab_stop: Option<usize>) {
+        if tab_stop == Some(0) {
+            panic!("The distance between tab stops should be positive")
+        }
+        self.tab_stop = tab_stop;
+    }
+
+    /// Applies the rules to a stream of `lexemes`.
+    pub fn apply<Lexemes>(&self, lexemes: Lexemes) -> IndentedLexemes<'_, LexemeType, Lexemes>
+    where
+        Lexemes: Iterator<Item=Lexeme<LexemeType>>,
+    {
+        IndentedLexemes {
+            rules: self,
+            lexemes,
+            pending_lexemes: VecDeque::new(),
+            indentation_stack: vec![String::new()],
+            current_indentation: String::new(),
+            is_line_blank: true,
+            bracket_depth: 0,
+            line: 1,
+            error: None,
+            is_exhausted: false,
+        }
+    }
+
+    // Without tab stops, every character of the indentation is a single column
+    fn measure(&self, indentation: &str) -> usize {
+        let Some(tab_stop) = self.tab_stop else {
+            return indentation.chars().count();
+        };
+        indentation.chars().fold(0, |width, char| match char {
+            '\t' => (width / tab_stop + 1) * tab_stop,
+            _ => width + 1,
+        })
+    }
+
+    fn is_end_of_input(&self, lexeme_type: &LexemeType) -> bool {
+        self.end_of_input.as_ref() == Some(lexeme_type)
+    }
+
+    fn is_ignored(&self, lexeme_type: &LexemeType) -> bool {
+        *lexeme_type == self.white_space || self.ignored.contains(lexeme_type)
+    }
+
+    fn is_open_bracket(&self, lexeme_type: &LexemeType) -> bool {
+        self.bracket_pairs.iter().any(|(open, _)| open == lexeme_type)
+    }
+
+    fn is_close_bracket(&self, lexeme_type: &LexemeType) -> bool {
+        self.bracket_pairs.iter().any(|(_, close)| close == lexeme_type)
+    }
+}
+
+/// An error found in the indentation of a stream of lexemes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndentationError {
+    /// A line was dedented to an indentation that does not match any enclosing block.
+    InconsistentDedent {
+        /// The (1-based) number of the dedented line.
+        line: usize,
+
+        /// The width of the line's indentation.
+        indentation: usize,
+    },
+
+    /// Without [tab stops](IndentationRules::set_tab_stop), a line's indentation mixes tabs and
+    /// spaces in a way that's inconsistent with its enclosing blocks, so whether it's indented
+    /// relative to them is ambiguous.
+    InconsistentTabs {
+        /// The (1-based) number of the line.
+        line: usize,
+    },
+}
+
+impl Display for IndentationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndentationError::InconsistentDedent { line, indentation } => write!(
+                f,
+                "line {} is dedented to width {}, which does not match any enclosing block",
+                line, indentation
+            ),
+            IndentationError::InconsistentTabs { line } => write!(
+                f,
+                "line {} mixes tabs and spaces inconsistently with its enclosing blocks",
+                line
+            ),
+        }
+    }
+}
+
+/// A stream of lexemes with synthesized indentation lexemes (see [IndentationRules]).
+///
+/// If an [IndentationError] is found, the stream ends early, and the error is available through
+/// [IndentedLexemes::error]. To check for errors after feeding the stream to a consumer, pass it
+/// by a mutable reference.
+pub struct IndentedLexemes<'a, LexemeType, Lexemes> {
+    rules: &'a IndentationRules<LexemeType>,
+    lexemes: Lexemes,
+    pending_lexemes: VecDeque<Lexeme<LexemeType>>,
+
+    // The leading white space of the enclosing blocks' lines
+    indentation_stack: Vec<String>,
+
+    // The leading white space of the current line
+    current_indentation: String,
+
+    is_line_blank: bool,
+    bracket_depth: usize,
+    line: usize,
+    error: Option<IndentationError>,
+    is_exhausted: bool,
+}
+
+impl<'a, LexemeType, Lexemes> IndentedLexemes<'a, LexemeType, Lexemes>
+where
+    LexemeType: Eq + Clone,
+    Lexemes: Iterator<Item=Lexeme<LexemeType>>,
+{
+    /// Gets the error that ended the stream, if any.
+    pub fn error(&self) -> Option<&IndentationError> {
+        self.error.as_ref()
+    }
+
+    // Queues the lexemes that result from the next input lexeme
+    fn process(&mut self, lexeme: Lexeme<LexemeType>) {
+        let line = self.line;
+        self.line += lexeme.contents.bytes().filter(|&byte| byte == b'\n').count();
+
+        if self.rules.is_end_of_input(&lexeme.lexeme_type) {
+            self.conclude();
+            self.is_line_blank = true;
+            self.pending_lexemes.push_back(lexeme);
+        } else if lexeme.lexeme_type == self.rules.newline {
+            if !self.is_line_blank && self.bracket_depth == 0 {
+                self.pending_lexemes
+                    .push_back(Lexeme::new(self.rules.newline.clone(), &lexeme.contents));
+                self.is_line_blank = true;
+            }
+            if self.is_line_blank {
+                self.current_indentation.clear();
+            }
+        } else if self.rules.is_ignored(&lexeme.lexeme_type) {
+            if self.is_line_blank && lexeme.lexeme_type == self.rules.white_space {
+                self.current_indentation.push_str(&lexeme.contents);
+            }
+        } else {
+            if self.is_line_blank {
+                self.is_line_blank = false;
+                self.indent_line(line);
+            }
+            if self.rules.is_open_bracket(&lexeme.lexeme_type) {
+                self.bracket_depth += 1;
+            } else if self.rules.is_close_bracket(&lexeme.lexeme_type) {
+                self.bracket_depth = self.bracket_depth.saturating_sub(1);
+            }
+            self.pending_lexemes.push_back(lexeme);
+        }
+    }
+
+    // Queues the lexemes that mark the change to the indentation of the line starting a new
+    // logical line
+    fn indent_line(&mut self, line: usize) {
+        let rules = self.rules;
+        let indentation = rules.measure(&self.current_indentation);
+        let enclosing_indentation = self.enclosing_indentation();
+
+        if indentation > rules.measure(enclosing_indentation) {
+            if rules.tab_stop.is_none()
+                && !self.current_indentation.starts_with(enclosing_indentation)
+            {
+                self.error = Some(IndentationError::InconsistentTabs { line });
+            }
+            self.pending_lexemes
+                .push_back(Lexeme::new(rules.indent.clone(), &self.current_indentation));
+            self.indentation_stack
+                .push(std::mem::take(&mut self.current_indentation));
+        } else {
+            while indentation < rules.measure(self.enclosing_indentation()) {
+                self.indentation_stack.pop();
+                self.pending_lexemes
+                    .push_back(Lexeme::new(rules.dedent.clone(), ""));
+            }
+            let enclosing_indentation = self.enclosing_indentation();
+            if rules.measure(enclosing_indentation) != indentation {
+                self.error = Some(IndentationError::InconsistentDedent { line, indentation });
+            } else if rules.tab_stop.is_none() && enclosing_indentation != self.current_indentation
+            {
+                self.error = Some(IndentationError::InconsistentTabs { line });
+            }
+            self.current_indentation.clear();
+        }
+    }
+
+    // The top-level indentation is never popped, as no indentation is narrower
+    fn enclosing_indentation(&self) -> &str {
+        self.indentation_stack
+            .last()
+            .expect("Indentation stack should always hold the top-level indentation")
+    }
+
+    // Queues the lexemes that close the stream
+    fn conclude(&mut self) {
+        if !self.is_line_blank {
+            self.pending_lexemes
+                .push_back(Lexeme::new(self.rules.newline.clone(), ""));
+        }
+        while self.indentation_stack.len() > 1 {
+            self.indentation_stack.pop();
+            self.pending_lexemes
+                .push_back(Lexeme::new(self.rules.dedent.clone(), ""));
+        }
+    }
+}
+
+impl<'a, LexemeType, Lexemes> Iterator for IndentedLexemes<'a, LexemeType, Lexemes>
+where
+    LexemeType: Eq + Clone,
+    Lexemes: Iterator<Item=Lexeme<LexemeType>>,
+{
+    type Item = Lexeme<LexemeType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending_lexemes.is_empty() {
+            if self.error.is_some() || self.is_exhausted {
+                return None;
+            }
+            match self.lexemes.next() {
+                Some(lexeme) => self.process(lexeme),
+                None => {
+                    self.is_exhausted = true;
+                    self.conclude();
+                }
+            }
+        }
+        if self.error.is_some() {
+            self.pending_lexemes.clear();
+            return None;
+        }
+        self.pending_lexemes.pop_front()
+    }
+}
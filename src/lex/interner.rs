@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::Rc;
+
+/// A compact identifier of contents interned in an [Interner].
+///
+/// Atoms of the same interner are equal if and only if the contents they stand for are equal, so
+/// they can be compared and hashed in constant time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Atom(u32);
+
+impl Atom {
+    /// The atom's index in its interner: atoms are numbered sequentially (starting from 0), in
+    /// the order in which their contents were first interned.
+    ///
+    /// This is useful for storing data about atoms in plain vectors.
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A table of distinct lexeme contents (strings, for text), each stored once and identified by
+/// an [Atom].
+///
+/// A lexical analyzer may intern the contents of lexemes of chosen types into an interner shared
+/// with the rest of the parser (see
+/// [LexicalAnalyzer::analyze_interned](crate::lex::LexicalAnalyzer::analyze_interned)).
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { Identifier, WhiteSpace }
+/// let mut interner = Interner::new();
+/// let mut builder = LexicalAnalyzerBuilder::new(vec![
+///     LexemeDescriptor::new(MyLexemeType::Identifier, patterns::identifier()),
+///     LexemeDescriptor::new(MyLexemeType::WhiteSpace, Regex::white_space()),
+/// ]);
+/// builder.set_interned_lexeme_types(vec![MyLexemeType::Identifier]);
+/// let lexical_analyzer = builder.build();
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("x y x");
+/// let atoms: Vec<Atom> = lexical_analyzer
+///     .analyze_interned(input_text, &mut interner)
+///     .filter_map(|(_, atom)| atom)
+///     .collect();
+/// assert_eq!(atoms[0], atoms[2]);
+/// assert_ne!(atoms[0], atoms[1]);
+/// assert_eq!(interner.resolve(atoms[1]), "y");
+/// assert_eq!(interner.len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct Interner<Contents = String> {
+    atoms: HashMap<Rc<Contents>, Atom>,
+
+    // Indexed by the atoms
+    contents: Vec<Rc<Contents>>,
+}
+
+impl<Contents> Interner<Contents>
+where
+    Contents: Hash + Eq + Clone,
+{
+    /// Creates a new, empty, [Interner].
+    pub fn new() -> Self {
+        Self {
+            atoms: HashMap::new(),
+            contents: Vec::new(),
+        }
+    }
+
+    /// Gets the atom identifying the specified contents, storing them in the table if they're
+    /// not already there.
+    ///
+    /// # Panics
+    ///
+    /// If more than 2<sup>32</sup> distinct contents are interned.
+    pub fn intern(&mut self, contents: &Contents) -> Atom {
+        if let Some(&atom) = self.atoms.get(contents) {
+            return atom;
+        }
+        let atom = Atom(
+            self.contents
+                .len()
+                .try_into()
+                .expect("Interners cannot hold more than 2^32 distinct contents"),
+        );
+        let contents = Rc::new(contents.clone());
+        self.atoms.insert(contents.clone(), atom);
+        self.contents.push(contents);
+        atom
+    }
+
+    /// Gets the atom identifying the specified contents, if they were interned.
+    pub fn get(&self, contents: &Contents) -> Option<Atom> {
+        self.atoms.get(contents).copied()
+    }
+
+    /// Gets the contents identified by the specified atom.
+    ///
+    /// # Panics
+    ///
+    /// If the atom was not created by this interner.
+    pub fn resolve(&self, atom: Atom) -> &Contents {
+        self.contents
+            .get(atom.index())
+            .expect("Atom should have been created by the interner resolving it")
+    }
+
+    /// The number of distinct contents interned.
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    /// Checks whether no contents were interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+}
+
+impl<Contents> Default for Interner<Contents>
+where
+    Contents: Hash + Eq + Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,27 +1,131 @@
+use crate::lex::Alphabet;
 use crate::lex::regex::Regex;
 
 /// Describes a category of lexemes with similar syntactic meanings.
 ///
 /// This is used as part of a lexical-analyzer's specification, as it is built to recognize
 /// different types of lexemes.
-pub struct LexemeDescriptor<LexemeType> {
+pub struct LexemeDescriptor<LexemeType, Symbol = u8> {
     /// The type of lexemes being described.
     pub lexeme_type: LexemeType,
 
     /// A regular-expression pattern that matches the lexemes of the specified type.
-    pub pattern: Regex,
+    pub pattern: Regex<Symbol>,
+
+    // Set by with_priority
+    pub(super) priority: i32,
+
+    // Set by nested_region
+    pub(super) closing_pattern: Option<Regex<Symbol>>,
 }
 
-impl<LexemeType> LexemeDescriptor<LexemeType> {
+impl<LexemeType, Symbol> LexemeDescriptor<LexemeType, Symbol> {
     /// Creates a [LexemeDescriptor] describing the specified `lexeme_type` with the specified
     ///`pattern`.
-    pub fn new(lexeme_type: LexemeType, pattern: Regex) -> Self {
+    pub fn new(lexeme_type: LexemeType, pattern: Regex<Symbol>) -> Self {
         LexemeDescriptor {
             lexeme_type,
             pattern,
+            priority: 0,
+            closing_pattern: None,
         }
     }
 
+    /// Creates a [LexemeDescriptor] describing regions delimited by matches of `opening` and
+    /// `closing`, which may be nested (such as Rust's block comments).
+    ///
+    /// A lexeme of this type starts when `opening` is matched (as any other lexeme would be), and
+    /// then extends up to its balancing match of `closing`. Within the region, the longest match of
+    /// `closing` or `opening` at each position respectively decreases or increases the nesting
+    /// depth (with `closing` prioritized), and any other symbol is skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Comment, Name, WhiteSpace }
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![
+    ///     LexemeDescriptor::nested_region(
+    ///         MyLexemeType::Comment,
+    ///         Regex::constant_string("/*"),
+    ///         Regex::constant_string("*/"),
+    ///     ),
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Name,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    ///     LexemeDescriptor::new(MyLexemeType::WhiteSpace, Regex::white_space()),
+    /// ]);
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("/* a /* b */ */ c");
+    /// assert_eq!(
+    ///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+    ///     vec![
+    ///         Lexeme::new(MyLexemeType::Comment, "/* a /* b */ */"),
+    ///         Lexeme::new(MyLexemeType::WhiteSpace, " "),
+    ///         Lexeme::new(MyLexemeType::Name, "c"),
+    ///     ],
+    /// );
+    /// ```
+    pub fn nested_region(
+        lexeme_type: LexemeType,
+        opening: Regex<Symbol>,
+        closing: Regex<Symbol>,
+    ) -> Self {
+        LexemeDescriptor {
+            closing_pattern: Some(closing),
+            ..Self::new(lexeme_type, opening)
+        }
+    }
+
+    /// Sets the descriptor's [priority](LexemeDescriptor::priority) (0 by default).
+    ///
+    /// # Example
+    ///
+    /// Keywords may be prioritized over identifiers, regardless of the order in which they're
+    /// listed:
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { If, Identifier }
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Identifier,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    ///     LexemeDescriptor::keyword(MyLexemeType::If, "if").with_priority(1),
+    /// ]);
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("if");
+    /// assert_eq!(
+    ///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<MyLexemeType>>>(),
+    ///     vec![Lexeme::new(MyLexemeType::If, "if")],
+    /// );
+    /// ```
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The priority of this descriptor over others matching the same lexeme (higher wins).
+    ///
+    /// Descriptors of equal priority are prioritized by their order.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// If this descriptor describes nested regions (see [LexemeDescriptor::nested_region]), the
+    /// pattern that closes them.
+    pub fn closing_pattern(&self) -> Option<&Regex<Symbol>> {
+        self.closing_pattern.as_ref()
+    }
+}
+
+impl<LexemeType> LexemeDescriptor<LexemeType> {
     /// Creates a new [LexemeDescriptor] that describes a keyword.
     ///
     /// A keyword is a type of lexeme that only matches some hard-coded string (such as `if` or
@@ -66,12 +170,15 @@ impl<LexemeType> LexemeDescriptor<LexemeType> {
 /// the lexical analysis phase of the syntax-parsing pipeline. They represent atomic units of
 /// syntactic meaning.
 #[derive(Debug, PartialEq, Eq)]
-pub struct Lexeme<LexemeType> {
+pub struct Lexeme<LexemeType, Symbol = u8>
+where
+    Symbol: Alphabet,
+{
     /// The type (category) of the lexeme.
     pub lexeme_type: LexemeType,
 
-    /// The original text that constituted the lexeme.
-    pub contents: String,
+    /// The original text that constituted the lexeme (see [Alphabet::Contents]).
+    pub contents: Symbol::Contents,
 }
 
 impl<LexemeType> Lexeme<LexemeType> {
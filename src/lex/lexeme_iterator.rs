@@ -1,42 +1,157 @@
+use std::collections::VecDeque;
 use std::hash::Hash;
 
-use crate::lex::Lexeme;
-use crate::lex::lexical_analyzer::LexicalAnalyzer;
+use crate::lex::{Alphabet, Captures, Lexeme};
+use crate::lex::lexical_analyzer::{AnalysisState, CapturedLexeme, LexicalAnalyzer};
 use crate::readers::Reader;
 
-pub struct LexemeIterator<'a, LexemeType, ReaderStruct>
+/// The stream of lexemes extracted from some input by a [LexicalAnalyzer] (see
+/// [LexicalAnalyzer::analyze]).
+///
+/// Besides being an [Iterator], the stream supports looking ahead at upcoming lexemes, and
+/// returning lexemes to it. Lexemes are only analyzed when they are needed, and only the lexemes
+/// looked at (or pushed back) but not yet consumed are buffered.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// # enum MyLexemeType { Name, LeftParen, RightParen }
+/// let lexical_analyzer = LexicalAnalyzer::new(vec![
+///     LexemeDescriptor::new(
+///         MyLexemeType::Name,
+///         Regex::plus_from(Regex::character_range('a', 'z')),
+///     ),
+///     LexemeDescriptor::special_char(MyLexemeType::LeftParen, '('),
+///     LexemeDescriptor::special_char(MyLexemeType::RightParen, ')'),
+/// ]);
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("(int)x");
+/// let mut lexemes = lexical_analyzer.analyze(input_text);
+///
+/// // A parenthesized name followed by another name is a cast
+/// let is_cast = lexemes.peek_nth(2).map(|lexeme| &lexeme.lexeme_type)
+///     == Some(&MyLexemeType::RightParen)
+///     && lexemes.peek_nth(3).map(|lexeme| &lexeme.lexeme_type) == Some(&MyLexemeType::Name);
+/// assert!(is_cast);
+///
+/// let left_paren = lexemes.next().unwrap();
+/// assert_eq!(lexemes.peek(), Some(&Lexeme::new(MyLexemeType::Name, "int")));
+/// lexemes.push_back(left_paren);
+/// assert_eq!(lexemes.count(), 4);
+/// ```
+pub struct LexemeIterator<'a, LexemeType, ReaderType, Symbol = u8>
 where
-    ReaderStruct: Reader<u8>,
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
     LexemeType: Clone,
 {
-    lexical_analyzer: &'a LexicalAnalyzer<LexemeType>,
-    reader: &'a mut ReaderStruct,
+    lexical_analyzer: &'a LexicalAnalyzer<LexemeType, Symbol>,
+    reader: &'a mut ReaderType,
+    analysis_state: AnalysisState<Symbol>,
+
+    // Lexemes that were peeked at or pushed back (with no captures), along with their captures, to
+    // be yielded before analyzing any further
+    lookahead_lexemes: VecDeque<CapturedLexeme<LexemeType, Symbol>>,
+
+    // The lexemes yielded after the input was exhausted, once it is
+    trailing_lexemes: Option<VecDeque<Lexeme<LexemeType, Symbol>>>,
 }
 
-impl<'a, LexemeType, ReaderType> LexemeIterator<'a, LexemeType, ReaderType>
+impl<'a, LexemeType, ReaderType, Symbol> LexemeIterator<'a, LexemeType, ReaderType, Symbol>
 where
-    ReaderType: Reader<u8>,
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
     LexemeType: Clone,
 {
-    pub fn new(
-        lexical_analyzer: &'a LexicalAnalyzer<LexemeType>,
+    pub(super) fn new(
+        lexical_analyzer: &'a LexicalAnalyzer<LexemeType, Symbol>,
         reader: &'a mut ReaderType,
     ) -> Self {
         Self {
             lexical_analyzer,
             reader,
+            analysis_state: AnalysisState::new(lexical_analyzer),
+            lookahead_lexemes: VecDeque::new(),
+            trailing_lexemes: None,
+        }
+    }
+
+    /// Returns a lexeme to the stream, so that it is the next one yielded.
+    ///
+    /// Lexemes pushed back consecutively are yielded in the reverse order of their pushing, so
+    /// pushing back consumed lexemes in reverse order restores the original stream.
+    pub fn push_back(&mut self, lexeme: Lexeme<LexemeType, Symbol>) {
+        self.lookahead_lexemes
+            .push_front((lexeme, Captures::default()));
+    }
+}
+
+impl<'a, LexemeType, ReaderType, Symbol> LexemeIterator<'a, LexemeType, ReaderType, Symbol>
+where
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
+    LexemeType: Hash + Clone + Eq,
+{
+    /// Gets the next lexeme in the stream, without consuming it.
+    pub fn peek(&mut self) -> Option<&Lexeme<LexemeType, Symbol>> {
+        self.peek_nth(0)
+    }
+
+    /// Gets the lexeme `n` positions ahead in the stream (where `0` is the next lexeme), without
+    /// consuming any lexemes.
+    ///
+    /// Returns [None] if the stream ends before that lexeme.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Lexeme<LexemeType, Symbol>> {
+        while self.lookahead_lexemes.len() <= n {
+            let captured_lexeme = self.analyze_next_lexeme()?;
+            self.lookahead_lexemes.push_back(captured_lexeme);
+        }
+        self.lookahead_lexemes.get(n).map(|(lexeme, _)| lexeme)
+    }
+
+    // Consumes the next lexeme in the stream, along with its captures (see
+    // LexicalAnalyzer::analyze_captured)
+    pub(super) fn next_captured(&mut self) -> Option<CapturedLexeme<LexemeType, Symbol>> {
+        match self.lookahead_lexemes.pop_front() {
+            Some(captured_lexeme) => Some(captured_lexeme),
+            None => self.analyze_next_lexeme(),
+        }
+    }
+
+    fn analyze_next_lexeme(&mut self) -> Option<CapturedLexeme<LexemeType, Symbol>> {
+        if self.trailing_lexemes.is_none() {
+            while let Some((lexeme, captures)) = self
+                .lexical_analyzer
+                .collect_next_lexeme(self.reader, &mut self.analysis_state)
+            {
+                if !self.lexical_analyzer.is_skipped(&lexeme.lexeme_type) {
+                    return Some((lexeme, captures));
+                }
+            }
+            self.trailing_lexemes = Some(
+                self.lexical_analyzer
+                    .conclude_analysis(&self.analysis_state)
+                    .into(),
+            );
         }
+        let lexeme = self.trailing_lexemes.as_mut()?.pop_front()?;
+        Some((lexeme, Captures::default()))
     }
 }
 
-impl<'a, LexemeType, ReaderType> Iterator for LexemeIterator<'a, LexemeType, ReaderType>
+impl<'a, LexemeType, ReaderType, Symbol> Iterator
+for LexemeIterator<'a, LexemeType, ReaderType, Symbol>
 where
-    ReaderType: Reader<u8>,
+    ReaderType: Reader<Symbol>,
+    Symbol: Alphabet,
     LexemeType: Hash + Clone + Eq,
 {
-    type Item = Lexeme<LexemeType>;
+    type Item = Lexeme<LexemeType, Symbol>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.lexical_analyzer.collect_next_lexeme(self.reader)
+        self.next_captured().map(|(lexeme, _)| lexeme)
     }
 }
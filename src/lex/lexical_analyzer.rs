@@ -1,182 +1,1003 @@
-use std::collections::HashMap;
+use std::fmt::Debug;
 use std::hash::Hash;
+use std::ops::Range;
 
-use crate::automata::dfa::Dfa;
-use crate::automata::nfa::Nfa;
-use crate::handles::specials::AutomaticallyHandled;
-use crate::lex::{Lexeme, LexemeDescriptor};
+use crate::lex::{Alphabet, Atom, Interner, Lexeme, LexemeDescriptor, LexerMode};
+use crate::lex::LexicalAnalyzerBuilder;
+use crate::lex::ModeAction;
+use crate::lex::automaton::{LexerAutomaton, LexerDfa, LexerAutomatonScan};
+use crate::lex::build::{NESTED_REGION_CLOSING, NESTED_REGION_OPENING};
+use crate::lex::captures::{CaptureAutomaton, CaptureScan, Captures};
+use crate::lex::end_of_input::{EndOfInput, EndOfInputAction};
+use crate::lex::include_stack::IncludingLexemeIterator;
+use crate::lex::incremental::LexemeList;
+use crate::lex::failure_memo::FailureMemo;
 use crate::lex::lexeme_iterator::LexemeIterator;
+use crate::lex::dot::{dfa_to_dot, nfa_to_dot};
+use crate::lex::regex::{Anchor, Regex};
+use crate::lex::trace::{TraceEvent, TraceHook};
 use crate::readers::Reader;
 
-impl AutomaticallyHandled for u8 {
-    type HandleCoreType = u8;
-    fn serial(&self) -> usize {
-        *self as usize
-    }
-}
-
 /// A lexical analyzer.
 ///
 /// A lexical analyzer is a computation unit (an automaton), that is capable of reading a stream
 /// of characters, and separating it into [Lexeme]s: atomic sequences units of meaningful text,
 /// tokens. See [crate::lex] for more detail.
-pub struct LexicalAnalyzer<LexemeType> {
-    dfa: Dfa<u8, LexemeType>,
+///
+/// The analyzed stream consists of bytes by default, and may consist of the symbols of any other
+/// [Alphabet].
+pub struct LexicalAnalyzer<LexemeType, Symbol = u8>
+where
+    Symbol: Alphabet,
+{
+    // Indexed by the modes' identifiers
+    pub(super) modes: Vec<AnalyzerMode<LexemeType, Symbol>>,
+
+    pub(super) is_linear_time: bool,
+    pub(super) end_of_input_lexeme_type: Option<LexemeType>,
+    pub(super) end_of_input_action: Option<EndOfInputAction<LexemeType, Symbol>>,
+    pub(super) interned_lexeme_types: Vec<LexemeType>,
+    pub(super) skipped_lexeme_types: Vec<LexemeType>,
+    pub(super) trace_hook: Option<TraceHook<LexemeType, Symbol>>,
 }
 
-// Earlier lexeme descriptors are prioritized
-impl<LexemeType> LexicalAnalyzer<LexemeType>
+/// The compiled specifications of a [LexerMode].
+pub(super) struct AnalyzerMode<LexemeType, Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) automaton: LexerAutomaton<Symbol>,
+
+    // Indexed by the labels of the automaton's states
+    pub(super) lexeme_types: Vec<LexemeType>,
+    pub(super) nested_region_automata: Vec<Option<LexerAutomaton<Symbol>>>,
+    pub(super) capture_automaton: Option<CaptureAutomaton<Symbol>>,
+    pub(super) actions: Vec<Option<ModeAction>>,
+
+    // Kept for visualizing the automaton
+    pub(super) patterns: Vec<Regex<Symbol>>,
+}
+
+impl<LexemeType, Symbol> LexicalAnalyzer<LexemeType, Symbol>
 where
     LexemeType: Hash + Eq + Clone,
+    Symbol: Alphabet,
 {
     /// Builds a new [LexicalAnalyzer].
     ///
     /// The different `LexemeType`s that the analyzer will be capable of recognizing are described
-    /// by `lexeme_descriptors`.
+    /// by `lexeme_descriptors`. Check out [LexicalAnalyzerBuilder] for additional settings.
+    ///
+    /// # Panics
+    ///
+    /// If some lexeme type is associated with a regex accepting the empty string.
     pub fn new(
-        lexeme_descriptors: Vec<LexemeDescriptor<LexemeType>>,
-    ) -> LexicalAnalyzer<LexemeType> {
-        let mut nfa = Nfa::new();
-        let global_start_state = nfa.new_state();
-        nfa.set_initial_state(global_start_state);
-
-        let mut priority_map = HashMap::new();
-
-        for (
-            priority,
-            LexemeDescriptor {
-                pattern,
-                lexeme_type,
-            },
-        ) in lexeme_descriptors.iter().enumerate()
-        {
-            let (pattern_start_state, pattern_end_state) = pattern.build_into_nfa(&mut nfa);
-            nfa.link(global_start_state, pattern_start_state, None);
-            nfa.label(pattern_end_state, Some(lexeme_type.clone()));
-
-            priority_map.insert(lexeme_type, priority);
-        }
-
-        let dfa = nfa
-            .compile_to_dfa(|labels| {
-                labels
-                    .iter()
-                    .min_by_key(|&&lexeme_type| priority_map.get(lexeme_type))
-                    .cloned()
-                    .cloned()
-            })
-            .minimize();
-
-        // Make initial state is unlabeled, so we won't get stuck on epsilon when input is exhausted
-        let initial_state = dfa.get_initial_state().expect(
-            "Minimized DFA should have an initial state, as the associated NFA had one, and \
-            the initial state ",
-        );
-        if !dfa.get_label(initial_state).is_none() {
-            panic!(
-                "Tried to create a lexical analyzer where some lexeme type is associated with a \
-                regex accepting the empty string, which will make it get stuck on input exhaustion"
-            )
-        }
-
-        LexicalAnalyzer { dfa }
+        lexeme_descriptors: Vec<LexemeDescriptor<LexemeType, Symbol>>,
+    ) -> LexicalAnalyzer<LexemeType, Symbol> {
+        LexicalAnalyzerBuilder::new(lexeme_descriptors).build()
     }
 
     /// Parses a stream of input text specified by a `reader`, and yields the lexemes it is consists
     /// of.
     ///
+    /// The lexemes are analyzed lazily, as they are consumed from the returned [LexemeIterator],
+    /// which also supports looking ahead at upcoming lexemes.
+    ///
     /// # Conflict Resolution
     ///
     /// - Longer lexemes are prioritized.
     /// - Among the matching `LexemeType`s for a lexeme of a given length, priority is given to the
-    ///     `LexemeType` whose [LexemeDescriptor] was listed first during the [LexicalAnalyzer]'s
-    ///     construction (see the `lexeme_descriptors` argument of [LexicalAnalyzer::new]).
+    ///     `LexemeType` whose [LexemeDescriptor] has the highest [LexemeDescriptor::priority], and
+    ///     then to the one listed first (see the `lexeme_descriptors` of [LexicalAnalyzer::new]).
+    ///
+    /// # Complexity
+    ///
+    /// Finding the longest lexeme may require reading past its end, and rewinding back to it.
+    /// Some specifications make this quadratic in the length of the input (for example, lexemes
+    /// `a` and `a*b` on input `aaa...a`). If the input is untrusted, consider building the analyzer
+    /// with [LexicalAnalyzerBuilder::set_linear_time_tokenization].
+    ///
+    /// # End of Input
+    ///
+    /// Once the input is exhausted, the lexemes returned by the analyzer's end-of-input action are
+    /// yielded, followed by its end-of-input lexeme (see
+    /// [LexicalAnalyzerBuilder::set_end_of_input_action] and
+    /// [LexicalAnalyzerBuilder::set_end_of_input_lexeme]), if these were set.
     ///
     /// # Panics
     ///
-    /// If no known `LexemeType` could be matched against a prefix of the remaining input.
+    /// If no known `LexemeType` could be matched against a prefix of the remaining input, or if the
+    /// input ends inside a nested region (see [LexemeDescriptor::nested_region]). The panic message
+    /// reports the offset (in symbols) where the unterminated region starts.
     ///
-    pub fn analyze<'a>(
+    pub fn analyze<'a, ReaderType>(
         &'a self,
-        reader: &'a mut impl Reader<u8>,
-    ) -> impl Iterator<Item=Lexeme<LexemeType>> + 'a {
+        reader: &'a mut ReaderType,
+    ) -> LexemeIterator<'a, LexemeType, ReaderType, Symbol>
+    where
+        ReaderType: Reader<Symbol>,
+    {
         LexemeIterator::new(self, reader)
     }
 
-    fn identify_next_lexeme(
+    /// Parses a stream of input text specified by a `reader`, and yields the lexemes it consists
+    /// of, along with the [Atom]s identifying their contents in `interner`, for lexemes of the
+    /// interned types (see [LexicalAnalyzerBuilder::set_interned_lexeme_types]).
+    ///
+    /// The interner is only borrowed for the duration of the analysis, so it may be shared by
+    /// consecutive analyses, and with the rest of the parser (such as its symbol tables). See
+    /// [Interner] for an example, and [LexicalAnalyzer::analyze] for the rules of the analysis.
+    pub fn analyze_interned<'a, ReaderType>(
+        &'a self,
+        reader: &'a mut ReaderType,
+        interner: &'a mut Interner<Symbol::Contents>,
+    ) -> impl Iterator<Item = (Lexeme<LexemeType, Symbol>, Option<Atom>)> + 'a
+    where
+        ReaderType: Reader<Symbol>,
+    {
+        self.analyze(reader).map(move |lexeme| {
+            let atom = if self.interned_lexeme_types.contains(&lexeme.lexeme_type) {
+                Some(interner.intern(&lexeme.contents))
+            } else {
+                None
+            };
+            (lexeme, atom)
+        })
+    }
+
+    /// Parses a stream of input text specified by a `reader`, and yields the lexemes it consists
+    /// of, along with the [Captures] of the named capture groups of their patterns (see
+    /// [Regex::capture](crate::lex::Regex::capture)).
+    ///
+    /// Lexemes whose patterns contain no capture groups, as well as the lexemes yielded once the
+    /// input is exhausted, have no captures. See [LexicalAnalyzer::analyze] for the rules of the
+    /// analysis.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Assignment }
+    /// let name = || Regex::plus_from(Regex::character_range('a', 'z'));
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![LexemeDescriptor::new(
+    ///     MyLexemeType::Assignment,
+    ///     Regex::concat(vec![
+    ///         Regex::capture("target", name()),
+    ///         Regex::single_char('='),
+    ///         Regex::capture("value", name()),
+    ///     ]),
+    /// )]);
+    ///
+    /// let input_text = &mut ByteArrayReader::from_string_slice("ab=cde");
+    /// let (lexeme, captures) = lexical_analyzer.analyze_captured(input_text).next().unwrap();
+    /// assert_eq!(&lexeme.contents[captures.get("target").unwrap()], "ab");
+    /// assert_eq!(&lexeme.contents[captures.get("value").unwrap()], "cde");
+    /// ```
+    pub fn analyze_captured<'a, ReaderType>(
+        &'a self,
+        reader: &'a mut ReaderType,
+    ) -> impl Iterator<Item = (Lexeme<LexemeType, Symbol>, Captures)> + 'a
+    where
+        ReaderType: Reader<Symbol>,
+    {
+        let mut lexemes = self.analyze(reader);
+        std::iter::from_fn(move || lexemes.next_captured())
+    }
+
+    /// Parses a stream of input text specified by a `reader`, which may include other streams of
+    /// input text, and yields the lexemes they consist of, along with their
+    /// [Span](crate::lex::Span)s.
+    ///
+    /// Each lexeme found is passed to `include_action`, along with its [Captures] (see
+    /// [LexicalAnalyzer::analyze_captured]). If it returns a reader, the analysis continues in
+    /// that reader's input, and resumes after the lexeme once it is exhausted. Included sources
+    /// may include others in turn. Each source is analyzed from
+    /// [LexerMode::INITIAL], and the end-of-input action and lexeme (see
+    /// [LexicalAnalyzerBuilder::set_end_of_input_action]) are only applied at the end of the
+    /// outermost source, with an empty span at its end.
+    ///
+    /// Sources are identified in the yielded spans by [SourceId](crate::lex::SourceId)s, assigned
+    /// sequentially in the order of their inclusion, starting from the root for the outermost one.
+    ///
+    /// See [LexicalAnalyzer::analyze] for the rules of the analysis.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # use syntax_parser_generator::readers::ByteArrayReader;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Name, Include, Space }
+    /// let name = || Regex::plus_from(Regex::character_range('a', 'z'));
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![
+    ///     LexemeDescriptor::new(MyLexemeType::Name, name()),
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Include,
+    ///         Regex::concat(vec![
+    ///             Regex::constant_string("include \""),
+    ///             Regex::capture("path", name()),
+    ///             Regex::single_char('"'),
+    ///         ]),
+    ///     ),
+    ///     LexemeDescriptor::new(MyLexemeType::Space, Regex::white_space()),
+    /// ]);
+    ///
+    /// let files = [("first", "a include \"second\" d"), ("second", "b c")];
+    /// let open = |path: &str| {
+    ///     let (_, contents) = files.iter().find(|(name, _)| *name == path)?;
+    ///     Some(ByteArrayReader::from_string_slice(contents))
+    /// };
+    /// let lexemes =
+    ///     lexical_analyzer.analyze_with_includes(open("first").unwrap(), |lexeme, captures| {
+    ///         open(&lexeme.contents[captures.get("path")?])
+    ///     });
+    /// let names: Vec<(String, Span)> = lexemes
+    ///     .filter(|(lexeme, _)| lexeme.lexeme_type == MyLexemeType::Name)
+    ///     .map(|(lexeme, span)| (lexeme.contents, span))
+    ///     .collect();
+    /// assert_eq!(
+    ///     names,
+    ///     vec![
+    ///         (String::from("a"), Span { source: SourceId::ROOT, start: 0, end: 1 }),
+    ///         (String::from("b"), Span { source: SourceId(1), start: 0, end: 1 }),
+    ///         (String::from("c"), Span { source: SourceId(1), start: 2, end: 3 }),
+    ///         (String::from("d"), Span { source: SourceId::ROOT, start: 19, end: 20 }),
+    ///     ],
+    /// );
+    /// ```
+    pub fn analyze_with_includes<ReaderType, IncludeAction>(
         &self,
-        reader: &mut impl Reader<u8>,
-    ) -> LexemeIdentificationResult<LexemeType> {
-        let mut recent_lexeme_type: Option<LexemeType> = None;
-        let mut current_state = self.dfa.get_initial_state();
+        reader: ReaderType,
+        include_action: IncludeAction,
+    ) -> IncludingLexemeIterator<'_, LexemeType, ReaderType, IncludeAction, Symbol>
+    where
+        ReaderType: Reader<Symbol>,
+        IncludeAction: FnMut(&Lexeme<LexemeType, Symbol>, &Captures) -> Option<ReaderType>,
+    {
+        IncludingLexemeIterator::new(self, reader, include_action)
+    }
 
-        let mut is_string_empty = true;
+    /// Analyzes the input, and collects its lexemes into a [LexemeList], which can be efficiently
+    /// updated whenever the input is edited (see [LexicalAnalyzer::relex]).
+    ///
+    /// See [LexicalAnalyzer::analyze] for the rules of the analysis, except that it doesn't stop at
+    /// input that matches no lexeme (see [LexemeList]).
+    pub fn analyze_for_relexing(&self, input: &[Symbol]) -> LexemeList<LexemeType, Symbol> {
+        LexemeList::analyze(self, input)
+    }
 
-        loop {
-            match current_state {
-                None => break,
+    /// Updates the lexemes of some input after it was edited, and returns the range of the
+    /// indices of the updated lexemes in the list.
+    ///
+    /// `lexemes` should have been analyzed by this analyzer, from the input before the edit.
+    /// `edited_range` is the range of that input that was replaced, and `input` is the entire
+    /// input after the edit. Only the lexemes whose identification read the edited range are
+    /// analyzed again, until the analysis reaches the start of a lexeme following the edit in the
+    /// same state as before, from which on the previous lexemes are kept (with their ranges
+    /// shifted). The lexemes yielded once the input is exhausted are always collected again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { Name, Integer, Space }
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Name,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Integer,
+    ///         Regex::plus_from(Regex::character_range('0', '9')),
+    ///     ),
+    ///     LexemeDescriptor::new(MyLexemeType::Space, Regex::white_space()),
+    /// ]);
+    /// let mut lexemes = lexical_analyzer.analyze_for_relexing(b"ab 12 cd 34");
+    ///
+    /// // Replace "12" with "x"
+    /// let relexed = lexical_analyzer.relex(&mut lexemes, 3..5, b"ab x cd 34");
+    /// assert_eq!(relexed, 1..3);
+    /// assert_eq!(
+    ///     lexemes.get(2),
+    ///     Some((&Lexeme::new(MyLexemeType::Name, "x"), 3..4)),
+    /// );
+    /// assert_eq!(
+    ///     lexemes.get(6),
+    ///     Some((&Lexeme::new(MyLexemeType::Integer, "34"), 8..10)),
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `edited_range` is not within the previous input, or if `input` is shorter than the
+    /// previous input without the edited range.
+    pub fn relex(
+        &self,
+        lexemes: &mut LexemeList<LexemeType, Symbol>,
+        edited_range: Range<usize>,
+        input: &[Symbol],
+    ) -> Range<usize> {
+        lexemes.relex(self, edited_range, input)
+    }
 
-                Some(state) => {
-                    if let Some(lexeme_type) = self.dfa.get_label(state) {
-                        recent_lexeme_type = Some(lexeme_type.clone());
-                        reader.set_tail();
-                    }
+    fn identify_next_lexeme(
+        &self,
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> LexemeIdentificationResult<'_, LexemeType, Symbol> {
+        let mode = &self.modes[analysis_state.current_mode().0];
+        if let Some(trace_hook) = &self.trace_hook {
+            trace_hook(&TraceEvent::ScanStart {
+                mode: analysis_state.current_mode(),
+                position: analysis_state.position,
+            });
+        }
+        let trace = self.trace_hook.as_ref().map(|trace_hook| {
+            move |event: TraceEvent<usize, Symbol>| {
+                trace_hook(&event.map_label(&mode.lexeme_types))
+            }
+        });
+        let scan_result = scan_longest_prefix(
+            &mode.automaton,
+            mode.capture_automaton.as_ref(),
+            reader,
+            analysis_state.preceding_symbol,
+            analysis_state.failure_memo.as_mut(),
+            trace.as_ref().map(|trace| trace as &dyn Fn(_)),
+        );
+        if let Some(memo) = &mut analysis_state.failure_memo {
+            memo.conclude_scan(scan_result.accepted_length);
+        }
 
-                    match reader.read_next() {
-                        None => {
-                            break;
-                        }
-                        Some(next_byte) => {
-                            current_state = self.dfa.step(state, next_byte.handle());
-                            is_string_empty = false;
-                        }
+        if scan_result.scanned_length == 0 {
+            return LexemeIdentificationResult::InputExhausted;
+        }
+        match scan_result.label {
+            Some(descriptor_index) => {
+                let identified_lexeme = IdentifiedLexeme {
+                    lexeme_type: mode.lexeme_types[descriptor_index].clone(),
+                    descriptor_index,
+                    capture_automaton: mode.capture_automaton.as_ref(),
+                    tags: scan_result.accepted_tags,
+                };
+                analysis_state.preceding_symbol = scan_result.accepted_preceding_symbol;
+                if let Some(region_automaton) = &mode.nested_region_automata[descriptor_index] {
+                    let start = analysis_state.position;
+                    if !Self::scan_nested_region(region_automaton, reader, analysis_state) {
+                        return LexemeIdentificationResult::UnterminatedNestedRegion { start };
                     }
                 }
+                if let Some(action) = mode.actions[descriptor_index] {
+                    analysis_state.apply_mode_action(action, self);
+                }
+                LexemeIdentificationResult::Identified(identified_lexeme)
             }
-        }
 
-        return if is_string_empty {
-            LexemeIdentificationResult::InputExhausted
-        } else if let Some(lexeme_type) = recent_lexeme_type {
-            LexemeIdentificationResult::Identified(lexeme_type)
-        } else {
             // We read some data, but couldn't identify available prefix
-            LexemeIdentificationResult::LexicalError
-        };
+            None => LexemeIdentificationResult::LexicalError,
+        }
+    }
+
+    // Extends the lexeme that opened a nested region (which ends at the reader's tail) up to the
+    // end of the region, or up to the end of the input if the region is unterminated, in which
+    // case false is returned
+    fn scan_nested_region(
+        region_automaton: &LexerAutomaton<Symbol>,
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> bool {
+        let mut depth = 1;
+        let mut region_length = 0;
+        while depth > 0 {
+            reader.move_cursor_to_tail();
+            let scan_result = scan_longest_prefix(
+                region_automaton,
+                None,
+                reader,
+                analysis_state.preceding_symbol,
+                None,
+                None,
+            );
+            match scan_result.label {
+                Some(NESTED_REGION_CLOSING) => depth -= 1,
+                Some(NESTED_REGION_OPENING) => depth += 1,
+                Some(_) => {}
+                None => break,
+            }
+            region_length += scan_result.accepted_length;
+            analysis_state.preceding_symbol = scan_result.accepted_preceding_symbol;
+        }
+        if let Some(memo) = &mut analysis_state.failure_memo {
+            memo.skip(region_length);
+        }
+        depth == 0
     }
 
     pub(super) fn collect_next_lexeme(
         &self,
-        reader: &mut impl Reader<u8>,
-    ) -> Option<Lexeme<LexemeType>> {
-        let lexeme_type = loop {
-            match self.identify_next_lexeme(reader) {
-                LexemeIdentificationResult::Identified(lexeme_type) => break lexeme_type,
-                LexemeIdentificationResult::InputExhausted => return None,
-                LexemeIdentificationResult::LexicalError => {
-                    self.error_recovery_routine(reader);
-                }
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> Option<CapturedLexeme<LexemeType, Symbol>> {
+        loop {
+            match self.try_collect_next_lexeme(reader, analysis_state) {
+                Ok(lexeme) => return lexeme,
+                Err(LexicalError::UnrecognizedInput) => self.error_recovery_routine(reader),
+                Err(LexicalError::UnterminatedNestedRegion { start }) => panic!(
+                    "Reader had an unterminated nested region, starting at offset {}",
+                    start,
+                ),
+            }
+        }
+    }
+
+    // Collects the next lexeme, along with its captures, unless no lexeme matches a prefix of the
+    // remaining input, or the lexeme opens an unterminated nested region
+    pub(super) fn try_collect_next_lexeme(
+        &self,
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> Result<Option<CapturedLexeme<LexemeType, Symbol>>, LexicalError> {
+        let identified_lexeme = match self.identify_next_lexeme(reader, analysis_state) {
+            LexemeIdentificationResult::Identified(identified_lexeme) => identified_lexeme,
+            LexemeIdentificationResult::InputExhausted => return Ok(None),
+            LexemeIdentificationResult::LexicalError => {
+                return Err(LexicalError::UnrecognizedInput)
+            }
+            LexemeIdentificationResult::UnterminatedNestedRegion { start } => {
+                return Err(Self::skip_unterminated_nested_region(
+                    reader,
+                    analysis_state,
+                    start,
+                ))
             }
         };
 
-        let contents = String::from_utf8(reader.get_sequence().collect())
-            .expect("Tokens from lexically-analyzed Reader<u8> are expected to be UTF-8 encoded");
+        let contents: Vec<Symbol> = reader.get_sequence().collect();
+        let captures = match identified_lexeme.capture_automaton {
+            Some(capture_automaton) => capture_automaton
+                .captures(identified_lexeme.descriptor_index, &identified_lexeme.tags),
+            None => Captures::default(),
+        };
+        analysis_state.position += contents.len();
+        let contents = Symbol::to_contents(contents);
         let lexeme = Lexeme {
-            lexeme_type,
+            lexeme_type: identified_lexeme.lexeme_type,
             contents,
         };
         reader.restart_from_tail();
-        Some(lexeme)
+        Ok(Some((lexeme, captures)))
+    }
+
+    // Identifies the next lexeme, and returns its type, without collecting its contents (see
+    // try_collect_next_lexeme)
+    pub(super) fn try_identify_next_lexeme_type(
+        &self,
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> Result<Option<LexemeType>, LexicalError> {
+        let identified_lexeme = match self.identify_next_lexeme(reader, analysis_state) {
+            LexemeIdentificationResult::Identified(identified_lexeme) => identified_lexeme,
+            LexemeIdentificationResult::InputExhausted => return Ok(None),
+            LexemeIdentificationResult::LexicalError => {
+                return Err(LexicalError::UnrecognizedInput)
+            }
+            LexemeIdentificationResult::UnterminatedNestedRegion { start } => {
+                return Err(Self::skip_unterminated_nested_region(
+                    reader,
+                    analysis_state,
+                    start,
+                ))
+            }
+        };
+        analysis_state.position += reader.get_sequence().count();
+        reader.restart_from_tail();
+        Ok(Some(identified_lexeme.lexeme_type))
     }
 
-    fn error_recovery_routine(&self, _reader: &mut impl Reader<u8>) {
+    // Skips the rest of the input, once the lexeme at its start opened a nested region that isn't
+    // terminated before the input ends
+    fn skip_unterminated_nested_region(
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+        start: usize,
+    ) -> LexicalError {
+        analysis_state.position += reader.get_sequence().count();
+        reader.restart_from_tail();
+        LexicalError::UnterminatedNestedRegion { start }
+    }
+
+    // Skips the first symbol of the remaining input, once no lexeme matched a prefix of it (see
+    // try_collect_next_lexeme), and returns it
+    pub(super) fn skip_unrecognized_symbol(
+        &self,
+        reader: &mut impl Reader<Symbol>,
+        analysis_state: &mut AnalysisState<Symbol>,
+    ) -> Symbol {
+        reader.move_cursor_to_tail();
+        let symbol = reader
+            .read_next()
+            .expect("Unrecognized input should have at least one symbol");
+        reader.set_tail();
+        reader.restart_from_tail();
+        analysis_state.position += 1;
+        analysis_state.preceding_symbol = Some(symbol);
+        if let Some(memo) = &mut analysis_state.failure_memo {
+            memo.skip(1);
+        }
+        symbol
+    }
+
+    // Skipped lexemes are collected as usual, and dropped by the iterators that yield them
+    pub(super) fn is_skipped(&self, lexeme_type: &LexemeType) -> bool {
+        self.skipped_lexeme_types.contains(lexeme_type)
+    }
+
+    // Collects the lexemes yielded once the input is exhausted
+    pub(super) fn conclude_analysis(
+        &self,
+        analysis_state: &AnalysisState<Symbol>,
+    ) -> Vec<Lexeme<LexemeType, Symbol>> {
+        let mut lexemes = match &self.end_of_input_action {
+            Some(action) => action(&EndOfInput {
+                mode_stack: analysis_state.mode_stack.clone(),
+                position: analysis_state.position,
+            }),
+            None => Vec::new(),
+        };
+        if let Some(lexeme_type) = &self.end_of_input_lexeme_type {
+            lexemes.push(Lexeme {
+                lexeme_type: lexeme_type.clone(),
+                contents: Symbol::to_contents(Vec::new()),
+            });
+        }
+        lexemes
+    }
+
+    fn error_recovery_routine(&self, _reader: &mut impl Reader<Symbol>) {
         // TODO make this configurable
         panic!("Reader had a lexical error in it, and error recovery is not yet implemented");
     }
 }
 
-enum LexemeIdentificationResult<LexemeType> {
-    Identified(LexemeType),
+impl<LexemeType, Symbol> LexicalAnalyzer<LexemeType, Symbol>
+where
+    LexemeType: Hash + Eq + Clone + Debug,
+    Symbol: Alphabet,
+{
+    /// Renders the NFA compiled from the lexeme descriptors of the specified mode, in the
+    /// [DOT](https://graphviz.org/doc/info/lang.html) language of Graphviz.
+    ///
+    /// Accepting states are labeled by the lexeme types they match, and transitions by the
+    /// symbols they consume (see [Alphabet::describe]), where consecutive symbols are compacted
+    /// into ranges (such as `a-z`), epsilon transitions are labeled `ε`, and anchors are labeled
+    /// `^`, `$`, and `\z`. The patterns of nested regions
+    /// (see [LexemeDescriptor::nested_region]) are only rendered up to their opening.
+    ///
+    /// # Panics
+    ///
+    /// If the mode was not created for this analyzer, or if its patterns can't be compiled into an
+    /// NFA (see [Regex::intersection](crate::lex::Regex::intersection)).
+    pub fn nfa_to_dot(&self, mode: LexerMode) -> String {
+        let mode = &self.modes[mode.0];
+        let symbol_classes = &mode.automaton.symbol_classes;
+        let nfa = LexicalAnalyzerBuilder::<LexemeType, Symbol>::build_nfa(
+            &mode.patterns,
+            symbol_classes,
+        );
+        nfa_to_dot(&nfa, symbol_classes, &mode.lexeme_types)
+    }
+
+    /// Renders the minimized DFA that drives the analysis in the specified mode, in the
+    /// [DOT](https://graphviz.org/doc/info/lang.html) language of Graphviz.
+    ///
+    /// The graph is labeled as described in [LexicalAnalyzer::nfa_to_dot]. Each accepting state is
+    /// labeled by the lexeme type it identifies, which shows how overlapping patterns are
+    /// resolved. With [DfaConstruction::Lazy](crate::lex::DfaConstruction::Lazy), the entire DFA
+    /// is compiled for rendering.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::*;
+    /// # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+    /// # enum MyLexemeType { If, Name }
+    /// let lexical_analyzer = LexicalAnalyzer::new(vec![
+    ///     LexemeDescriptor::keyword(MyLexemeType::If, "if"),
+    ///     LexemeDescriptor::new(
+    ///         MyLexemeType::Name,
+    ///         Regex::plus_from(Regex::character_range('a', 'z')),
+    ///     ),
+    /// ]);
+    /// let dot = lexical_analyzer.minimized_dfa_to_dot(LexerMode::INITIAL);
+    /// assert!(dot.starts_with("digraph {"));
+    /// assert!(dot.contains("\\nIf\"]"));
+    /// assert!(dot.contains("[label=\"a-h, j-z\"]"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the mode was not created for this analyzer.
+    pub fn minimized_dfa_to_dot(&self, mode: LexerMode) -> String {
+        let mode = &self.modes[mode.0];
+        let symbol_classes = &mode.automaton.symbol_classes;
+        match &mode.automaton.dfa {
+            LexerDfa::Eager(dfa) => dfa_to_dot(dfa, symbol_classes, &mode.lexeme_types),
+            LexerDfa::Lazy(_) => {
+                let dfa = LexicalAnalyzerBuilder::<LexemeType, Symbol>::build_nfa(
+                    &mode.patterns,
+                    symbol_classes,
+                )
+                .compile_to_dfa(LexicalAnalyzerBuilder::<LexemeType, Symbol>::reduce_labels)
+                .minimize();
+                dfa_to_dot(&dfa, symbol_classes, &mode.lexeme_types)
+            }
+        }
+    }
+}
+
+/// The state of an ongoing analysis of some input, kept between the lexemes it yields.
+pub(super) struct AnalysisState<Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) failure_memo: Option<FailureMemo<Symbol>>,
+
+    // The symbol preceding the next lexeme, if any
+    pub(super) preceding_symbol: Option<Symbol>,
+
+    // The offset of the next lexeme from the beginning of the input
+    pub(super) position: usize,
+
+    // Never empty, as the initial mode is never popped
+    mode_stack: Vec<LexerMode>,
+}
+
+impl<Symbol> AnalysisState<Symbol>
+where
+    Symbol: Alphabet,
+{
+    pub(super) fn new<LexemeType>(lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>) -> Self {
+        let mut analysis_state = Self {
+            failure_memo: None,
+            preceding_symbol: None,
+            position: 0,
+            mode_stack: vec![LexerMode::INITIAL],
+        };
+        analysis_state.reset_failure_memo(lexical_analyzer);
+        analysis_state
+    }
+
+    // Resumes an analysis from the specified offset of the input, at which it was at the
+    // specified resume point
+    pub(super) fn resume<LexemeType>(
+        lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+        resume_point: &ResumePoint<Symbol>,
+        position: usize,
+    ) -> Self {
+        let mut analysis_state = Self {
+            failure_memo: None,
+            preceding_symbol: resume_point.preceding_symbol,
+            position,
+            mode_stack: resume_point.mode_stack.clone(),
+        };
+        analysis_state.reset_failure_memo(lexical_analyzer);
+        analysis_state
+    }
+
+    pub(super) fn resume_point(&self) -> ResumePoint<Symbol> {
+        ResumePoint {
+            mode_stack: self.mode_stack.clone(),
+            preceding_symbol: self.preceding_symbol,
+        }
+    }
+
+    fn current_mode(&self) -> LexerMode {
+        *self
+            .mode_stack
+            .last()
+            .expect("The mode stack should always hold the initial mode")
+    }
+
+    fn apply_mode_action<LexemeType>(
+        &mut self,
+        action: ModeAction,
+        lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+    ) {
+        match action {
+            ModeAction::Push(mode) => self.mode_stack.push(mode),
+            ModeAction::Pop => {
+                if self.mode_stack.len() > 1 {
+                    self.mode_stack.pop();
+                }
+            }
+        }
+        self.reset_failure_memo(lexical_analyzer);
+    }
+
+    // Memoized states are only meaningful for the automaton of the current mode
+    fn reset_failure_memo<LexemeType>(
+        &mut self,
+        lexical_analyzer: &LexicalAnalyzer<LexemeType, Symbol>,
+    ) {
+        if lexical_analyzer.is_linear_time {
+            let automaton = &lexical_analyzer.modes[self.current_mode().0].automaton;
+            self.failure_memo = Some(FailureMemo::new(automaton));
+        }
+    }
+}
+
+/// The part of an [AnalysisState] that determines the lexemes found from some offset of the input
+/// onwards (the failure memo only saves redundant work, and doesn't affect them).
+#[derive(Debug, Clone)]
+pub(super) struct ResumePoint<Symbol> {
+    mode_stack: Vec<LexerMode>,
+    preceding_symbol: Option<Symbol>,
+}
+
+impl<Symbol> ResumePoint<Symbol>
+where
+    Symbol: Alphabet,
+{
+    // Line anchors are the only patterns that depend on the preceding symbol
+    fn is_at_line_start(&self) -> bool {
+        self.preceding_symbol
+            .map_or(true, |symbol| symbol.is_line_break())
+    }
+}
+
+// Resume points are equivalent if they lead to the same lexemes
+impl<Symbol> PartialEq for ResumePoint<Symbol>
+where
+    Symbol: Alphabet,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.mode_stack == other.mode_stack && self.is_at_line_start() == other.is_at_line_start()
+    }
+}
+
+impl<Symbol> Eq for ResumePoint<Symbol> where Symbol: Alphabet {}
+
+/// The result of scanning the longest prefix of the input that's accepted by an automaton.
+struct ScanResult<Symbol> {
+    // The label of the accepting state, if any prefix was accepted
+    label: Option<usize>,
+
+    scanned_length: usize,
+    accepted_length: usize,
+    accepted_preceding_symbol: Option<Symbol>,
+
+    // The tags recorded by the capture automaton for the accepted prefix, if any
+    accepted_tags: Vec<Option<usize>>,
+}
+
+// Scans the input from the reader's cursor (expected to be at its tail), and sets the reader's tail
+// to the end of the longest prefix that's accepted by the automaton, while the capture automaton
+// (if any) records the tags of its captures
+fn scan_longest_prefix<Symbol>(
+    automaton: &LexerAutomaton<Symbol>,
+    capture_automaton: Option<&CaptureAutomaton<Symbol>>,
+    reader: &mut impl Reader<Symbol>,
+    mut preceding_symbol: Option<Symbol>,
+    mut failure_memo: Option<&mut FailureMemo<Symbol>>,
+    trace: Option<&dyn Fn(TraceEvent<usize, Symbol>)>,
+) -> ScanResult<Symbol>
+where
+    Symbol: Alphabet,
+{
+    let report = |event| {
+        if let Some(trace) = trace {
+            trace(event);
+        }
+    };
+    let mut capture_scan = capture_automaton
+        .map(|capture_automaton| CaptureScan::new(capture_automaton, &automaton.symbol_classes));
+    let record_tags = |capture_scan: &Option<CaptureScan<Symbol>>, label, tags: &mut Vec<_>| {
+        if let Some(capture_scan) = capture_scan {
+            capture_scan.record(label, tags);
+        }
+    };
+    let mut automaton = automaton.scan();
+    let feed = |automaton: &mut LexerAutomatonScan<Symbol>,
+                capture_scan: &mut Option<CaptureScan<Symbol>>,
+                state,
+                anchor,
+                position| {
+        if let Some(capture_scan) = capture_scan {
+            capture_scan.feed(anchor, position);
+        }
+        let next_state = automaton.feed(state, anchor);
+        if next_state != state {
+            report(TraceEvent::AnchorFed {
+                state: state.into(),
+                anchor,
+                next_state: next_state.into(),
+            });
+        }
+        next_state
+    };
+
+    let mut label = None;
+    let mut current_state = automaton.get_initial_state();
+
+    let mut scanned_length = 0;
+    let mut accepted_length = 0;
+    let mut accepted_preceding_symbol = preceding_symbol;
+    let mut accepted_tags = Vec::new();
+
+    while let Some(mut state) = current_state {
+        if preceding_symbol.map_or(true, |symbol| symbol.is_line_break()) {
+            state = feed(
+                &mut automaton,
+                &mut capture_scan,
+                state,
+                Anchor::LineStart,
+                scanned_length,
+            );
+        }
+
+        // Lexemes are never empty, even if they're only made of anchors
+        if scanned_length > 0 {
+            if let Some(state_label) = automaton.get_label(state) {
+                report(TraceEvent::Accept {
+                    state: state.into(),
+                    lexeme_type: state_label,
+                    length: scanned_length,
+                });
+                label = Some(state_label);
+                reader.set_tail();
+                accepted_length = scanned_length;
+                accepted_preceding_symbol = preceding_symbol;
+                record_tags(&capture_scan, state_label, &mut accepted_tags);
+            }
+        }
+
+        match reader.read_next() {
+            None => {
+                let unanchored_state = state;
+                for anchor in [Anchor::LineEnd, Anchor::InputEnd] {
+                    state = feed(
+                        &mut automaton,
+                        &mut capture_scan,
+                        state,
+                        anchor,
+                        scanned_length,
+                    );
+                }
+                if scanned_length > 0 {
+                    if let Some(state_label) = automaton.get_label(state) {
+                        // Otherwise, the acceptance was already reported
+                        if state != unanchored_state {
+                            report(TraceEvent::Accept {
+                                state: state.into(),
+                                lexeme_type: state_label,
+                                length: scanned_length,
+                            });
+                        }
+                        label = Some(state_label);
+                        reader.set_tail();
+                        accepted_length = scanned_length;
+                        accepted_preceding_symbol = preceding_symbol;
+                        record_tags(&capture_scan, state_label, &mut accepted_tags);
+                    }
+                }
+                break;
+            }
+            Some(next_symbol) => {
+                if next_symbol.is_line_break() {
+                    let unanchored_state = state;
+                    state = feed(
+                        &mut automaton,
+                        &mut capture_scan,
+                        state,
+                        Anchor::LineEnd,
+                        scanned_length,
+                    );
+                    if scanned_length > 0 {
+                        if let Some(state_label) = automaton.get_label(state) {
+                            if state != unanchored_state {
+                                report(TraceEvent::Accept {
+                                    state: state.into(),
+                                    lexeme_type: state_label,
+                                    length: scanned_length,
+                                });
+                            }
+                            label = Some(state_label);
+                            if accepted_length < scanned_length {
+                                // The newline was already read, so we rewind to its start
+                                move_tail(reader, accepted_length, scanned_length);
+                                accepted_length = scanned_length;
+                            }
+                            accepted_preceding_symbol = preceding_symbol;
+                            record_tags(&capture_scan, state_label, &mut accepted_tags);
+                        }
+                    }
+                }
+
+                current_state = automaton.step(state, next_symbol);
+                report(TraceEvent::Step {
+                    state: state.into(),
+                    symbol: next_symbol,
+                    next_state: current_state.map(Into::into),
+                });
+                scanned_length += 1;
+                preceding_symbol = Some(next_symbol);
+                if let Some(capture_scan) = &mut capture_scan {
+                    capture_scan.step(next_symbol, scanned_length);
+                }
+
+                if let (Some(memo), Some(next_state)) =
+                    (failure_memo.as_deref_mut(), current_state)
+                {
+                    memo.sync(&automaton);
+                    if memo.has_failed(next_state, scanned_length) {
+                        current_state = None;
+                    } else {
+                        memo.visit(next_state, scanned_length);
+                    }
+                }
+            }
+        }
+    }
+
+    if label.is_some() && accepted_length < scanned_length {
+        report(TraceEvent::Rewind {
+            scanned_length,
+            accepted_length,
+        });
+    }
+
+    ScanResult {
+        label,
+        scanned_length,
+        accepted_length,
+        accepted_preceding_symbol,
+        accepted_tags,
+    }
+}
+
+// Moves the reader's tail from `tail_length` to `new_tail_length` symbols past the beginning of the
+// scan, while keeping its cursor one symbol past `new_tail_length`
+fn move_tail<Symbol>(
+    reader: &mut impl Reader<Symbol>,
+    tail_length: usize,
+    new_tail_length: usize,
+) {
+    reader.move_cursor_to_tail();
+    for _ in tail_length..new_tail_length {
+        reader.read_next();
+    }
+    reader.set_tail();
+    reader.read_next();
+}
+
+// A lexeme whose extent was identified, along with the tags of its captures (see CaptureScan)
+struct IdentifiedLexeme<'a, LexemeType, Symbol> {
+    lexeme_type: LexemeType,
+    descriptor_index: usize,
+    capture_automaton: Option<&'a CaptureAutomaton<Symbol>>,
+    tags: Vec<Option<usize>>,
+}
+
+// A collected lexeme, along with its captures
+pub(super) type CapturedLexeme<LexemeType, Symbol> = (Lexeme<LexemeType, Symbol>, Captures);
+
+// The reason no lexeme was collected at the start of the remaining input
+pub(super) enum LexicalError {
+    // No lexeme matches a prefix of the remaining input, whose first symbol should be skipped
+    // (see LexicalAnalyzer::skip_unrecognized_symbol)
+    UnrecognizedInput,
+
+    // A lexeme at the specified offset opened a nested region that the input ended inside of,
+    // which was skipped up to the end of the input
+    UnterminatedNestedRegion { start: usize },
+}
+
+enum LexemeIdentificationResult<'a, LexemeType, Symbol> {
+    Identified(IdentifiedLexeme<'a, LexemeType, Symbol>),
     InputExhausted,
     LexicalError,
+    UnterminatedNestedRegion { start: usize },
 }
@@ -13,6 +13,9 @@
 //! consists of is known as a [lexical analyzer](LexicalAnalyzer), and is compiled from a set of
 //! [LexemeDescriptor]s.
 //!
+//! Lexical analyzers operate on text (as UTF-8 bytes) by default, but may analyze sequences of
+//! the symbols of any other [Alphabet] as well, such as Unicode characters (`char`).
+//!
 //! # Example
 //! ```rust
 //! # use syntax_parser_generator::lex::*;
@@ -55,14 +58,52 @@
 //! assert_eq!(extracted_lexemes.collect::<Vec<Lexeme<MyLexemeType>>>(), actual_lexemes);
 //! ```
 
+pub use alphabet::Alphabet;
+pub use build::{DfaConstruction, LexicalAnalyzerBuilder};
+pub use captures::Captures;
+pub use dynamic::{DynamicLexemeType, LexemeTypeRegistry};
+pub use end_of_input::EndOfInput;
+pub use highlight::Highlighter;
+pub use include_stack::IncludingLexemeIterator;
+pub use incremental::LexemeList;
+pub use indentation::{IndentationError, IndentationRules, IndentedLexemes};
+pub use interner::{Atom, Interner};
 pub use lexeme::{Lexeme, LexemeDescriptor};
+pub use lexeme_iterator::LexemeIterator;
 pub use lexical_analyzer::LexicalAnalyzer;
-pub use regex::Regex;
+pub use mode::{LexerMode, ModeAction};
+pub use regex::{Anchor, Regex};
+pub use span::{SourceId, Span};
+pub use spec::{LexerSpec, SpecError};
+pub use trace::TraceEvent;
+pub use unicode::GeneralCategory;
+
+pub mod patterns;
 
 mod regex;
 mod lexeme;
 mod lexical_analyzer;
 mod lexeme_iterator;
+mod automaton;
+mod alphabet;
+mod build;
+mod captures;
+mod derivatives;
+mod dot;
+mod dynamic;
+mod end_of_input;
+mod failure_memo;
+mod highlight;
+mod include_stack;
+mod incremental;
+mod indentation;
+mod interner;
+mod mode;
+mod span;
+mod spec;
+mod symbol_classes;
+mod trace;
+mod unicode;
 
 #[cfg(test)]
 mod tests;
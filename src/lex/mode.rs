@@ -0,0 +1,32 @@
+/// A mode of a [LexicalAnalyzer](crate::lex::LexicalAnalyzer): a set of lexeme descriptors that's
+/// active in some context of the input (such as the contents of string literals).
+///
+/// Modes are created by
+/// [LexicalAnalyzerBuilder::new_mode](crate::lex::LexicalAnalyzerBuilder::new_mode). While
+/// analyzing, the analyzer maintains a stack of modes, and only recognizes the lexemes
+/// described by the mode at its top. The stack is manipulated by [ModeAction]s, triggered by the
+/// lexemes found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LexerMode(pub(super) usize);
+
+impl LexerMode {
+    /// The mode at the bottom of the stack, in which the analysis starts.
+    ///
+    /// Its lexemes are described by the descriptors given to
+    /// [LexicalAnalyzerBuilder::new](crate::lex::LexicalAnalyzerBuilder::new).
+    pub const INITIAL: LexerMode = LexerMode(0);
+}
+
+/// An action on the mode stack of a [LexicalAnalyzer](crate::lex::LexicalAnalyzer), taken when a
+/// lexeme of some type is found (see
+/// [LexicalAnalyzerBuilder::set_mode_action](crate::lex::LexicalAnalyzerBuilder::set_mode_action)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModeAction {
+    /// Enter the specified mode, until it is popped.
+    Push(LexerMode),
+
+    /// Return to the mode that was active before the current one.
+    ///
+    /// Has no effect if the current mode is the initial one.
+    Pop,
+}
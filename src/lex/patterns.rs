@@ -0,0 +1,492 @@
+//! Ready-made [Regex] patterns for common shapes of lexemes.
+//!
+//! The patterns match UTF-8 encoded text, and are built from the basic [Regex] constructors, so
+//! they're supported by every [DfaConstruction](crate::lex::DfaConstruction).
+//!
+//! # Example
+//!
+//! ```rust
+//! # use syntax_parser_generator::lex::*;
+//! # use syntax_parser_generator::readers::ByteArrayReader;
+//! # #[derive(Debug, Clone, Eq, Hash, PartialEq)]
+//! # enum MyLexemeType { Identifier, Integer, String, Comment, WhiteSpace }
+//! let escapes = patterns::Escapes::c();
+//! let lexical_analyzer = LexicalAnalyzer::new(vec![
+//!     LexemeDescriptor::new(MyLexemeType::Identifier, patterns::identifier()),
+//!     LexemeDescriptor::new(
+//!         MyLexemeType::Integer,
+//!         Regex::union(vec![
+//!             patterns::decimal_integer(Some('_')),
+//!             patterns::hex_integer(Some('_')),
+//!         ]),
+//!     ),
+//!     LexemeDescriptor::new(MyLexemeType::String, patterns::string_literal(&escapes)),
+//!     LexemeDescriptor::new(MyLexemeType::Comment, patterns::line_comment("//")),
+//!     LexemeDescriptor::new(MyLexemeType::WhiteSpace, Regex::white_space()),
+//! ]);
+//!
+//! let input_text = &mut ByteArrayReader::from_string_slice("x 0xFF_FF \"a\\n\" // b");
+//! let lexemes: Vec<Lexeme<MyLexemeType>> = lexical_analyzer
+//!     .analyze(input_text)
+//!     .filter(|lexeme| lexeme.lexeme_type != MyLexemeType::WhiteSpace)
+//!     .collect();
+//! assert_eq!(
+//!     lexemes,
+//!     vec![
+//!         Lexeme::new(MyLexemeType::Identifier, "x"),
+//!         Lexeme::new(MyLexemeType::Integer, "0xFF_FF"),
+//!         Lexeme::new(MyLexemeType::String, "\"a\\n\""),
+//!         Lexeme::new(MyLexemeType::Comment, "// b"),
+//!     ],
+//! );
+//! ```
+
+use crate::lex::Regex;
+
+/// The escape sequences allowed in string and character literals (see [string_literal] and
+/// [char_literal]).
+///
+/// # Example
+///
+/// C's escape sequences, without the universal character names:
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::patterns::Escapes;
+/// let escapes = Escapes {
+///     unicode: false,
+///     ..Escapes::c()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Escapes {
+    /// The characters that form an escape sequence when following a backslash (such as `n` in
+    /// `\n`).
+    pub simple: Vec<char>,
+
+    /// Whether a backslash followed by 1 to 3 octal digits (such as `\0` or `\177`) is allowed.
+    pub octal: bool,
+
+    /// Whether a backslash followed by `x` and 1 or more hexadecimal digits (such as `\x7F`) is
+    /// allowed.
+    pub hex: bool,
+
+    /// Whether a backslash followed by `u` and 4 hexadecimal digits, or by `U` and 8 hexadecimal
+    /// digits (such as `\u00E9`), is allowed.
+    pub unicode: bool,
+}
+
+impl Escapes {
+    /// The escape sequences of the C language.
+    pub fn c() -> Self {
+        Self {
+            simple: vec!['\'', '"', '?', '\\', 'a', 'b', 'f', 'n', 'r', 't', 'v'],
+            octal: true,
+            hex: true,
+            unicode: true,
+        }
+    }
+
+    /// No escape sequences at all, in which case a backslash is an ordinary character.
+    pub fn none() -> Self {
+        Self {
+            simple: Vec::new(),
+            octal: false,
+            hex: false,
+            unicode: false,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.simple.is_empty() && !self.octal && !self.hex && !self.unicode
+    }
+
+    fn to_regex(&self) -> Regex {
+        let mut options: Vec<Regex> = self.simple.iter().copied().map(Regex::single_char).collect();
+        if self.octal {
+            let octal_digit = || digit(8);
+            options.push(Regex::concat(vec![
+                octal_digit(),
+                Regex::optional(octal_digit()),
+                Regex::optional(octal_digit()),
+            ]));
+        }
+        if self.hex {
+            options.push(Regex::concat(vec![
+                Regex::single_char('x'),
+                Regex::plus_from(digit(16)),
+            ]));
+        }
+        if self.unicode {
+            options.push(Regex::concat(vec![Regex::single_char('u'), repeat(digit(16), 4)]));
+            options.push(Regex::concat(vec![Regex::single_char('U'), repeat(digit(16), 8)]));
+        }
+        Regex::concat(vec![Regex::single_char('\\'), Regex::union(options)])
+    }
+}
+
+/// A C-style identifier: an ASCII letter or `_`, followed by any number of ASCII letters, digits,
+/// and `_`s.
+pub fn identifier() -> Regex {
+    let start = || {
+        Regex::union(vec![
+            Regex::character_range('a', 'z'),
+            Regex::character_range('A', 'Z'),
+            Regex::single_char('_'),
+        ])
+    };
+    Regex::concat(vec![
+        start(),
+        Regex::star_from(Regex::union(vec![start(), Regex::character_range('0', '9')])),
+    ])
+}
+
+/// A Unicode identifier, following the default syntax of
+/// [UAX #31](https://www.unicode.org/reports/tr31/): a character with the `XID_Start` property or
+/// `_`, followed by any number of characters with the `XID_Continue` property.
+pub fn unicode_identifier() -> Regex {
+    Regex::concat(vec![
+        Regex::union(vec![Regex::xid_start(), Regex::single_char('_')]),
+        Regex::star_from(Regex::xid_continue()),
+    ])
+}
+
+/// One or more digits in the given `radix`, where digits above 9 are letters of either case.
+///
+/// If a `digit_separator` is given, it may appear between any two digits (such as `_` in
+/// `1_000`), but not at the beginning or the end of the digits, and not twice in a row.
+///
+/// # Panics
+///
+/// If the radix is not between 2 and 36 (inclusive).
+pub fn digits(radix: u32, digit_separator: Option<char>) -> Regex {
+    if !(2..=36).contains(&radix) {
+        panic!("Cannot create a pattern of digits in radix {}, which is not in 2..=36", radix)
+    }
+    let followed_digit = match digit_separator {
+        None => digit(radix),
+        Some(separator) => Regex::concat(vec![
+            Regex::optional(Regex::single_char(separator)),
+            digit(radix),
+        ]),
+    };
+    Regex::concat(vec![digit(radix), Regex::star_from(followed_digit)])
+}
+
+/// A decimal integer literal, such as `42` (see [digits] for the `digit_separator`).
+///
+/// Signs are not included, as they're usually analyzed as separate operators.
+pub fn decimal_integer(digit_separator: Option<char>) -> Regex {
+    digits(10, digit_separator)
+}
+
+/// A hexadecimal integer literal, prefixed by `0x` or `0X`, such as `0x7F` (see [digits] for the
+/// `digit_separator`).
+pub fn hex_integer(digit_separator: Option<char>) -> Regex {
+    prefixed_integer('x', 16, digit_separator)
+}
+
+/// An octal integer literal, prefixed by `0o` or `0O`, such as `0o755` (see [digits] for the
+/// `digit_separator`).
+///
+/// Note that C-style octal literals (`0755`) are matched by [decimal_integer].
+pub fn octal_integer(digit_separator: Option<char>) -> Regex {
+    prefixed_integer('o', 8, digit_separator)
+}
+
+/// A binary integer literal, prefixed by `0b` or `0B`, such as `0b1010` (see [digits] for the
+/// `digit_separator`).
+pub fn binary_integer(digit_separator: Option<char>) -> Regex {
+    prefixed_integer('b', 2, digit_separator)
+}
+
+/// A decimal floating-point literal, with a fractional part, an exponent, or both (such as `1.5`,
+/// `1.`, `.5`, `1e10`, or `1.5E-3`). See [digits] for the `digit_separator`.
+///
+/// Integers with neither a fractional part nor an exponent are not matched, so this pattern can
+/// be used alongside [decimal_integer].
+pub fn float(digit_separator: Option<char>) -> Regex {
+    let digits = || digits(10, digit_separator);
+    let exponent = || {
+        Regex::concat(vec![
+            Regex::union(vec![Regex::single_char('e'), Regex::single_char('E')]),
+            Regex::optional(Regex::union(vec![Regex::single_char('+'), Regex::single_char('-')])),
+            digits(),
+        ])
+    };
+    Regex::union(vec![
+        Regex::concat(vec![
+            digits(),
+            Regex::single_char('.'),
+            Regex::optional(digits()),
+            Regex::optional(exponent()),
+        ]),
+        Regex::concat(vec![Regex::single_char('.'), digits(), Regex::optional(exponent())]),
+        Regex::concat(vec![digits(), exponent()]),
+    ])
+}
+
+/// A string literal delimited by `"`, which may contain the given escape sequences.
+///
+/// The literal may contain any character but `"`, a newline, and (if any escape sequences are
+/// allowed) a backslash that does not start an allowed escape sequence.
+pub fn string_literal(escapes: &Escapes) -> Regex {
+    quoted('"', Regex::star_from, escapes)
+}
+
+/// A character literal delimited by `'`, which holds a single character or one of the given
+/// escape sequences (such as `'a'` or `'\n'`).
+pub fn char_literal(escapes: &Escapes) -> Regex {
+    quoted('\'', |contents| contents, escapes)
+}
+
+/// A line comment, starting with `start` (such as `//` or `#`), and extending up to the end of
+/// the line (the newline is not included).
+pub fn line_comment(start: &str) -> Regex {
+    Regex::concat(vec![
+        Regex::constant_string(start),
+        Regex::star_from(text_character_except(&['\n'])),
+    ])
+}
+
+/// A C-style block comment, delimited by `/*` and `*/`, which cannot be nested.
+///
+/// See [LexemeDescriptor::nested_region](crate::lex::LexemeDescriptor::nested_region) for
+/// comments that can be nested.
+pub fn block_comment() -> Regex {
+    // After the opening "/*", the comment consists of runs of non-stars, each followed by a run of
+    // stars, and ends at the first star run that's followed by a slash
+    let stars = || Regex::plus_from(Regex::single_char('*'));
+    Regex::concat(vec![
+        Regex::constant_string("/*"),
+        Regex::star_from(text_character_except(&['*'])),
+        stars(),
+        Regex::star_from(Regex::concat(vec![
+            text_character_except(&['*', '/']),
+            Regex::star_from(text_character_except(&['*'])),
+            stars(),
+        ])),
+        Regex::single_char('/'),
+    ])
+}
+
+// A single digit in the given radix
+fn digit(radix: u32) -> Regex {
+    let mut options = Vec::new();
+    for value in 0..radix {
+        let digit = char::from_digit(value, radix).expect("Digit should be below the radix");
+        options.push(Regex::single_char(digit));
+        if digit.is_ascii_lowercase() {
+            options.push(Regex::single_char(digit.to_ascii_uppercase()));
+        }
+    }
+    Regex::union(options)
+}
+
+fn prefixed_integer(prefix: char, radix: u32, digit_separator: Option<char>) -> Regex {
+    Regex::concat(vec![
+        Regex::single_char('0'),
+        Regex::union(vec![
+            Regex::single_char(prefix),
+            Regex::single_char(prefix.to_ascii_uppercase()),
+        ]),
+        digits(radix, digit_separator),
+    ])
+}
+
+fn repeat(pattern: Regex, count: usize) -> Regex {
+    Regex::concat(vec![pattern; count])
+}
+
+// A literal delimited by `quote`, whose contents are constrained by `repeat_contents`
+fn quoted(quote: char, repeat_contents: fn(Regex) -> Regex, escapes: &Escapes) -> Regex {
+    let contents = if escapes.is_empty() {
+        text_character_except(&[quote, '\n'])
+    } else {
+        Regex::union(vec![text_character_except(&[quote, '\n', '\\']), escapes.to_regex()])
+    };
+    Regex::concat(vec![
+        Regex::single_char(quote),
+        repeat_contents(contents),
+        Regex::single_char(quote),
+    ])
+}
+
+// The UTF-8 encoding of a single character, other than the specified ASCII characters
+fn text_character_except(excluded: &[char]) -> Regex {
+    let mut options: Vec<Regex> = (0..=0x7F)
+        .map(char::from)
+        .filter(|character| !excluded.contains(character))
+        .map(Regex::single_char)
+        .collect();
+    options.push(non_ascii_character());
+    Regex::union(options)
+}
+
+// The well-formed UTF-8 encodings of non-ASCII characters (see RFC 3629)
+fn non_ascii_character() -> Regex {
+    let continuation = || byte_range(0x80, 0xBF);
+    Regex::union(vec![
+        Regex::concat(vec![byte_range(0xC2, 0xDF), continuation()]),
+        Regex::concat(vec![Regex::symbol(0xE0), byte_range(0xA0, 0xBF), continuation()]),
+        Regex::concat(vec![byte_range(0xE1, 0xEC), continuation(), continuation()]),
+        Regex::concat(vec![Regex::symbol(0xED), byte_range(0x80, 0x9F), continuation()]),
+        Regex::concat(vec![byte_range(0xEE, 0xEF), continuation(), continuation()]),
+        Regex::concat(vec![
+            Regex::symbol(0xF0),
+            byte_range(0x90, 0xBF),
+            continuation(),
+            continuation(),
+        ]),
+        Regex::concat(vec![
+            byte_range(0xF1, 0xF3),
+            continuation(),
+            continuation(),
+            continuation(),
+        ]),
+        Regex::concat(vec![
+            Regex::symbol(0xF4),
+            byte_range(0x80, 0x8F),
+            continuation(),
+            continuation(),
+        ]),
+    ])
+}
+
+fn byte_range(start: u8, end: u8) -> Regex {
+    Regex::union((start..=end).map(Regex::symbol).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automata::dfa::{Dfa, DfaState};
+    use crate::handles::Handle;
+    use crate::handles::specials::AutomaticallyHandled;
+    use crate::lex::automaton::LexerSymbol;
+    use crate::lex::derivatives::build_dfa_by_derivatives;
+    use crate::lex::symbol_classes::SymbolClasses;
+
+    use super::*;
+
+    fn reduce_labels(indices: Vec<&usize>) -> Option<usize> {
+        indices.into_iter().min().copied()
+    }
+
+    // The DFA consumes the classes of the symbols
+    type ClassifiedDfa = (Dfa<LexerSymbol<u8>, usize>, SymbolClasses<u8>);
+
+    fn create_dfa_for_regex(pattern: Regex) -> ClassifiedDfa {
+        let symbol_classes = SymbolClasses::new(&[pattern.clone()]);
+        let dfa = build_dfa_by_derivatives(vec![pattern], &symbol_classes, reduce_labels);
+        (dfa.minimize(), symbol_classes)
+    }
+
+    fn scan(
+        (dfa, symbol_classes): &ClassifiedDfa,
+        data: &[u8],
+    ) -> Option<Handle<DfaState<LexerSymbol<u8>, usize>>> {
+        let classes = data.iter().map(|&x| symbol_classes.classify(x));
+        dfa.scan(classes.map(|class| LexerSymbol::Input(class).handle()))
+    }
+
+    fn is_string_in(dfa: &ClassifiedDfa, data: &str) -> bool {
+        match scan(dfa, data.as_bytes()) {
+            None => false,
+            Some(end_state) => dfa.0.get_label(end_state).is_some(),
+        }
+    }
+
+    fn assert_matches(pattern: Regex, accepted: &[&str], rejected: &[&str]) {
+        let dfa = create_dfa_for_regex(pattern);
+        for data in accepted {
+            assert!(is_string_in(&dfa, data), "{:?} should be matched", data);
+        }
+        for data in rejected {
+            assert!(!is_string_in(&dfa, data), "{:?} should not be matched", data);
+        }
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_matches(identifier(), &["x", "_a1", "CamelCase"], &["", "1a", "a-b", "\u{E9}"]);
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_matches(decimal_integer(None), &["0", "0755", "42"], &["", "4_2", "-1"]);
+        assert_matches(
+            decimal_integer(Some('_')),
+            &["1_000", "1_2_3"],
+            &["_1", "1_", "1__0"],
+        );
+        assert_matches(
+            hex_integer(Some('\'')),
+            &["0x7F", "0XdeadBEEF", "0xFF'FF"],
+            &["0x", "0xG", "x7F"],
+        );
+        assert_matches(octal_integer(None), &["0o755", "0O7"], &["0o8", "0755"]);
+        assert_matches(binary_integer(None), &["0b1010", "0B1"], &["0b2", "0b"]);
+    }
+
+    #[test]
+    fn test_digits_in_large_radix() {
+        assert_matches(digits(36, None), &["zZ09"], &["", "_"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_digits_in_invalid_radix() {
+        digits(37, None);
+    }
+
+    #[test]
+    fn test_float() {
+        assert_matches(
+            float(Some('_')),
+            &["1.5", "1.", ".5", "1e10", "1.5E-3", "1_000.000_1e+1_0"],
+            &["1", ".", "e10", "1e", "1.5e+", "1._5"],
+        );
+    }
+
+    #[test]
+    fn test_string_literal() {
+        assert_matches(
+            string_literal(&Escapes::c()),
+            &[r#""""#, r#""a b""#, r#""\"\\\n""#, r#""\0\177\x7Fé""#, "\"\u{E9}\u{1F600}\""],
+            &[r#""a"#, r#""\q""#, "\"a\nb\"", r#""\u00E""#, r#""\8""#, r#""a"b""#],
+        );
+        let escapes = Escapes {
+            simple: vec!['"'],
+            ..Escapes::none()
+        };
+        assert_matches(string_literal(&escapes), &[r#""\"""#], &[r#""\n""#, r#""\\""#]);
+        assert_matches(string_literal(&Escapes::none()), &[r#""\""#], &[r#""\"""#]);
+    }
+
+    #[test]
+    fn test_char_literal() {
+        assert_matches(
+            char_literal(&Escapes::c()),
+            &["'a'", r"'\''", r"'\n'", "'\u{E9}'", "'\"'"],
+            &["''", "'ab'", "'''", r"'\'"],
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let dfa = create_dfa_for_regex(line_comment("#"));
+        for data in [&[b'#', 0x80][..], &[b'#', 0xC0, 0x80], &[b'#', 0xED, 0xA0, 0x80]] {
+            let scan = scan(&dfa, data);
+            assert!(scan.map_or(true, |end_state| dfa.0.get_label(end_state).is_none()));
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_matches(line_comment("//"), &["//", "// a /* b", "//\r"], &["/", "// a\n"]);
+        assert_matches(
+            block_comment(),
+            &["/**/", "/* a */", "/***/", "/* a * b / c **/", "/*\n*/"],
+            &["/*/", "/* a", "/* a */ b */", "/* a */*/"],
+        );
+    }
+}
@@ -1,35 +1,89 @@
+use std::collections::BTreeSet;
+
 use crate::automata::nfa::{Nfa, NfaState};
 use crate::handles::Handle;
 use crate::handles::specials::AutomaticallyHandled;
+use crate::lex::Alphabet;
+use crate::lex::automaton::LexerSymbol;
+use crate::lex::symbol_classes::SymbolClasses;
+
+/// A zero-width assertion about the position in the input at which it is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Anchor {
+    /// Holds at the beginning of the input, and right after each newline (`\n`, or any other
+    /// [line break](Alphabet::is_line_break)).
+    LineStart,
+
+    /// Holds at the end of the input, and right before each newline (`\n`, or any other
+    /// [line break](Alphabet::is_line_break)).
+    LineEnd,
+
+    /// Holds at the end of the input.
+    InputEnd,
+}
 
-/// A regular-expression pattern over raw bytes.
+/// A regular-expression pattern over the symbols of some [Alphabet] (raw bytes, by default).
 ///
 /// In practice, you won't need to create instances of this type directly. Check out the [Regex]
 /// API and the high-level factory methods it offers.
-#[derive(Clone)]
-pub enum Regex {
-    /// Only matches a single hardcoded byte.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Regex<Symbol = u8> {
+    /// Only matches a single hardcoded symbol.
     SingleCharacter {
-        /// The matching byte.
-        value: u8,
+        /// The matching symbol.
+        value: Symbol,
     },
 
     /// Matches each one of the specified patterns.
     Union {
         /// The possible matching patterns.
-        options: Vec<Regex>,
+        options: Vec<Regex<Symbol>>,
     },
 
     /// Matches a concatenation of the specified patterns.
     Concat {
         /// The concatenated patterns.
-        parts: Vec<Regex>,
+        parts: Vec<Regex<Symbol>>,
     },
 
     /// Matches a concatenation of zero or more repetitions of the specified pattern.
     Star {
         /// The repeated pattern.
-        repeated_pattern: Box<Regex>,
+        repeated_pattern: Box<Regex<Symbol>>,
+    },
+
+    /// Matches sequences that are matched by all the specified patterns.
+    ///
+    /// Only supported by
+    /// [DfaConstruction::Derivatives](crate::lex::DfaConstruction::Derivatives).
+    Intersection {
+        /// The intersected patterns.
+        parts: Vec<Regex<Symbol>>,
+    },
+
+    /// Matches sequences that are not matched by the specified pattern.
+    ///
+    /// Only supported by
+    /// [DfaConstruction::Derivatives](crate::lex::DfaConstruction::Derivatives).
+    Complement {
+        /// The complemented pattern.
+        complemented_pattern: Box<Regex<Symbol>>,
+    },
+
+    /// Matches the empty sequence, at positions where the specified anchor's assertion holds.
+    Anchor {
+        /// The asserted anchor.
+        anchor: Anchor,
+    },
+
+    /// Matches the same sequences as the specified pattern, and records the part of the lexeme it
+    /// matched under the specified name (see [Captures](crate::lex::Captures)).
+    Capture {
+        /// The name of the capture group.
+        name: String,
+
+        /// The captured pattern.
+        pattern: Box<Regex<Symbol>>,
     },
 }
 
@@ -50,29 +104,6 @@ impl Regex {
         }
     }
 
-    /// Creates a pattern that matches each of the specified patterns.
-    pub fn union(options: Vec<Regex>) -> Regex {
-        Regex::Union { options }
-    }
-
-    /// Creates a pattern that matches a concatenation of the specified patterns.
-    pub fn concat(parts: Vec<Regex>) -> Regex {
-        Regex::Concat { parts }
-    }
-
-    /// Creates a pattern that matches zero or more repetitions of the specified pattern.
-    pub fn star_from(repeated_pattern: Regex) -> Regex {
-        Regex::Star {
-            repeated_pattern: Box::new(repeated_pattern),
-        }
-    }
-
-    /// Creates a pattern that matches one or more repetitions of the specified pattern.
-    pub fn plus_from(repeated_pattern: Regex) -> Regex {
-        let star_pattern = Regex::star_from(repeated_pattern.clone());
-        Regex::concat(vec![repeated_pattern, star_pattern])
-    }
-
     /// Creates a pattern that matches a single white-space character.
     pub fn white_space() -> Regex {
         let white_space_characters = vec![' ', '\t', '\n', '\r', '\x0B', '\x0C'];
@@ -94,65 +125,270 @@ impl Regex {
     pub fn character_range(start: char, end: char) -> Regex {
         Regex::union((start..=end).map(Regex::single_char).collect())
     }
+}
+
+impl<Symbol> Regex<Symbol>
+where
+    Symbol: Clone,
+{
+    /// Creates a pattern that only matches the specified symbol.
+    ///
+    /// This is mostly useful for analyzers of [Alphabet]s other than bytes, as characters can be
+    /// matched with [Regex::single_char].
+    pub fn symbol(value: Symbol) -> Regex<Symbol> {
+        Regex::SingleCharacter { value }
+    }
+
+    /// Creates a pattern that matches each of the specified patterns.
+    pub fn union(options: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        Regex::Union { options }
+    }
+
+    /// Creates a pattern that matches a concatenation of the specified patterns.
+    pub fn concat(parts: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        Regex::Concat { parts }
+    }
+
+    /// Creates a pattern that matches zero or more repetitions of the specified pattern.
+    pub fn star_from(repeated_pattern: Regex<Symbol>) -> Regex<Symbol> {
+        Regex::Star {
+            repeated_pattern: Box::new(repeated_pattern),
+        }
+    }
 
-    /// Creates a pattern that matches the specified pattern, and an empty sequence of bytes.
-    pub fn optional(option: Regex) -> Regex {
+    /// Creates a pattern that matches one or more repetitions of the specified pattern.
+    pub fn plus_from(repeated_pattern: Regex<Symbol>) -> Regex<Symbol> {
+        let star_pattern = Regex::star_from(repeated_pattern.clone());
+        Regex::concat(vec![repeated_pattern, star_pattern])
+    }
+
+    /// Creates a pattern that matches the specified pattern, and an empty sequence of symbols.
+    pub fn optional(option: Regex<Symbol>) -> Regex<Symbol> {
         Regex::union(vec![option, Regex::epsilon()])
     }
 
     /// Creates a pattern that only matches an empty sequence of characters.
-    pub fn epsilon() -> Regex {
+    pub fn epsilon() -> Regex<Symbol> {
         Regex::concat(vec![])
     }
 
-    pub(crate) fn build_into_nfa<Label>(
+    /// Creates a pattern that matches sequences matched by all of the specified patterns.
+    ///
+    /// Lexical analyzers can only be built from such patterns with
+    /// [DfaConstruction::Derivatives](crate::lex::DfaConstruction::Derivatives).
+    pub fn intersection(parts: Vec<Regex<Symbol>>) -> Regex<Symbol> {
+        Regex::Intersection { parts }
+    }
+
+    /// Creates a pattern that matches every sequence not matched by the specified pattern.
+    ///
+    /// Lexical analyzers can only be built from such patterns with
+    /// [DfaConstruction::Derivatives](crate::lex::DfaConstruction::Derivatives).
+    ///
+    /// # Example
+    ///
+    /// C-style comments can be described by a `/*`, followed by anything not containing `*/`, and a
+    /// terminating `*/`:
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::Regex;
+    /// let anything = Regex::complement(Regex::union(vec![]));
+    /// let comment = Regex::concat(vec![
+    ///     Regex::constant_string("/*"),
+    ///     Regex::complement(Regex::concat(vec![
+    ///         anything.clone(),
+    ///         Regex::constant_string("*/"),
+    ///         anything,
+    ///     ])),
+    ///     Regex::constant_string("*/"),
+    /// ]);
+    /// ```
+    pub fn complement(complemented_pattern: Regex<Symbol>) -> Regex<Symbol> {
+        Regex::Complement {
+            complemented_pattern: Box::new(complemented_pattern),
+        }
+    }
+
+    /// Creates a pattern that matches the empty sequence at the beginning of a line.
+    ///
+    /// # Example
+    ///
+    /// Comments that must start at the beginning of a line, as in Makefiles:
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::Regex;
+    /// let comment = Regex::concat(vec![
+    ///     Regex::line_start(),
+    ///     Regex::single_char('#'),
+    ///     Regex::star_from(Regex::character_range(' ', '~')),
+    /// ]);
+    /// ```
+    pub fn line_start() -> Regex<Symbol> {
+        Regex::Anchor {
+            anchor: Anchor::LineStart,
+        }
+    }
+
+    /// Creates a pattern that matches the empty sequence at the end of a line (right before the
+    /// newline, which is not consumed).
+    pub fn line_end() -> Regex<Symbol> {
+        Regex::Anchor {
+            anchor: Anchor::LineEnd,
+        }
+    }
+
+    /// Creates a pattern that matches the empty sequence at the end of the input.
+    pub fn input_end() -> Regex<Symbol> {
+        Regex::Anchor {
+            anchor: Anchor::InputEnd,
+        }
+    }
+
+    /// Creates a named capture group, matching the same sequences as `pattern`.
+    ///
+    /// When a lexeme is matched by a pattern containing capture groups, the part of it matched by
+    /// each group is reported in its [Captures](crate::lex::Captures) (see
+    /// [LexicalAnalyzer::analyze_captured](crate::lex::LexicalAnalyzer::analyze_captured)). If
+    /// several ways of matching the lexeme exist, earlier options of unions are preferred, and
+    /// repetitions match as many times as possible. Capture groups are recorded while the lexeme
+    /// is scanned, and are not supported inside patterns containing intersection or complement
+    /// patterns (see [LexicalAnalyzerBuilder::build](crate::lex::LexicalAnalyzerBuilder::build)).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::Regex;
+    /// let hex_literal = Regex::concat(vec![
+    ///     Regex::constant_string("0x"),
+    ///     Regex::capture("digits", Regex::plus_from(Regex::character_range('0', '9'))),
+    /// ]);
+    /// ```
+    pub fn capture(name: &str, pattern: Regex<Symbol>) -> Regex<Symbol> {
+        Regex::Capture {
+            name: String::from(name),
+            pattern: Box::new(pattern),
+        }
+    }
+}
+
+impl<Symbol> Regex<Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Creates a pattern that matches any single symbol of the alphabet.
+    pub fn any_symbol() -> Regex<Symbol> {
+        Regex::union(Symbol::list_symbols().into_iter().map(Regex::symbol).collect())
+    }
+
+    /// Builds the pattern into the NFA, whose transitions consume the symbols' classes.
+    pub(super) fn build_into_nfa<Label>(
         &self,
-        nfa: &mut Nfa<u8, Label>,
-    ) -> (Handle<NfaState<u8, Label>>, Handle<NfaState<u8, Label>>)
-    where
-    {
+        nfa: &mut Nfa<LexerSymbol<Symbol>, Label>,
+        symbol_classes: &SymbolClasses<Symbol>,
+    ) -> (
+        Handle<NfaState<LexerSymbol<Symbol>, Label>>,
+        Handle<NfaState<LexerSymbol<Symbol>, Label>>,
+    ) {
+        let start = nfa.new_state();
+        let end = nfa.new_state();
+        self.link_into_nfa(nfa, symbol_classes, start, end);
+        (start, end)
+    }
+
+    // Builds the pattern into the NFA, such that it matches the paths from `start` to `end`.
+    // Sub-patterns are linked directly between their parent's states where possible, so that
+    // large patterns don't blow up the NFA (and the DFA constructed from it) with epsilon
+    // transitions and intermediate states. This relies on the built patterns never linking back
+    // into `start`, nor out of `end`.
+    fn link_into_nfa<Label>(
+        &self,
+        nfa: &mut Nfa<LexerSymbol<Symbol>, Label>,
+        symbol_classes: &SymbolClasses<Symbol>,
+        start: Handle<NfaState<LexerSymbol<Symbol>, Label>>,
+        end: Handle<NfaState<LexerSymbol<Symbol>, Label>>,
+    ) {
         match self {
             Regex::SingleCharacter { value } => {
-                let start = nfa.new_state();
-                let end = nfa.new_state();
-                nfa.link(start, end, Some(value.handle()));
-                (start, end)
+                let class = symbol_classes.classify(*value);
+                nfa.link(start, end, Some(LexerSymbol::Input(class).handle()));
+            }
+            Regex::Anchor { anchor } => {
+                nfa.link(start, end, Some(LexerSymbol::Anchor(*anchor).handle()));
             }
             Regex::Union { options } => {
-                let start = nfa.new_state();
-                let end = nfa.new_state();
                 for option in options {
-                    let (option_start, option_end) = option.build_into_nfa(nfa);
-                    nfa.link(start, option_start, None);
-                    nfa.link(option_end, end, None);
+                    option.link_into_nfa(nfa, symbol_classes, start, end);
                 }
-                (start, end)
             }
-            Regex::Concat { parts } => {
-                let start = nfa.new_state();
-                let end = nfa.new_state();
-                let mut curr = start;
-                for part in parts {
-                    let (part_start, part_end) = part.build_into_nfa(nfa);
-                    nfa.link(curr, part_start, None);
-                    curr = part_end;
+            Regex::Concat { parts } => match parts.split_last() {
+                None => nfa.link(start, end, None),
+                Some((last_part, other_parts)) => {
+                    let mut curr = start;
+                    for part in other_parts {
+                        let part_end = nfa.new_state();
+                        part.link_into_nfa(nfa, symbol_classes, curr, part_end);
+                        curr = part_end;
+                    }
+                    last_part.link_into_nfa(nfa, symbol_classes, curr, end);
                 }
-                nfa.link(curr, end, None);
-                (start, end)
-            }
+            },
             Regex::Star { repeated_pattern } => {
-                let start = nfa.new_state();
-                let end = nfa.new_state();
+                // The repetitions loop between dedicated states, so they never link back into
+                // `start`, nor out of `end`
                 let (repeated_pattern_start, repeated_pattern_end) =
-                    repeated_pattern.build_into_nfa(nfa);
+                    repeated_pattern.build_into_nfa(nfa, symbol_classes);
 
                 nfa.link(start, repeated_pattern_start, None);
                 nfa.link(start, end, None);
                 nfa.link(repeated_pattern_end, end, None);
                 nfa.link(repeated_pattern_end, repeated_pattern_start, None);
+            }
+            Regex::Capture { pattern, .. } => {
+                pattern.link_into_nfa(nfa, symbol_classes, start, end)
+            },
+            Regex::Intersection { .. } | Regex::Complement { .. } => panic!(
+                "Intersection and complement patterns cannot be compiled into an NFA, use \
+                DfaConstruction::Derivatives instead"
+            ),
+        }
+    }
+
+    pub(super) fn collect_anchors(&self, anchors: &mut BTreeSet<Anchor>) {
+        match self {
+            Regex::SingleCharacter { .. } => {}
+            Regex::Anchor { anchor } => {
+                anchors.insert(*anchor);
+            }
+            Regex::Union { options: parts }
+            | Regex::Concat { parts }
+            | Regex::Intersection { parts } => {
+                for part in parts {
+                    part.collect_anchors(anchors);
+                }
+            }
+            Regex::Star {
+                repeated_pattern: pattern,
+            }
+            | Regex::Complement {
+                complemented_pattern: pattern,
+            }
+            | Regex::Capture { pattern, .. } => pattern.collect_anchors(anchors),
+        }
+    }
 
-                (start, end)
+    pub(super) fn has_captures(&self) -> bool {
+        match self {
+            Regex::SingleCharacter { .. } | Regex::Anchor { .. } => false,
+            Regex::Capture { .. } => true,
+            Regex::Union { options: parts }
+            | Regex::Concat { parts }
+            | Regex::Intersection { parts } => parts.iter().any(Regex::has_captures),
+            Regex::Star {
+                repeated_pattern: pattern,
             }
+            | Regex::Complement {
+                complemented_pattern: pattern,
+            } => pattern.has_captures(),
         }
     }
 }
@@ -163,9 +399,13 @@ mod tests {
 
     use super::*;
 
-    fn create_dfa_for_regex(pattern: Regex) -> Dfa<u8, ()> {
+    // The DFA consumes the classes of the symbols
+    type ClassifiedDfa = (Dfa<LexerSymbol<u8>, ()>, SymbolClasses<u8>);
+
+    fn create_dfa_for_regex(pattern: Regex) -> ClassifiedDfa {
+        let symbol_classes = SymbolClasses::new(&[pattern.clone()]);
         let mut nfa = Nfa::new();
-        let (start, end) = pattern.build_into_nfa(&mut nfa);
+        let (start, end) = pattern.build_into_nfa(&mut nfa, &symbol_classes);
         nfa.label(end, Some(()));
         nfa.set_initial_state(start);
 
@@ -173,15 +413,15 @@ mod tests {
             .compile_to_dfa(|labels| if labels.is_empty() { None } else { Some(()) })
             .minimize();
 
-        return dfa;
+        return (dfa, symbol_classes);
     }
 
-    fn is_string_in(dfa: &Dfa<u8, ()>, data: &str) -> bool {
+    fn is_string_in((dfa, symbol_classes): &ClassifiedDfa, data: &str) -> bool {
         match dfa.scan(
             String::from(data)
                 .into_bytes()
                 .into_iter()
-                .map(|x| x.handle()),
+                .map(|x| LexerSymbol::Input(symbol_classes.classify(x)).handle()),
         ) {
             None => false,
             Some(end_state) => !dfa.get_label(end_state).is_none(),
@@ -282,4 +522,43 @@ mod tests {
         assert_eq!(is_string_in(&dfa, "12"), false);
         assert_eq!(is_string_in(&dfa, "1ours"), false);
     }
+
+    #[test]
+    fn test_character_class_nfa() {
+        // A character class is linked directly between two states, by a single transition
+        let pattern = Regex::union((0..=u8::MAX).map(Regex::symbol).collect());
+        let mut nfa: Nfa<LexerSymbol<u8>, ()> = Nfa::new();
+        pattern.build_into_nfa(&mut nfa, &SymbolClasses::new(&[pattern.clone()]));
+        assert_eq!(nfa.list_states().count(), 2);
+        let transitions: Vec<_> = nfa
+            .list_states()
+            .flat_map(|state| nfa.list_transitions(state).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(transitions.len(), 1);
+        assert!(transitions[0].0.is_some());
+    }
+
+    #[test]
+    fn test_nested_patterns() {
+        // Repetitions of patterns that are linked directly between their parent's states
+        let pattern = Regex::concat(vec![
+            Regex::star_from(Regex::union(vec![
+                Regex::concat(vec![]),
+                Regex::concat(vec![
+                    Regex::single_char('a'),
+                    Regex::star_from(Regex::single_char('b')),
+                ]),
+                Regex::star_from(Regex::single_char('c')),
+            ])),
+            Regex::single_char('d'),
+        ]);
+        let dfa = create_dfa_for_regex(pattern);
+
+        assert_eq!(is_string_in(&dfa, "d"), true);
+        assert_eq!(is_string_in(&dfa, "abbcad"), true);
+        assert_eq!(is_string_in(&dfa, "ccd"), true);
+        assert_eq!(is_string_in(&dfa, ""), false);
+        assert_eq!(is_string_in(&dfa, "bd"), false);
+        assert_eq!(is_string_in(&dfa, "dd"), false);
+    }
 }
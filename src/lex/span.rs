@@ -0,0 +1,27 @@
+/// Identifies a source of input text, such as a file, analyzed by a
+/// [LexicalAnalyzer](crate::lex::LexicalAnalyzer).
+///
+/// When sources are included in one another (see
+/// [LexicalAnalyzer::analyze_with_includes](crate::lex::LexicalAnalyzer::analyze_with_includes)),
+/// the outermost source is [SourceId::ROOT], and the included sources are numbered sequentially
+/// in the order of their inclusion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SourceId(pub usize);
+
+impl SourceId {
+    /// The source in which the analysis starts.
+    pub const ROOT: SourceId = SourceId(0);
+}
+
+/// The location of a lexeme in the input text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The source containing the lexeme.
+    pub source: SourceId,
+
+    /// The offset of the lexeme's first byte from the beginning of its source.
+    pub start: usize,
+
+    /// The offset of the byte following the lexeme from the beginning of its source.
+    pub end: usize,
+}
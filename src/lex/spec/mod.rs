@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::lex::{LexemeDescriptor, LexerMode, LexicalAnalyzerBuilder, ModeAction};
+use crate::lex::regex::Regex;
+use pattern::parse_pattern;
+
+mod pattern;
+
+/// The name of the mode in which the analysis starts, which holds the rules declared before any
+/// mode header.
+const INITIAL_MODE_NAME: &str = "INITIAL";
+
+/// A lexical-analyzer specification, parsed from a lex-like text format.
+///
+/// This allows the lexemes of a language to be specified as data (for example, in a file shipped
+/// alongside the parser), rather than as Rust code. Lexeme types are plain strings by default, and
+/// may be converted to indices by [LexerSpec::into_indexed], or to any other type by
+/// [LexerSpec::map_lexeme_types].
+///
+/// # Format
+///
+/// The specification consists of lines of the following forms, where blank lines, and lines
+/// starting with `#`, are ignored:
+///
+/// * `NAME = pattern`: defines a pattern, which following patterns may refer to as `{NAME}`.
+/// * `pattern => TYPE [skip] [push MODE | pop] [priority N]`: describes lexemes of type `TYPE`,
+///   recognized in the current mode. Rules marked with `skip` describe lexemes that are analyzed,
+///   but not yielded (see
+///   [LexicalAnalyzerBuilder::set_skipped_lexeme_types]), `push` and `pop` set the
+///   [ModeAction] taken when the lexeme is found, and `priority` sets the descriptor's
+///   [priority](LexemeDescriptor::priority).
+/// * `mode NAME`: makes `NAME` the current mode, to which the following rules belong. Rules
+///   declared before any mode header belong to the initial mode, which is named `INITIAL`.
+///
+/// Patterns may not contain white space, unless it's quoted or escaped. They consist of literal
+/// characters, `"quoted strings"`, character classes (`[a-z_]`, `[^"\n]`), `.` (any character but
+/// a newline), references to definitions, grouping (`(...)`), alternation (`|`), repetition (`*`,
+/// `+` and `?`), and the anchors `^` and `$`. The escapes `\n`, `\t`, `\r`, `\0`, `\xHH` and
+/// `\u{HHHH}` are supported, and any other punctuation character may be escaped to match it
+/// literally. Patterns match the UTF-8 encoding of text, and the patterns of rules may not match
+/// the empty string.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::lex::*;
+/// # use syntax_parser_generator::readers::ByteArrayReader;
+/// let spec = LexerSpec::parse(r#"
+///     letter = [a-zA-Z_]
+///     digit = [0-9]
+///
+///     "let" => LET
+///     {letter}({letter}|{digit})* => NAME
+///     {digit}+ => INTEGER
+///     [\ \t\n]+ => WHITE_SPACE skip
+///     \" => QUOTE push STRING
+///
+///     mode STRING
+///     [^"]+ => STRING_PART
+///     \" => QUOTE pop
+/// "#).unwrap();
+/// let lexical_analyzer = spec.into_builder().build();
+///
+/// let input_text = &mut ByteArrayReader::from_string_slice("let x \"a b\"");
+/// assert_eq!(
+///     lexical_analyzer.analyze(input_text).collect::<Vec<Lexeme<String>>>(),
+///     vec![
+///         Lexeme::new(String::from("LET"), "let"),
+///         Lexeme::new(String::from("NAME"), "x"),
+///         Lexeme::new(String::from("QUOTE"), "\""),
+///         Lexeme::new(String::from("STRING_PART"), "a b"),
+///         Lexeme::new(String::from("QUOTE"), "\""),
+///     ],
+/// );
+/// ```
+pub struct LexerSpec<LexemeType = String> {
+    // Indexed by the modes' identifiers, where the first is the initial mode
+    modes: Vec<ModeSpec<LexemeType>>,
+
+    skipped_lexeme_types: Vec<LexemeType>,
+}
+
+struct ModeSpec<LexemeType> {
+    name: String,
+    lexeme_descriptors: Vec<LexemeDescriptor<LexemeType>>,
+    actions: Vec<(LexemeType, ModeAction)>,
+}
+
+/// An error in the text of a [LexerSpec].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    /// The (1-based) number of the line where the error was found.
+    pub line: usize,
+
+    /// A description of the error.
+    pub message: String,
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// An action on the mode stack, as specified by a rule, before the modes it refers to are known
+enum PendingAction {
+    Push { mode_name: String, line: usize },
+    Pop,
+}
+
+impl LexerSpec {
+    /// Parses a specification from its text (see [LexerSpec] for its format).
+    pub fn parse(source: &str) -> Result<LexerSpec, SpecError> {
+        let mut definitions: HashMap<String, Regex> = HashMap::new();
+        let mut modes = vec![ModeSpec::new(INITIAL_MODE_NAME)];
+        let mut current_mode = 0;
+        let mut pending_actions = Vec::new();
+        let mut skipped_lexeme_types: Vec<String> = Vec::new();
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index + 1;
+            let error = |message: String| SpecError { line, message };
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let tokens = split_tokens(text).map_err(error)?;
+
+            match tokens.as_slice() {
+                [name, "=", pattern] => {
+                    if definitions.contains_key(*name) {
+                        return Err(error(format!("{:?} is already defined", name)));
+                    }
+                    let pattern = parse_pattern(pattern, &definitions).map_err(error)?;
+                    definitions.insert(String::from(*name), pattern);
+                }
+                [pattern, "=>", lexeme_type, options @ ..] => {
+                    let pattern = parse_pattern(pattern, &definitions).map_err(error)?;
+                    if pattern.is_nullable() {
+                        return Err(error(String::from("pattern matches the empty string")));
+                    }
+                    let lexeme_type = String::from(*lexeme_type);
+                    let rule = parse_rule_options(options, line)?;
+                    if rule.is_skipped && !skipped_lexeme_types.contains(&lexeme_type) {
+                        skipped_lexeme_types.push(lexeme_type.clone());
+                    }
+                    if let Some(action) = rule.action {
+                        pending_actions.push((current_mode, lexeme_type.clone(), action));
+                    }
+                    modes[current_mode].lexeme_descriptors.push(
+                        LexemeDescriptor::new(lexeme_type, pattern).with_priority(rule.priority),
+                    );
+                }
+                ["mode", name] => {
+                    current_mode = match modes.iter().position(|mode| mode.name == *name) {
+                        Some(mode) => mode,
+                        None => {
+                            modes.push(ModeSpec::new(name));
+                            modes.len() - 1
+                        }
+                    };
+                }
+                _ => {
+                    return Err(error(String::from(
+                        "expected a definition, a rule, or a mode header",
+                    )))
+                }
+            }
+        }
+
+        // Modes may be pushed before they're declared
+        for (mode, lexeme_type, action) in pending_actions {
+            let action = match action {
+                PendingAction::Pop => ModeAction::Pop,
+                PendingAction::Push { mode_name, line } => {
+                    match modes.iter().position(|mode| mode.name == mode_name) {
+                        Some(target) => ModeAction::Push(LexerMode(target)),
+                        None => {
+                            return Err(SpecError {
+                                line,
+                                message: format!("undeclared mode {:?}", mode_name),
+                            })
+                        }
+                    }
+                }
+            };
+            modes[mode].actions.push((lexeme_type, action));
+        }
+
+        Ok(LexerSpec {
+            modes,
+            skipped_lexeme_types,
+        })
+    }
+
+    /// Converts the specification's lexeme types to indices, and returns it along with the
+    /// lexeme types' names, indexed by their assigned indices.
+    ///
+    /// Indices are assigned by the order in which the lexeme types first appear in the
+    /// specification.
+    pub fn into_indexed(self) -> (LexerSpec<usize>, Vec<String>) {
+        let mut names: Vec<String> = Vec::new();
+        let spec = self.map_lexeme_types(|name| {
+            match names.iter().position(|known_name| *known_name == name) {
+                Some(index) => index,
+                None => {
+                    names.push(name);
+                    names.len() - 1
+                }
+            }
+        });
+        (spec, names)
+    }
+}
+
+impl<LexemeType> LexerSpec<LexemeType> {
+    /// Gets the mode of the specified name, if it's declared by the specification.
+    ///
+    /// The modes are identified by the order in which they're first declared, and match the modes
+    /// of the analyzer built by [LexerSpec::into_builder].
+    pub fn mode(&self, name: &str) -> Option<LexerMode> {
+        self.modes
+            .iter()
+            .position(|mode| mode.name == name)
+            .map(LexerMode)
+    }
+
+    /// Gets the descriptors of the lexemes recognized in the specified mode, in the order of their
+    /// rules.
+    ///
+    /// # Panics
+    ///
+    /// If the mode is not declared by this specification.
+    pub fn lexeme_descriptors(&self, mode: LexerMode) -> &[LexemeDescriptor<LexemeType>] {
+        &self.modes[mode.0].lexeme_descriptors
+    }
+
+    /// Converts the specification's lexeme types by the specified function, which is called with
+    /// each occurrence of a lexeme type in the specification.
+    pub fn map_lexeme_types<F, T>(self, mut f: F) -> LexerSpec<T>
+    where
+        F: FnMut(LexemeType) -> T,
+    {
+        let modes = self
+            .modes
+            .into_iter()
+            .map(|mode| ModeSpec {
+                name: mode.name,
+                lexeme_descriptors: mode
+                    .lexeme_descriptors
+                    .into_iter()
+                    .map(|descriptor| LexemeDescriptor {
+                        lexeme_type: f(descriptor.lexeme_type),
+                        pattern: descriptor.pattern,
+                        priority: descriptor.priority,
+                        closing_pattern: descriptor.closing_pattern,
+                    })
+                    .collect(),
+                actions: mode
+                    .actions
+                    .into_iter()
+                    .map(|(lexeme_type, action)| (f(lexeme_type), action))
+                    .collect(),
+            })
+            .collect();
+        let skipped_lexeme_types = self.skipped_lexeme_types.into_iter().map(f).collect();
+        LexerSpec {
+            modes,
+            skipped_lexeme_types,
+        }
+    }
+
+    /// Creates a builder for an analyzer of the specified lexemes, with the specified modes,
+    /// mode actions, and skipped lexeme types.
+    ///
+    /// The builder may be further configured before the analyzer is built.
+    pub fn into_builder(self) -> LexicalAnalyzerBuilder<LexemeType> {
+        let mut modes = self.modes.into_iter();
+        let initial_mode = modes.next().expect("A specification always has an initial mode");
+        let mut builder = LexicalAnalyzerBuilder::new(initial_mode.lexeme_descriptors);
+        let mut actions = vec![(LexerMode::INITIAL, initial_mode.actions)];
+        for mode in modes {
+            actions.push((builder.new_mode(mode.lexeme_descriptors), mode.actions));
+        }
+        for (mode, mode_actions) in actions {
+            for (lexeme_type, action) in mode_actions {
+                builder.set_mode_action(mode, lexeme_type, action);
+            }
+        }
+        builder.set_skipped_lexeme_types(self.skipped_lexeme_types);
+        builder
+    }
+}
+
+impl<LexemeType> ModeSpec<LexemeType> {
+    fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            lexeme_descriptors: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+}
+
+// The options that follow a rule's lexeme type
+struct RuleOptions {
+    is_skipped: bool,
+    action: Option<PendingAction>,
+    priority: i32,
+}
+
+fn parse_rule_options(options: &[&str], line: usize) -> Result<RuleOptions, SpecError> {
+    let error = |message: String| SpecError { line, message };
+    let mut rule = RuleOptions {
+        is_skipped: false,
+        action: None,
+        priority: 0,
+    };
+    let mut options = options.iter();
+    while let Some(&option) = options.next() {
+        match option {
+            "skip" => rule.is_skipped = true,
+            "pop" | "push" if rule.action.is_some() => {
+                return Err(error(String::from("a rule may only have a single mode action")))
+            }
+            "pop" => rule.action = Some(PendingAction::Pop),
+            "push" => {
+                let mode_name = options
+                    .next()
+                    .ok_or_else(|| error(String::from("expected a mode to push")))?;
+                rule.action = Some(PendingAction::Push {
+                    mode_name: String::from(*mode_name),
+                    line,
+                });
+            }
+            "priority" => {
+                rule.priority = options
+                    .next()
+                    .and_then(|priority| priority.parse().ok())
+                    .ok_or_else(|| error(String::from("expected an integer priority")))?;
+            }
+            _ => return Err(error(format!("unknown rule option {:?}", option))),
+        }
+    }
+    Ok(rule)
+}
+
+// Splits a line into its white-space-separated tokens, where white space that's quoted, escaped,
+// or in a character class doesn't separate tokens
+fn split_tokens(text: &str) -> Result<Vec<&str>, String> {
+    let mut tokens = Vec::new();
+    let mut token_start = None;
+    let mut chars = text.char_indices();
+    while let Some((offset, character)) = chars.next() {
+        if character.is_whitespace() {
+            if let Some(start) = token_start.take() {
+                tokens.push(&text[start..offset]);
+            }
+            continue;
+        }
+        token_start.get_or_insert(offset);
+        let closing = match character {
+            '\\' => {
+                chars.next();
+                continue;
+            }
+            '"' => '"',
+            '[' => ']',
+            _ => continue,
+        };
+        loop {
+            match chars.next() {
+                None => return Err(format!("missing closing {:?}", closing)),
+                Some((_, '\\')) => {
+                    chars.next();
+                }
+                Some((_, next)) if next == closing => break,
+                Some(_) => {}
+            }
+        }
+    }
+    if let Some(start) = token_start {
+        tokens.push(&text[start..]);
+    }
+    Ok(tokens)
+}
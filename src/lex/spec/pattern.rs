@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::lex::Regex;
+use crate::lex::unicode::character_ranges;
+
+/// Parses the textual pattern of a lexer specification into a [Regex] matching its UTF-8
+/// encoding.
+///
+/// The syntax is a subset of the one of lex: literal characters, `"quoted strings"`, character
+/// classes (`[a-z_]`, `[^"]`), `.` (any character but a newline), references to definitions
+/// (`{name}`), grouping, `|`, `*`, `+`, `?`, and the anchors `^` and `$`. The characters
+/// `\n`, `\t`, `\r`, `\0`, `\xHH` and `\u{HHHH}` are escaped as usual, and any other punctuation
+/// character may be escaped to match it literally.
+pub(super) fn parse_pattern(
+    source: &str,
+    definitions: &HashMap<String, Regex>,
+) -> Result<Regex, String> {
+    let mut parser = PatternParser {
+        chars: source.chars().peekable(),
+        definitions,
+    };
+    let pattern = parser.parse_union()?;
+    match parser.chars.next() {
+        None => Ok(pattern),
+        Some(character) => Err(format!("unexpected {:?} in pattern", character)),
+    }
+}
+
+// A recursive-descent parser, where each method parses a construct starting at the next
+// character
+struct PatternParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    definitions: &'a HashMap<String, Regex>,
+}
+
+impl PatternParser<'_> {
+    fn parse_union(&mut self) -> Result<Regex, String> {
+        let mut options = vec![self.parse_concat()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            options.push(self.parse_concat()?);
+        }
+        Ok(match options.len() {
+            1 => options.remove(0),
+            _ => Regex::union(options),
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Regex, String> {
+        let mut parts = Vec::new();
+        while self
+            .chars
+            .peek()
+            .is_some_and(|&character| character != '|' && character != ')')
+        {
+            parts.push(self.parse_repetition()?);
+        }
+        Ok(match parts.len() {
+            0 => Regex::epsilon(),
+            1 => parts.remove(0),
+            _ => Regex::concat(parts),
+        })
+    }
+
+    fn parse_repetition(&mut self) -> Result<Regex, String> {
+        let mut pattern = self.parse_atom()?;
+        while let Some(operator) = self.chars.next_if(|&character| "*+?".contains(character)) {
+            pattern = match operator {
+                '*' => Regex::star_from(pattern),
+                '+' => Regex::plus_from(pattern),
+                _ => Regex::optional(pattern),
+            };
+        }
+        Ok(pattern)
+    }
+
+    fn parse_atom(&mut self) -> Result<Regex, String> {
+        let character = self
+            .chars
+            .next()
+            .ok_or_else(|| String::from("pattern ends unexpectedly"))?;
+        match character {
+            '(' => {
+                let pattern = self.parse_union()?;
+                self.expect(')')?;
+                Ok(pattern)
+            }
+            '[' => self.parse_class(),
+            '"' => self.parse_string(),
+            '{' => self.parse_reference(),
+            '.' => Ok(complement_ranges(&[('\n', '\n')])),
+            '^' => Ok(Regex::line_start()),
+            '$' => Ok(Regex::line_end()),
+            '\\' => Ok(char_pattern(self.parse_escape()?)),
+            '*' | '+' | '?' => Err(format!("{:?} repeats nothing", character)),
+            _ => Ok(char_pattern(character)),
+        }
+    }
+
+    // Parses the rest of a character class, after its opening bracket
+    fn parse_class(&mut self) -> Result<Regex, String> {
+        let is_negated = self.chars.next_if_eq(&'^').is_some();
+        let mut ranges = Vec::new();
+        loop {
+            let start = match self.chars.next() {
+                None => return Err(String::from("unterminated character class")),
+                Some(']') => break,
+                Some('\\') => self.parse_escape()?,
+                Some(character) => character,
+            };
+            let end = match self.chars.next_if_eq(&'-') {
+                None => start,
+                Some(_) => match self.chars.next() {
+                    None => return Err(String::from("unterminated character class")),
+                    Some(']') => {
+                        // A trailing dash is literal
+                        ranges.push(('-', '-'));
+                        ranges.push((start, start));
+                        break;
+                    }
+                    Some('\\') => self.parse_escape()?,
+                    Some(character) => character,
+                },
+            };
+            if end < start {
+                return Err(format!("invalid range {:?}-{:?} in character class", start, end));
+            }
+            ranges.push((start, end));
+        }
+        Ok(match is_negated {
+            false => character_ranges(&merge_ranges(ranges)),
+            true => complement_ranges(&ranges),
+        })
+    }
+
+    // Parses the rest of a quoted string, after its opening quote
+    fn parse_string(&mut self) -> Result<Regex, String> {
+        let mut parts = Vec::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(String::from("unterminated string")),
+                Some('"') => break,
+                Some('\\') => parts.push(char_pattern(self.parse_escape()?)),
+                Some(character) => parts.push(char_pattern(character)),
+            }
+        }
+        Ok(Regex::concat(parts))
+    }
+
+    // Parses the rest of a reference to a definition, after its opening brace
+    fn parse_reference(&mut self) -> Result<Regex, String> {
+        let mut name = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(String::from("unterminated reference to a definition")),
+                Some('}') => break,
+                Some(character) => name.push(character),
+            }
+        }
+        self.definitions
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| format!("undefined name {:?}", name))
+    }
+
+    // Parses the rest of an escape sequence, after its backslash
+    fn parse_escape(&mut self) -> Result<char, String> {
+        let character = self
+            .chars
+            .next()
+            .ok_or_else(|| String::from("pattern ends with an incomplete escape"))?;
+        match character {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            'x' => {
+                let digits: String = (0..2).filter_map(|_| self.chars.next()).collect();
+                parse_code_point(&digits)
+            }
+            'u' => {
+                self.expect('{')?;
+                let mut digits = String::new();
+                loop {
+                    match self.chars.next() {
+                        None => return Err(String::from("unterminated \\u escape")),
+                        Some('}') => break,
+                        Some(digit) => digits.push(digit),
+                    }
+                }
+                parse_code_point(&digits)
+            }
+            _ if character.is_ascii_punctuation() || character == ' ' => Ok(character),
+            _ => Err(format!("unknown escape \\{}", character)),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(character) if character == expected => Ok(()),
+            Some(character) => Err(format!("expected {:?}, found {:?}", expected, character)),
+            None => Err(format!("expected {:?}, found the end of the pattern", expected)),
+        }
+    }
+}
+
+fn parse_code_point(digits: &str) -> Result<char, String> {
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| format!("invalid character code {:?}", digits))
+}
+
+// Matches the UTF-8 encoding of the character
+fn char_pattern(character: char) -> Regex {
+    let mut buffer = [0; 4];
+    let bytes = character.encode_utf8(&mut buffer).bytes();
+    match character.len_utf8() {
+        1 => Regex::single_char(character),
+        _ => Regex::concat(bytes.map(Regex::symbol).collect()),
+    }
+}
+
+// Sorts the ranges, and merges the overlapping or adjacent ones
+fn merge_ranges(mut ranges: Vec<(char, char)>) -> Vec<(char, char)> {
+    ranges.sort();
+    let mut merged: Vec<(char, char)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if (start as u32) <= (*last_end as u32) + 1 => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Matches any character outside the ranges
+fn complement_ranges(ranges: &[(char, char)]) -> Regex {
+    let mut complement = Vec::new();
+    let mut next_start = Some('\0');
+    for (start, end) in merge_ranges(ranges.to_vec()) {
+        if let Some(gap_start) = next_start {
+            if gap_start < start {
+                complement.push((gap_start, before(start)));
+            }
+        }
+        next_start = after(end);
+    }
+    if let Some(gap_start) = next_start {
+        complement.push((gap_start, char::MAX));
+    }
+    character_ranges(&complement)
+}
+
+// The characters adjacent to some character (surrogates are skipped by the UTF-8 encoding anyway)
+fn before(character: char) -> char {
+    char::from_u32(character as u32 - 1).unwrap_or('\u{D7FF}')
+}
+
+fn after(character: char) -> Option<char> {
+    match character {
+        char::MAX => None,
+        _ => Some(char::from_u32(character as u32 + 1).unwrap_or('\u{E000}')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automata::dfa::Dfa;
+    use crate::automata::nfa::Nfa;
+    use crate::handles::specials::AutomaticallyHandled;
+    use crate::lex::automaton::LexerSymbol;
+    use crate::lex::symbol_classes::SymbolClasses;
+
+    use super::*;
+
+    fn matches(pattern: &str, data: &str) -> bool {
+        let mut definitions = HashMap::new();
+        definitions.insert(String::from("digit"), Regex::character_range('0', '9'));
+        let pattern = parse_pattern(pattern, &definitions).unwrap();
+
+        let symbol_classes = SymbolClasses::new(&[pattern.clone()]);
+        let mut nfa = Nfa::new();
+        let (start, end) = pattern.build_into_nfa(&mut nfa, &symbol_classes);
+        nfa.set_initial_state(start);
+        nfa.label(end, Some(()));
+        let dfa: Dfa<LexerSymbol<u8>, ()> =
+            nfa.compile_to_dfa(|labels| labels.first().copied().copied());
+        let classes = data.bytes().map(|byte| symbol_classes.classify(byte));
+        dfa.scan(classes.map(|class| LexerSymbol::Input(class).handle()))
+            .is_some_and(|state| dfa.get_label(state).is_some())
+    }
+
+    #[test]
+    fn test_operators() {
+        assert!(matches("ab|c(d|e)*f?", "ab"));
+        assert!(matches("ab|c(d|e)*f?", "cdedf"));
+        assert!(!matches("ab|c(d|e)*f?", "abc"));
+        assert!(matches("a+", "aaa"));
+        assert!(!matches("a+", ""));
+        assert!(matches("x|", ""));
+    }
+
+    #[test]
+    fn test_strings_and_escapes() {
+        assert!(matches("\"a|b\\\"\"", "a|b\""));
+        assert!(matches("\\*\\x41\\u{3bb}\\n", "*Aλ\n"));
+        assert!(matches("é+", "éé"));
+    }
+
+    #[test]
+    fn test_character_classes() {
+        assert!(matches("[a-c_]+", "ab_c"));
+        assert!(!matches("[a-c_]", "d"));
+        assert!(matches("[^a-c]", "λ"));
+        assert!(!matches("[^a-c]", "b"));
+        assert!(matches("[a-]+", "a-"));
+        assert!(matches("[\\]]", "]"));
+        assert!(matches(".", "λ"));
+        assert!(!matches(".", "\n"));
+    }
+
+    #[test]
+    fn test_definitions() {
+        assert!(matches("{digit}+", "123"));
+        assert_eq!(
+            parse_pattern("{letter}", &HashMap::new()),
+            Err(String::from("undefined name \"letter\""))
+        );
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let definitions = HashMap::new();
+        assert!(parse_pattern("(a", &definitions).is_err());
+        assert!(parse_pattern("a)", &definitions).is_err());
+        assert!(parse_pattern("[a", &definitions).is_err());
+        assert!(parse_pattern("*", &definitions).is_err());
+        assert!(parse_pattern("[z-a]", &definitions).is_err());
+        assert!(parse_pattern("\\q", &definitions).is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+use crate::lex::{Alphabet, Regex};
+use crate::lex::automaton::MAX_SYMBOL_CLASSES;
+
+// Symbols of smaller serials (such as bytes and ASCII characters) are classified by a direct
+// lookup, rather than by a search of the classes' ranges
+const DIRECT_LOOKUP_SIZE: usize = 1 << 8;
+
+/// A class of symbols that the patterns of an automaton don't distinguish, which is consumed by
+/// the automaton in place of its symbols (see [SymbolClasses]).
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SymbolClass<Symbol> {
+    index: usize,
+    phantom: PhantomData<Symbol>,
+}
+
+impl<Symbol> SymbolClass<Symbol> {
+    pub(super) fn from_index(index: usize) -> Self {
+        Self {
+            index,
+            phantom: PhantomData,
+        }
+    }
+
+    pub(super) fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// A partition of an alphabet into classes of symbols that some patterns don't distinguish, so
+/// that the patterns match a sequence of symbols if and only if they match any other sequence of
+/// the same classes.
+///
+/// Automata consume the classes of the input's symbols rather than the symbols themselves, so they
+/// have a transition per class, rather than per symbol of the (possibly huge) alphabet. Two
+/// symbols are of the same class if every single-symbol pattern and every union of single symbols
+/// (such as a character class) either matches both of them or neither. The class of the symbols
+/// that the patterns don't mention at all is always the first one (possibly empty).
+pub(super) struct SymbolClasses<Symbol> {
+    // Each class applies to the serials from its range's start up to the start of the next one
+    ranges: Vec<(usize, usize)>,
+
+    // The classes of the serials below DIRECT_LOOKUP_SIZE
+    direct_lookup: Vec<usize>,
+
+    // Some symbol of each class, which is absent if the class is empty
+    representatives: Vec<Option<Symbol>>,
+}
+
+impl<Symbol> SymbolClasses<Symbol>
+where
+    Symbol: Alphabet,
+{
+    /// Partitions the alphabet into the classes of symbols that `patterns` don't distinguish.
+    ///
+    /// # Panics
+    ///
+    /// If the patterns distinguish more classes than the automata of lexical analyzers support.
+    pub(super) fn new(patterns: &[Regex<Symbol>]) -> Self {
+        let mut symbol_sets = Vec::new();
+        for pattern in patterns {
+            pattern.collect_symbol_sets(&mut symbol_sets);
+        }
+
+        // The sets of each mentioned symbol, by its serial, determine its class
+        let mut memberships: BTreeMap<usize, (Symbol, Vec<usize>)> = BTreeMap::new();
+        for (set_index, symbol_set) in symbol_sets.iter().enumerate() {
+            for &symbol in symbol_set {
+                let (_, set_indices) = memberships
+                    .entry(symbol.serial())
+                    .or_insert_with(|| (symbol, Vec::new()));
+                if set_indices.last() != Some(&set_index) {
+                    set_indices.push(set_index);
+                }
+            }
+        }
+
+        let mut classes = Self {
+            ranges: Vec::new(),
+            direct_lookup: Vec::new(),
+            representatives: vec![None],
+        };
+        let mut classes_by_memberships = HashMap::new();
+        let mut next_serial = 0;
+        for (serial, (symbol, set_indices)) in memberships {
+            let representatives = &mut classes.representatives;
+            let class = *classes_by_memberships
+                .entry(set_indices)
+                .or_insert_with(|| {
+                    representatives.push(Some(symbol));
+                    representatives.len() - 1
+                });
+            if serial > next_serial {
+                classes.extend_ranges(next_serial, 0);
+            }
+            classes.extend_ranges(serial, class);
+            next_serial = serial + 1;
+        }
+        classes.extend_ranges(next_serial, 0);
+
+        if classes.count() > MAX_SYMBOL_CLASSES {
+            panic!(
+                "Tried to create a lexical analyzer whose patterns distinguish {} classes of \
+                symbols, while its automata support at most {}",
+                classes.count(),
+                MAX_SYMBOL_CLASSES,
+            )
+        }
+
+        classes.direct_lookup = (0..DIRECT_LOOKUP_SIZE)
+            .map(|serial| classes.search_class(serial))
+            .collect();
+        classes.representatives[0] = classes.find_unmentioned_symbol();
+        classes
+    }
+
+    // Finds some symbol of the first class, whose ranges are the gaps between mentioned symbols
+    fn find_unmentioned_symbol(&self) -> Option<Symbol> {
+        let range_ends = self.ranges.iter().skip(1).map(|&(start, _)| start);
+        self.ranges
+            .iter()
+            .zip(range_ends.chain([usize::MAX]))
+            .filter(|((_, class), _)| *class == 0)
+            .find_map(|(&(start, _), end)| Symbol::find_symbol(start..end))
+    }
+
+    fn extend_ranges(&mut self, start: usize, class: usize) {
+        if self.ranges.last().map(|&(_, last_class)| last_class) != Some(class) {
+            self.ranges.push((start, class));
+        }
+    }
+
+    fn search_class(&self, serial: usize) -> usize {
+        let range_index = self.ranges.partition_point(|&(start, _)| start <= serial) - 1;
+        self.ranges[range_index].1
+    }
+
+    /// Gets the class of the symbol.
+    pub(super) fn classify(&self, symbol: Symbol) -> SymbolClass<Symbol> {
+        let serial = symbol.serial();
+        let index = match self.direct_lookup.get(serial) {
+            Some(&index) => index,
+            None => self.search_class(serial),
+        };
+        SymbolClass::from_index(index)
+    }
+
+    pub(super) fn count(&self) -> usize {
+        self.representatives.len()
+    }
+
+    /// Gets some symbol of the class, unless it's empty.
+    pub(super) fn get_representative(&self, class: SymbolClass<Symbol>) -> Option<Symbol> {
+        self.representatives[class.index]
+    }
+
+    /// Creates a pattern that matches any single symbol, by matching some symbol of each class.
+    /// This is only equivalent to [Regex::any_symbol] for automata of these classes, but doesn't
+    /// list the entire alphabet.
+    pub(super) fn any_symbol(&self) -> Regex<Symbol> {
+        Regex::union(
+            self.representatives
+                .iter()
+                .flatten()
+                .map(|&symbol| Regex::symbol(symbol))
+                .collect(),
+        )
+    }
+}
+
+impl<Symbol> Regex<Symbol>
+where
+    Symbol: Alphabet,
+{
+    // Collects the sets of symbols that the pattern distinguishes from the rest of the alphabet:
+    // its single symbols, where the single-symbol options of a union (such as a character class)
+    // form a single set
+    fn collect_symbol_sets(&self, symbol_sets: &mut Vec<Vec<Symbol>>) {
+        match self {
+            Regex::SingleCharacter { value } => symbol_sets.push(vec![*value]),
+            Regex::Anchor { .. } => {}
+            Regex::Union { options } => {
+                let mut single_characters = Vec::new();
+                for option in options {
+                    match option {
+                        Regex::SingleCharacter { value } => single_characters.push(*value),
+                        option => option.collect_symbol_sets(symbol_sets),
+                    }
+                }
+                if !single_characters.is_empty() {
+                    symbol_sets.push(single_characters);
+                }
+            }
+            Regex::Concat { parts } | Regex::Intersection { parts } => {
+                for part in parts {
+                    part.collect_symbol_sets(symbol_sets);
+                }
+            }
+            Regex::Star {
+                repeated_pattern: pattern,
+            }
+            | Regex::Complement {
+                complemented_pattern: pattern,
+            }
+            | Regex::Capture { pattern, .. } => pattern.collect_symbol_sets(symbol_sets),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes_of(classes: &SymbolClasses<u8>, data: &[u8]) -> Vec<usize> {
+        data.iter()
+            .map(|&symbol| classes.classify(symbol).index())
+            .collect()
+    }
+
+    #[test]
+    fn test_symbol_classes() {
+        let classes = SymbolClasses::new(&[
+            Regex::plus_from(Regex::character_range('a', 'z')),
+            Regex::constant_string("if"),
+        ]);
+        assert_eq!(classes.count(), 4);
+        assert_eq!(
+            classes_of(&classes, b"abfiz0_\xFF"),
+            vec![1, 1, 2, 3, 1, 0, 0, 0]
+        );
+        assert_eq!(
+            classes.get_representative(SymbolClass::from_index(0)),
+            Some(0)
+        );
+        assert_eq!(
+            classes.get_representative(SymbolClass::from_index(1)),
+            Some(b'a')
+        );
+    }
+
+    #[test]
+    fn test_unmentioned_class_is_empty_once_all_symbols_are_mentioned() {
+        let classes = SymbolClasses::new(&[Regex::any_symbol(), Regex::single_char('a')]);
+        assert_eq!(classes.count(), 3);
+        assert_eq!(classes.get_representative(SymbolClass::from_index(0)), None);
+        assert_eq!(classes_of(&classes, b"\x00ab"), vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_classes_of_large_serials() {
+        let classes = SymbolClasses::new(&[
+            Regex::union(('α'..='ω').map(Regex::symbol).collect()),
+            Regex::symbol('😀'),
+        ]);
+        assert_eq!(classes.count(), 3);
+        let classify = |symbol: char| classes.classify(symbol).index();
+        assert_eq!(
+            ['a', 'α', 'λ', 'ω', 'ϊ', '😀', '😁'].map(classify),
+            [0, 1, 1, 1, 0, 2, 0]
+        );
+        assert_eq!(
+            classes.get_representative(SymbolClass::from_index(0)),
+            Some('\0')
+        );
+    }
+
+    #[test]
+    fn test_unmentioned_class_representative_follows_mentioned_symbols() {
+        let classes = SymbolClasses::new(&[
+            Regex::union(('\0'..='a').map(Regex::symbol).collect()),
+            Regex::symbol('c'),
+        ]);
+        assert_eq!(
+            classes.get_representative(SymbolClass::from_index(0)),
+            Some('b')
+        );
+
+        let classes =
+            SymbolClasses::new(&[Regex::union((0..=u8::MAX - 1).map(Regex::symbol).collect())]);
+        assert_eq!(
+            classes.get_representative(SymbolClass::from_index(0)),
+            Some(u8::MAX)
+        );
+    }
+}
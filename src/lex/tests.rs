@@ -1,8 +1,16 @@
-use crate::lex::Lexeme;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use crate::handles::specials::AutomaticallyHandled;
+use crate::lex::{
+    Alphabet, Atom, Captures, DfaConstruction, DynamicLexemeType, Highlighter, IndentationError,
+    IndentationRules, Interner, Lexeme, LexemeList, LexerMode, LexerSpec, LexicalAnalyzerBuilder,
+    ModeAction, SourceId, Span, SpecError, TraceEvent, patterns,
+};
 use crate::lex::LexemeDescriptor;
 use crate::lex::lexical_analyzer::LexicalAnalyzer;
 use crate::lex::regex::Regex;
-use crate::readers::ByteArrayReader;
+use crate::readers::{AddressBasedReader, ByteArrayReader, Reader};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 enum TestLexemeType {
@@ -132,6 +140,1315 @@ fn test_lexical_analyzer_on_string() {
     assert_eq!(lexemes, analyzed_program())
 }
 
+#[test]
+fn test_lazy_lexical_analyzer_on_string() {
+    for cache_capacity in [1, 4, 1024] {
+        let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+        builder.set_dfa_construction(DfaConstruction::Lazy { cache_capacity });
+        let lexical_analyzer = builder.build();
+        let lexemes: Vec<Lexeme<TestLexemeType>> = lexical_analyzer
+            .analyze(&mut ByteArrayReader::from_string(
+                source_program_string().to_string(),
+            ))
+            .collect();
+        assert_eq!(lexemes, analyzed_program())
+    }
+}
+
+#[test]
+fn test_derivatives_lexical_analyzer_on_string() {
+    let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+    builder.set_dfa_construction(DfaConstruction::Derivatives);
+    let lexical_analyzer = builder.build();
+    let lexemes: Vec<Lexeme<TestLexemeType>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string(
+            source_program_string().to_string(),
+        ))
+        .collect();
+    assert_eq!(lexemes, analyzed_program())
+}
+
+#[test]
+fn test_complement_pattern() {
+    let anything = Regex::complement(Regex::union(vec![]));
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new(
+            "comment",
+            Regex::concat(vec![
+                Regex::constant_string("/*"),
+                Regex::complement(Regex::concat(vec![
+                    anything.clone(),
+                    Regex::constant_string("*/"),
+                    anything,
+                ])),
+                Regex::constant_string("*/"),
+            ]),
+        ),
+        LexemeDescriptor::new("slash", Regex::single_char('/')),
+        LexemeDescriptor::new("star", Regex::single_char('*')),
+    ]);
+    builder.set_dfa_construction(DfaConstruction::Derivatives);
+    let lexical_analyzer = builder.build();
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice("/* a */*//**/"))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("comment", "/* a */"),
+            Lexeme::new("star", "*"),
+            Lexeme::new("slash", "/"),
+            Lexeme::new("comment", "/**/"),
+        ]
+    )
+}
+
+// Counts the bytes read by the lexical analyzer
+struct CountingReader {
+    reader: ByteArrayReader,
+    read_count: usize,
+}
+
+impl Reader<u8> for CountingReader {
+    fn read_next(&mut self) -> Option<u8> {
+        self.read_count += 1;
+        self.reader.read_next()
+    }
+
+    fn set_head(&mut self) {
+        self.reader.set_head()
+    }
+
+    fn set_tail(&mut self) {
+        self.reader.set_tail()
+    }
+
+    fn move_cursor_to_tail(&mut self) {
+        self.reader.move_cursor_to_tail()
+    }
+
+    fn get_sequence(&self) -> impl Iterator<Item=u8> {
+        self.reader.get_sequence()
+    }
+}
+
+fn count_reads_on_worst_case(is_linear_time: bool, input_length: usize) -> usize {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new("a", Regex::single_char('a')),
+        LexemeDescriptor::new(
+            "a*b",
+            Regex::concat(vec![
+                Regex::star_from(Regex::single_char('a')),
+                Regex::single_char('b'),
+            ]),
+        ),
+    ]);
+    builder.set_linear_time_tokenization(is_linear_time);
+    let lexical_analyzer = builder.build();
+
+    let mut reader = CountingReader {
+        reader: ByteArrayReader::from_string("a".repeat(input_length)),
+        read_count: 0,
+    };
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer.analyze(&mut reader).collect();
+    assert_eq!(lexemes.len(), input_length);
+    assert!(lexemes.iter().all(|lexeme| lexeme == &Lexeme::new("a", "a")));
+    reader.read_count
+}
+
+#[test]
+fn test_linear_time_tokenization() {
+    let input_length = 1000;
+    assert!(count_reads_on_worst_case(false, input_length) > input_length * input_length / 2);
+    assert!(count_reads_on_worst_case(true, input_length) <= 3 * input_length);
+}
+
+#[test]
+fn test_linear_time_lexical_analyzer_on_string() {
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 2 },
+    ] {
+        let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+        builder.set_dfa_construction(dfa_construction);
+        builder.set_linear_time_tokenization(true);
+        let lexical_analyzer = builder.build();
+        let lexemes: Vec<Lexeme<TestLexemeType>> = lexical_analyzer
+            .analyze(&mut ByteArrayReader::from_string(
+                source_program_string().to_string(),
+            ))
+            .collect();
+        assert_eq!(lexemes, analyzed_program())
+    }
+}
+
+fn line_oriented_lexeme_descriptors() -> Vec<LexemeDescriptor<&'static str>> {
+    vec![
+        LexemeDescriptor::new(
+            "comment",
+            Regex::concat(vec![
+                Regex::line_start(),
+                Regex::single_char('#'),
+                Regex::star_from(Regex::character_range(' ', '~')),
+            ]),
+        ),
+        LexemeDescriptor::new(
+            "trailing space",
+            Regex::concat(vec![
+                Regex::plus_from(Regex::single_char(' ')),
+                Regex::line_end(),
+            ]),
+        ),
+        LexemeDescriptor::new(
+            "final word",
+            Regex::concat(vec![
+                Regex::plus_from(Regex::character_range('a', 'z')),
+                Regex::input_end(),
+            ]),
+        ),
+        LexemeDescriptor::new("word", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char("hash", '#'),
+        LexemeDescriptor::special_char("space", ' '),
+        LexemeDescriptor::special_char("newline", '\n'),
+    ]
+}
+
+#[test]
+fn test_anchors() {
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 4 },
+        DfaConstruction::Derivatives,
+    ] {
+        for is_linear_time in [false, true] {
+            let mut builder = LexicalAnalyzerBuilder::new(line_oriented_lexeme_descriptors());
+            builder.set_dfa_construction(dfa_construction);
+            builder.set_linear_time_tokenization(is_linear_time);
+            let lexical_analyzer = builder.build();
+            let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+                .analyze(&mut ByteArrayReader::from_string_slice(
+                    "# a comment\nall a#b  \n#c d\nend",
+                ))
+                .collect();
+            assert_eq!(
+                lexemes,
+                vec![
+                    Lexeme::new("comment", "# a comment"),
+                    Lexeme::new("newline", "\n"),
+                    Lexeme::new("word", "all"),
+                    Lexeme::new("space", " "),
+                    Lexeme::new("word", "a"),
+                    Lexeme::new("hash", "#"),
+                    Lexeme::new("word", "b"),
+                    Lexeme::new("trailing space", "  "),
+                    Lexeme::new("newline", "\n"),
+                    Lexeme::new("comment", "#c d"),
+                    Lexeme::new("newline", "\n"),
+                    Lexeme::new("final word", "end"),
+                ]
+            )
+        }
+    }
+}
+
+fn keyword_and_identifier_descriptors(
+    keyword_priority: i32,
+) -> Vec<LexemeDescriptor<&'static str>> {
+    vec![
+        LexemeDescriptor::new(
+            "identifier",
+            Regex::plus_from(Regex::character_range('a', 'z')),
+        ),
+        LexemeDescriptor::keyword("if", "if").with_priority(keyword_priority),
+        LexemeDescriptor::special_char("space", ' '),
+    ]
+}
+
+#[test]
+fn test_explicit_priorities() {
+    for (keyword_priority, expected_type) in [(1, "if"), (0, "identifier"), (-1, "identifier")] {
+        let mut builder =
+            LexicalAnalyzerBuilder::new(keyword_and_identifier_descriptors(keyword_priority));
+        builder.set_strict_priorities(keyword_priority != 0);
+        let lexemes: Vec<Lexeme<&str>> = builder
+            .build()
+            .analyze(&mut ByteArrayReader::from_string_slice("if iff"))
+            .collect();
+        assert_eq!(
+            lexemes,
+            vec![
+                Lexeme::new(expected_type, "if"),
+                Lexeme::new("space", " "),
+                Lexeme::new("identifier", "iff"),
+            ]
+        )
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_strict_priorities_conflict() {
+    let mut builder = LexicalAnalyzerBuilder::new(keyword_and_identifier_descriptors(0));
+    builder.set_strict_priorities(true);
+    builder.build();
+}
+
+#[test]
+fn test_strict_priorities_with_lazy_dfa() {
+    let mut builder = LexicalAnalyzerBuilder::new(keyword_and_identifier_descriptors(0));
+    builder.set_strict_priorities(true);
+    builder.set_dfa_construction(DfaConstruction::Lazy { cache_capacity: 16 });
+    let lexemes: Vec<Lexeme<&str>> = builder
+        .build()
+        .analyze(&mut ByteArrayReader::from_string_slice("if"))
+        .collect();
+    assert_eq!(lexemes, vec![Lexeme::new("identifier", "if")]);
+}
+
+fn indentation_sensitive_lexemes(
+    source: &str,
+    tab_stop: Option<usize>,
+) -> (Vec<Lexeme<&str>>, Option<IndentationError>) {
+    let lexical_analyzer = LexicalAnalyzer::new(vec![
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char(":", ':'),
+        LexemeDescriptor::special_char("(", '('),
+        LexemeDescriptor::special_char(")", ')'),
+        LexemeDescriptor::new(
+            "space",
+            Regex::plus_from(Regex::union(vec![
+                Regex::single_char(' '),
+                Regex::single_char('\t'),
+            ])),
+        ),
+        LexemeDescriptor::new(
+            "comment",
+            Regex::concat(vec![
+                Regex::single_char('#'),
+                Regex::star_from(Regex::character_range(' ', '~')),
+            ]),
+        ),
+        LexemeDescriptor::special_char("newline", '\n'),
+    ]);
+    let mut indentation_rules = IndentationRules::new("space", "newline", "indent", "dedent");
+    indentation_rules.add_ignored("comment");
+    indentation_rules.add_bracket_pair("(", ")");
+    indentation_rules.set_tab_stop(tab_stop);
+
+    let mut reader = ByteArrayReader::from_string(source.to_string());
+    let mut lexemes = indentation_rules.apply(lexical_analyzer.analyze(&mut reader));
+    let collected_lexemes = lexemes.by_ref().collect();
+    (collected_lexemes, lexemes.error().cloned())
+}
+
+#[test]
+fn test_indentation() {
+    let (lexemes, error) = indentation_sensitive_lexemes(
+        "if a:\n  b (\nc\n  )\n\n    # comment\n  if d:\n      e\nf",
+        Some(8),
+    );
+    assert_eq!(error, None);
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("name", "if"),
+            Lexeme::new("name", "a"),
+            Lexeme::new(":", ":"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("indent", "  "),
+            Lexeme::new("name", "b"),
+            Lexeme::new("(", "("),
+            Lexeme::new("name", "c"),
+            Lexeme::new(")", ")"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("name", "if"),
+            Lexeme::new("name", "d"),
+            Lexeme::new(":", ":"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("indent", "      "),
+            Lexeme::new("name", "e"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("dedent", ""),
+            Lexeme::new("dedent", ""),
+            Lexeme::new("name", "f"),
+            Lexeme::new("newline", ""),
+        ]
+    );
+}
+
+#[test]
+fn test_inconsistent_dedent() {
+    let (lexemes, error) = indentation_sensitive_lexemes("a\n    b\n  c\n", Some(8));
+    assert_eq!(
+        error,
+        Some(IndentationError::InconsistentDedent {
+            line: 3,
+            indentation: 2,
+        })
+    );
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("name", "a"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("indent", "    "),
+            Lexeme::new("name", "b"),
+            Lexeme::new("newline", "\n"),
+        ]
+    );
+}
+
+#[test]
+fn test_indentation_with_tab_stops() {
+    let lexeme_types = |lexemes: Vec<Lexeme<&'static str>>| {
+        lexemes
+            .into_iter()
+            .map(|lexeme| lexeme.lexeme_type)
+            .collect::<Vec<_>>()
+    };
+
+    // The tab is as wide as the first line's spaces
+    let source = "a:\n    b:\n        c\n\td\n";
+    let (lexemes, error) = indentation_sensitive_lexemes(source, Some(8));
+    assert_eq!(error, None);
+    assert_eq!(
+        lexeme_types(lexemes),
+        vec![
+            "name", ":", "newline", "indent", "name", ":", "newline", "indent", "name", "newline",
+            "name", "newline", "dedent", "dedent",
+        ]
+    );
+
+    // The tab is only as wide as the second line's spaces
+    let (lexemes, error) = indentation_sensitive_lexemes(source, Some(4));
+    assert_eq!(error, None);
+    assert_eq!(
+        lexeme_types(lexemes),
+        vec![
+            "name", ":", "newline", "indent", "name", ":", "newline", "indent", "name", "newline",
+            "dedent", "name", "newline", "dedent",
+        ]
+    );
+
+    // Tabs advance to the next tab stop, rather than by a fixed width
+    let (lexemes, error) = indentation_sensitive_lexemes("a:\n  \tb\n    c\n", Some(4));
+    assert_eq!(error, None);
+    assert_eq!(
+        lexeme_types(lexemes),
+        vec!["name", ":", "newline", "indent", "name", "newline", "name", "newline", "dedent"]
+    );
+}
+
+#[test]
+fn test_indentation_without_tab_stops() {
+    let (lexemes, error) = indentation_sensitive_lexemes("a:\n\tb:\n\t  c\n\td\ne\n", None);
+    assert_eq!(error, None);
+    assert_eq!(
+        lexemes
+            .into_iter()
+            .filter(|lexeme| ["indent", "dedent"].contains(&lexeme.lexeme_type))
+            .collect::<Vec<_>>(),
+        vec![
+            Lexeme::new("indent", "\t"),
+            Lexeme::new("indent", "\t  "),
+            Lexeme::new("dedent", ""),
+            Lexeme::new("dedent", ""),
+        ]
+    );
+
+    // Whether spaces are indented relative to a tab is ambiguous
+    for source in ["a:\n\tb:\n   c\n", "a:\n\tb:\n\t  c\n  \td\n", "a:\n\tb\n \n c\n"] {
+        let (_, error) = indentation_sensitive_lexemes(source, None);
+        assert!(
+            matches!(error, Some(IndentationError::InconsistentTabs { .. })),
+            "Unexpected error for {:?}: {:?}",
+            source,
+            error
+        );
+    }
+}
+
+fn nested_comments_lexical_analyzer(
+    dfa_construction: DfaConstruction,
+) -> LexicalAnalyzer<&'static str> {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::nested_region(
+            "comment",
+            Regex::constant_string("/*"),
+            Regex::constant_string("*/"),
+        ),
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char("slash", '/'),
+        LexemeDescriptor::special_char("star", '*'),
+        LexemeDescriptor::special_char("space", ' '),
+    ]);
+    builder.set_dfa_construction(dfa_construction);
+    builder.build()
+}
+
+#[test]
+fn test_nested_region() {
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 4 },
+        DfaConstruction::Derivatives,
+    ] {
+        let lexemes: Vec<Lexeme<&str>> = nested_comments_lexical_analyzer(dfa_construction)
+            .analyze(&mut ByteArrayReader::from_string_slice(
+                "a /* b /* c */ /*/ */ */*/ d /**/",
+            ))
+            .collect();
+        assert_eq!(
+            lexemes,
+            vec![
+                Lexeme::new("name", "a"),
+                Lexeme::new("space", " "),
+                Lexeme::new("comment", "/* b /* c */ /*/ */ */"),
+                Lexeme::new("star", "*"),
+                Lexeme::new("slash", "/"),
+                Lexeme::new("space", " "),
+                Lexeme::new("name", "d"),
+                Lexeme::new("space", " "),
+                Lexeme::new("comment", "/**/"),
+            ]
+        )
+    }
+}
+
+#[test]
+#[should_panic(expected = "starting at offset 2")]
+fn test_unterminated_nested_region() {
+    let _ = nested_comments_lexical_analyzer(DfaConstruction::Eager)
+        .analyze(&mut ByteArrayReader::from_string_slice("a /* b /* c */"))
+        .collect::<Vec<Lexeme<&str>>>();
+}
+
+fn expression_lexeme_descriptors(
+    closing_brace_type: &'static str,
+) -> Vec<LexemeDescriptor<&'static str>> {
+    vec![
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char("plus", '+'),
+        LexemeDescriptor::special_char("space", ' '),
+        LexemeDescriptor::special_char("quote", '"'),
+        LexemeDescriptor::special_char("{", '{'),
+        LexemeDescriptor::special_char(closing_brace_type, '}'),
+    ]
+}
+
+#[test]
+fn test_string_interpolation_modes() {
+    let mut builder = LexicalAnalyzerBuilder::new(expression_lexeme_descriptors("}"));
+    let string_mode = builder.new_mode(vec![
+        LexemeDescriptor::new(
+            "string part",
+            Regex::plus_from(Regex::union(vec![
+                Regex::character_range(' ', '!'),
+                Regex::character_range('%', '~'),
+            ])),
+        ),
+        LexemeDescriptor::keyword("interpolation start", "${"),
+        LexemeDescriptor::special_char("quote", '"'),
+    ]);
+    let interpolation_mode = builder.new_mode(expression_lexeme_descriptors("interpolation end"));
+    let block_mode = builder.new_mode(expression_lexeme_descriptors("}"));
+    for mode in [LexerMode::INITIAL, interpolation_mode, block_mode] {
+        builder.set_mode_action(mode, "quote", ModeAction::Push(string_mode));
+        builder.set_mode_action(mode, "{", ModeAction::Push(block_mode));
+    }
+    builder.set_mode_action(string_mode, "quote", ModeAction::Pop);
+    builder.set_mode_action(
+        string_mode,
+        "interpolation start",
+        ModeAction::Push(interpolation_mode),
+    );
+    builder.set_mode_action(interpolation_mode, "interpolation end", ModeAction::Pop);
+    builder.set_mode_action(block_mode, "}", ModeAction::Pop);
+    builder.set_linear_time_tokenization(true);
+    let lexical_analyzer = builder.build();
+
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice(
+            "\"a ${b + {c} + \"d${e}\"} f\" + g",
+        ))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("quote", "\""),
+            Lexeme::new("string part", "a "),
+            Lexeme::new("interpolation start", "${"),
+            Lexeme::new("name", "b"),
+            Lexeme::new("space", " "),
+            Lexeme::new("plus", "+"),
+            Lexeme::new("space", " "),
+            Lexeme::new("{", "{"),
+            Lexeme::new("name", "c"),
+            Lexeme::new("}", "}"),
+            Lexeme::new("space", " "),
+            Lexeme::new("plus", "+"),
+            Lexeme::new("space", " "),
+            Lexeme::new("quote", "\""),
+            Lexeme::new("string part", "d"),
+            Lexeme::new("interpolation start", "${"),
+            Lexeme::new("name", "e"),
+            Lexeme::new("interpolation end", "}"),
+            Lexeme::new("quote", "\""),
+            Lexeme::new("interpolation end", "}"),
+            Lexeme::new("string part", " f"),
+            Lexeme::new("quote", "\""),
+            Lexeme::new("space", " "),
+            Lexeme::new("plus", "+"),
+            Lexeme::new("space", " "),
+            Lexeme::new("name", "g"),
+        ]
+    )
+}
+
+#[test]
+fn test_captures() {
+    let digits = Regex::union(vec![
+        Regex::character_range('0', '9'),
+        Regex::character_range('A', 'F'),
+    ]);
+    let lexeme_descriptors = || {
+        vec![
+            LexemeDescriptor::new(
+                "integer",
+                Regex::concat(vec![
+                    Regex::optional(Regex::capture(
+                        "base",
+                        Regex::union(vec![
+                            Regex::constant_string("0x"),
+                            Regex::constant_string("0b"),
+                        ]),
+                    )),
+                    Regex::capture("digits", Regex::plus_from(digits.clone())),
+                    Regex::optional(Regex::capture(
+                        "suffix",
+                        Regex::concat(vec![
+                            Regex::union(vec![Regex::single_char('u'), Regex::single_char('i')]),
+                            Regex::plus_from(Regex::character_range('0', '9')),
+                        ]),
+                    )),
+                ]),
+            ),
+            LexemeDescriptor::new(
+                "line",
+                Regex::concat(vec![
+                    Regex::single_char('#'),
+                    Regex::capture(
+                        "text",
+                        Regex::star_from(Regex::character_range('a', 'z')),
+                    ),
+                    Regex::line_end(),
+                ]),
+            ),
+            LexemeDescriptor::new("space", Regex::white_space()),
+        ]
+    };
+
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 4 },
+        DfaConstruction::Derivatives,
+    ] {
+        let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+        builder.set_dfa_construction(dfa_construction);
+        let lexical_analyzer = builder.build();
+
+        let lexemes: Vec<(Lexeme<&str>, Captures)> = lexical_analyzer
+            .analyze_captured(&mut ByteArrayReader::from_string_slice("0x1Fu64 42 #ab\n#c"))
+            .collect();
+        let captures: Vec<Vec<Option<&str>>> = lexemes
+            .iter()
+            .map(|(lexeme, captures)| {
+                ["base", "digits", "suffix", "text"]
+                    .into_iter()
+                    .map(|name| Some(&lexeme.contents[captures.get(name)?]))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            captures,
+            vec![
+                vec![Some("0x"), Some("1F"), Some("u64"), None],
+                vec![None, None, None, None],
+                vec![None, Some("42"), None, None],
+                vec![None, None, None, None],
+                vec![None, None, None, Some("ab")],
+                vec![None, None, None, None],
+                vec![None, None, None, Some("c")],
+            ]
+        );
+    }
+}
+
+fn list_captures<'a>(lexemes: &'a [(Lexeme<&str>, Captures)]) -> Vec<Vec<(&'a str, &'a str)>> {
+    lexemes
+        .iter()
+        .map(|(lexeme, captures)| {
+            captures
+                .iter()
+                .map(|(name, range)| (name, &lexeme.contents[range]))
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_captures_prefer_earlier_options_and_longer_repetitions() {
+    let x = || Regex::single_char('x');
+    let lexical_analyzer = LexicalAnalyzer::new(vec![
+        LexemeDescriptor::new(
+            "xs",
+            Regex::concat(vec![
+                Regex::capture("first", Regex::star_from(x())),
+                Regex::capture("second", Regex::star_from(x())),
+                Regex::single_char(';'),
+            ]),
+        ),
+        LexemeDescriptor::new(
+            "digits",
+            Regex::plus_from(Regex::concat(vec![
+                Regex::capture("digit", Regex::character_range('0', '9')),
+                Regex::single_char(','),
+            ])),
+        ),
+        LexemeDescriptor::new(
+            "ab",
+            Regex::union(vec![
+                Regex::capture("whole", Regex::constant_string("ab")),
+                Regex::concat(vec![
+                    Regex::capture("a", Regex::single_char('a')),
+                    Regex::single_char('b'),
+                ]),
+            ]),
+        ),
+        LexemeDescriptor::new(
+            "a",
+            Regex::union(vec![
+                Regex::concat(vec![
+                    Regex::capture("at line end", Regex::single_char('a')),
+                    Regex::line_end(),
+                ]),
+                Regex::capture("elsewhere", Regex::single_char('a')),
+            ]),
+        ),
+        LexemeDescriptor::new("space", Regex::white_space()),
+    ]);
+    let lexemes: Vec<(Lexeme<&str>, Captures)> = lexical_analyzer
+        .analyze_captured(&mut ByteArrayReader::from_string_slice(
+            "xxx; 1,2,3, ab a \na",
+        ))
+        .collect();
+    assert_eq!(
+        list_captures(&lexemes),
+        vec![
+            vec![("first", "xxx"), ("second", "")],
+            vec![],
+            vec![("digit", "3")],
+            vec![],
+            vec![("whole", "ab")],
+            vec![],
+            vec![("elsewhere", "a")],
+            vec![],
+            vec![],
+            vec![("at line end", "a")],
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "both capture groups and intersection or complement patterns")]
+fn test_captures_in_intersection() {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![LexemeDescriptor::new(
+        "name",
+        Regex::intersection(vec![
+            Regex::capture("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+            Regex::complement(Regex::constant_string("if")),
+        ]),
+    )]);
+    builder.set_dfa_construction(DfaConstruction::Derivatives);
+    let _: LexicalAnalyzer<&str> = builder.build();
+}
+
+#[test]
+fn test_end_of_input() {
+    let quote = || LexemeDescriptor::special_char("quote", '"');
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        quote(),
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char("newline", '\n'),
+        LexemeDescriptor::new("space", Regex::plus_from(Regex::single_char(' '))),
+    ]);
+    let string_mode = builder.new_mode(vec![
+        quote(),
+        LexemeDescriptor::new("string part", Regex::plus_from(Regex::character_range(' ', '!'))),
+    ]);
+    builder.set_mode_action(LexerMode::INITIAL, "quote", ModeAction::Push(string_mode));
+    builder.set_mode_action(string_mode, "quote", ModeAction::Pop);
+    builder.set_end_of_input_action(move |end_of_input| {
+        if end_of_input.current_mode() == string_mode {
+            vec![Lexeme::new("unterminated string", "")]
+        } else {
+            assert_eq!(end_of_input.mode_stack, vec![LexerMode::INITIAL]);
+            vec![]
+        }
+    });
+    builder.set_end_of_input_lexeme("end of input");
+    let lexical_analyzer = builder.build();
+
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice("a\"  !"))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("name", "a"),
+            Lexeme::new("quote", "\""),
+            Lexeme::new("string part", "  !"),
+            Lexeme::new("unterminated string", ""),
+            Lexeme::new("end of input", ""),
+        ]
+    );
+
+    let mut indentation_rules = IndentationRules::new("space", "newline", "indent", "dedent");
+    indentation_rules.set_end_of_input("end of input");
+    let lexemes: Vec<Lexeme<&str>> = indentation_rules
+        .apply(lexical_analyzer.analyze(&mut ByteArrayReader::from_string_slice("a\n  b")))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("name", "a"),
+            Lexeme::new("newline", "\n"),
+            Lexeme::new("indent", "  "),
+            Lexeme::new("name", "b"),
+            Lexeme::new("newline", ""),
+            Lexeme::new("dedent", ""),
+            Lexeme::new("end of input", ""),
+        ]
+    );
+}
+
+#[test]
+fn test_lookahead_and_push_back() {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::special_char("(", '('),
+        LexemeDescriptor::special_char(")", ')'),
+    ]);
+    builder.set_end_of_input_lexeme("end of input");
+    let lexical_analyzer = builder.build();
+    let mut reader = ByteArrayReader::from_string_slice("(a)b");
+    let mut lexemes = lexical_analyzer.analyze(&mut reader);
+
+    assert_eq!(lexemes.peek_nth(4), Some(&Lexeme::new("end of input", "")));
+    assert_eq!(lexemes.peek_nth(5), None);
+    assert_eq!(lexemes.peek_nth(1), Some(&Lexeme::new("name", "a")));
+    let left_paren = lexemes.next().unwrap();
+    let name = lexemes.next().unwrap();
+    assert_eq!(lexemes.peek(), Some(&Lexeme::new(")", ")")));
+    lexemes.push_back(name);
+    lexemes.push_back(left_paren);
+    lexemes.push_back(Lexeme::new("name", "c"));
+    assert_eq!(
+        lexemes.collect::<Vec<Lexeme<&str>>>(),
+        vec![
+            Lexeme::new("name", "c"),
+            Lexeme::new("(", "("),
+            Lexeme::new("name", "a"),
+            Lexeme::new(")", ")"),
+            Lexeme::new("name", "b"),
+            Lexeme::new("end of input", ""),
+        ]
+    );
+}
+
+#[test]
+fn test_include_stack() {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::new(
+            "include",
+            Regex::concat(vec![
+                Regex::single_char('@'),
+                Regex::capture("path", Regex::character_range('0', '9')),
+            ]),
+        ),
+    ]);
+    builder.set_end_of_input_lexeme("end of input");
+    let lexical_analyzer = builder.build();
+
+    let files = ["a@1b@2", "c@2", "", "d"];
+    let open = |path: &str| {
+        let index: usize = path.parse().ok()?;
+        Some(ByteArrayReader::from_string_slice(files[index]))
+    };
+    let lexemes: Vec<(Lexeme<&str>, Span)> = lexical_analyzer
+        .analyze_with_includes(open("0").unwrap(), |lexeme, captures| {
+            open(&lexeme.contents[captures.get("path")?])
+        })
+        .collect();
+    let span = |source, start, end| Span {
+        source: SourceId(source),
+        start,
+        end,
+    };
+    assert_eq!(
+        lexemes
+            .into_iter()
+            .map(|(lexeme, span)| (lexeme.contents, span))
+            .collect::<Vec<(String, Span)>>(),
+        vec![
+            (String::from("a"), span(0, 0, 1)),
+            (String::from("@1"), span(0, 1, 3)),
+            (String::from("c"), span(1, 0, 1)),
+            (String::from("@2"), span(1, 1, 3)),
+            (String::from("b"), span(0, 3, 4)),
+            (String::from("@2"), span(0, 4, 6)),
+            (String::from(""), span(0, 6, 6)),
+        ]
+    );
+}
+
+// The lexemes of a previous lexical analysis, analyzed in turn by another analyzer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Token {
+    Word,
+    Number,
+    Open,
+    Close,
+    Newline,
+}
+
+impl AutomaticallyHandled for Token {
+    type HandleCoreType = u8;
+    fn serial(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Alphabet for Token {
+    type Contents = Vec<Token>;
+
+    fn list_symbols() -> Vec<Self> {
+        vec![Token::Word, Token::Number, Token::Open, Token::Close, Token::Newline]
+    }
+
+    fn to_contents(symbols: Vec<Self>) -> Vec<Token> {
+        symbols
+    }
+
+    fn is_line_break(&self) -> bool {
+        *self == Token::Newline
+    }
+}
+
+#[test]
+fn test_non_byte_alphabet() {
+    let lexeme_descriptors = || {
+        vec![
+            LexemeDescriptor::new(
+                "record",
+                Regex::concat(vec![
+                    Regex::line_start(),
+                    Regex::symbol(Token::Word),
+                    Regex::capture("fields", Regex::plus_from(Regex::symbol(Token::Number))),
+                    Regex::line_end(),
+                ]),
+            ),
+            LexemeDescriptor::nested_region(
+                "group",
+                Regex::symbol(Token::Open),
+                Regex::symbol(Token::Close),
+            ),
+            LexemeDescriptor::new("newline", Regex::symbol(Token::Newline)),
+            LexemeDescriptor::new("other", Regex::any_symbol()),
+        ]
+    };
+    let input = vec![
+        Token::Word,
+        Token::Number,
+        Token::Number,
+        Token::Newline,
+        Token::Open,
+        Token::Open,
+        Token::Close,
+        Token::Word,
+        Token::Close,
+        Token::Word,
+        Token::Number,
+        Token::Newline,
+    ];
+
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 4 },
+        DfaConstruction::Derivatives,
+    ] {
+        let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+        builder.set_dfa_construction(dfa_construction);
+        builder.set_linear_time_tokenization(true);
+        let lexical_analyzer = builder.build();
+
+        let lexemes: Vec<(Lexeme<&str, Token>, Captures)> = lexical_analyzer
+            .analyze_captured(&mut AddressBasedReader::raw_new(input.clone()))
+            .collect();
+        assert_eq!(
+            lexemes
+                .iter()
+                .map(|(lexeme, _)| (lexeme.lexeme_type, lexeme.contents.len()))
+                .collect::<Vec<(&str, usize)>>(),
+            vec![
+                ("record", 3),
+                ("newline", 1),
+                ("group", 5),
+                ("other", 1),
+                ("other", 1),
+                ("newline", 1),
+            ]
+        );
+        assert_eq!(lexemes[0].1.get("fields"), Some(1..3));
+    }
+}
+
+#[test]
+fn test_char_alphabet() {
+    let letters = || Regex::union(('α'..='ω').chain('a'..='z').map(Regex::symbol).collect());
+    let lexeme_descriptors = || {
+        vec![
+            LexemeDescriptor::new("word", Regex::plus_from(letters())),
+            LexemeDescriptor::new("smile", Regex::symbol('😀')),
+            LexemeDescriptor::nested_region("quote", Regex::symbol('«'), Regex::symbol('»')),
+            LexemeDescriptor::new(
+                "line end",
+                Regex::concat(vec![Regex::symbol('\n'), Regex::line_start()]),
+            ),
+            LexemeDescriptor::new(
+                "other",
+                Regex::union(vec![Regex::symbol('ό'), Regex::symbol(' ')]),
+            ),
+        ]
+    };
+
+    // Symbols that no pattern mentions may still appear in nested regions
+    let input: Vec<char> = "λόγος 😀«a«中»»\n".chars().collect();
+
+    for dfa_construction in [
+        DfaConstruction::Eager,
+        DfaConstruction::Lazy { cache_capacity: 4 },
+        DfaConstruction::Derivatives,
+    ] {
+        let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+        builder.set_dfa_construction(dfa_construction);
+        let lexical_analyzer = builder.build();
+
+        let lexemes: Vec<(&str, String)> = lexical_analyzer
+            .analyze(&mut AddressBasedReader::raw_new(input.clone()))
+            .map(|lexeme| (lexeme.lexeme_type, lexeme.contents))
+            .collect();
+        assert_eq!(
+            lexemes,
+            vec![
+                ("word", String::from("λ")),
+                ("other", String::from("ό")),
+                ("word", String::from("γος")),
+                ("other", String::from(" ")),
+                ("smile", String::from("😀")),
+                ("quote", String::from("«a«中»»")),
+                ("line end", String::from("\n")),
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_char_alphabet_dot_export() {
+    let lexical_analyzer: LexicalAnalyzer<&str, char> = LexicalAnalyzer::new(vec![
+        LexemeDescriptor::new(
+            "word",
+            Regex::plus_from(Regex::union(('α'..='ω').map(Regex::symbol).collect())),
+        ),
+        LexemeDescriptor::new("letter", Regex::symbol('λ')).with_priority(1),
+    ]);
+    let dot = lexical_analyzer.minimized_dfa_to_dot(LexerMode::INITIAL);
+    assert!(dot.contains("[label=\"α-κ, μ-ω\"]"), "{dot}");
+    assert!(dot.contains("[label=\"α-ω\"]"), "{dot}");
+}
+
+// An alphabet whose symbols can all be distinguished by patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct WideSymbol(u16);
+
+impl AutomaticallyHandled for WideSymbol {
+    type HandleCoreType = u16;
+    fn serial(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Alphabet for WideSymbol {
+    type Contents = Vec<WideSymbol>;
+
+    fn list_symbols() -> Vec<Self> {
+        (u16::MIN..=u16::MAX).map(WideSymbol).collect()
+    }
+
+    fn to_contents(symbols: Vec<Self>) -> Vec<WideSymbol> {
+        symbols
+    }
+}
+
+#[test]
+#[should_panic(expected = "distinguish 65537 classes of symbols")]
+fn test_too_many_symbol_classes() {
+    LexicalAnalyzer::new(vec![LexemeDescriptor::new(
+        "symbols",
+        Regex::concat(WideSymbol::list_symbols().into_iter().map(Regex::symbol).collect()),
+    )]);
+}
+
+#[test]
+fn test_patterns() {
+    let escapes = patterns::Escapes::c();
+    let lexical_analyzer = LexicalAnalyzer::new(vec![
+        LexemeDescriptor::keyword("keyword", "if"),
+        LexemeDescriptor::new("identifier", patterns::identifier()),
+        LexemeDescriptor::new(
+            "integer",
+            Regex::union(vec![
+                patterns::decimal_integer(Some('_')),
+                patterns::hex_integer(Some('_')),
+                patterns::octal_integer(None),
+                patterns::binary_integer(None),
+            ]),
+        ),
+        LexemeDescriptor::new("float", patterns::float(None)),
+        LexemeDescriptor::new("string", patterns::string_literal(&escapes)),
+        LexemeDescriptor::new("char", patterns::char_literal(&escapes)),
+        LexemeDescriptor::new("comment", patterns::line_comment("//")),
+        LexemeDescriptor::new("comment", patterns::block_comment()),
+        LexemeDescriptor::new("operator", Regex::single_char('/')),
+        LexemeDescriptor::new("space", Regex::plus_from(Regex::white_space())),
+    ]);
+
+    let input = "if iffy_2 1_000 0x7F 0o17 0b10 1.5e3 .5 \"a\\\"b\" '\\n' /* a */ / // b\nc";
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice(input))
+        .filter(|lexeme| lexeme.lexeme_type != "space")
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new("keyword", "if"),
+            Lexeme::new("identifier", "iffy_2"),
+            Lexeme::new("integer", "1_000"),
+            Lexeme::new("integer", "0x7F"),
+            Lexeme::new("integer", "0o17"),
+            Lexeme::new("integer", "0b10"),
+            Lexeme::new("float", "1.5e3"),
+            Lexeme::new("float", ".5"),
+            Lexeme::new("string", "\"a\\\"b\""),
+            Lexeme::new("char", "'\\n'"),
+            Lexeme::new("comment", "/* a */"),
+            Lexeme::new("operator", "/"),
+            Lexeme::new("comment", "// b"),
+            Lexeme::new("identifier", "c"),
+        ]
+    );
+}
+
+#[test]
+fn test_interning() {
+    let mut interner = Interner::new();
+    interner.intern(&String::from("while"));
+
+    let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+    builder.set_interned_lexeme_types(vec![TestLexemeType::Identifier, TestLexemeType::While]);
+    let lexical_analyzer = builder.build();
+
+    // The interner may be shared by consecutive analyses
+    for _ in 0..2 {
+        let reader = &mut ByteArrayReader::from_string_slice("while x y x; 12");
+        let atoms: Vec<Option<Atom>> = lexical_analyzer
+            .analyze_interned(reader, &mut interner)
+            .filter(|(lexeme, _)| lexeme.lexeme_type != TestLexemeType::WhiteSpace)
+            .map(|(_, atom)| atom)
+            .collect();
+        let contents: Vec<Option<&str>> = atoms
+            .iter()
+            .map(|atom| atom.map(|atom| interner.resolve(atom).as_str()))
+            .collect();
+        assert_eq!(
+            contents,
+            vec![Some("while"), Some("x"), Some("y"), Some("x"), None, None]
+        );
+        assert_eq!(atoms[0], interner.get(&String::from("while")));
+        assert_eq!(atoms[1], atoms[3]);
+        assert_eq!(interner.len(), 3);
+    }
+}
+
+#[test]
+fn test_lexical_analyzer_is_thread_safe() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<LexicalAnalyzer<TestLexemeType>>();
+    assert_send_sync::<LexicalAnalyzer<DynamicLexemeType>>();
+}
+
+fn string_modes_lexical_analyzer() -> LexicalAnalyzer<&'static str> {
+    let mut builder = LexicalAnalyzerBuilder::new(expression_lexeme_descriptors("}"));
+    let string_mode = builder.new_mode(vec![
+        LexemeDescriptor::new(
+            "string part",
+            Regex::plus_from(Regex::union(vec![
+                Regex::character_range(' ', '!'),
+                Regex::character_range('#', '~'),
+            ])),
+        ),
+        LexemeDescriptor::special_char("quote", '"'),
+    ]);
+    builder.set_mode_action(LexerMode::INITIAL, "quote", ModeAction::Push(string_mode));
+    builder.set_mode_action(string_mode, "quote", ModeAction::Pop);
+    builder.build()
+}
+
+fn list_lexemes<'a>(
+    lexemes: &'a LexemeList<&'static str>,
+) -> Vec<(&'a Lexeme<&'static str>, Range<usize>)> {
+    lexemes.iter().collect()
+}
+
+#[test]
+fn test_relexing() {
+    let lexical_analyzer = string_modes_lexical_analyzer();
+    let edits: Vec<(Range<usize>, &str, Range<usize>)> = vec![
+        // Replacing a name
+        (4..6, "bcd", 3..5),
+        // Splitting a name
+        (5..5, " ", 4..7),
+        // Appending
+        (26..26, " + h", 22..27),
+        // Opening a string, which swaps the strings and the code that follow
+        (0..0, "\"", 0..8),
+        // Deleting it
+        (0..1, "", 0..27),
+        // Deleting everything
+        (0..30, "", 0..0),
+    ];
+
+    let mut input = String::from("a + bc + \"d e\" + {f} + g");
+    let mut lexemes = lexical_analyzer.analyze_for_relexing(input.as_bytes());
+    for (edited_range, replacement, expected_relexed_range) in edits {
+        input.replace_range(edited_range.clone(), replacement);
+        let relexed_range = lexical_analyzer.relex(&mut lexemes, edited_range, input.as_bytes());
+        let reanalyzed = lexical_analyzer.analyze_for_relexing(input.as_bytes());
+        assert_eq!(list_lexemes(&lexemes), list_lexemes(&reanalyzed), "{:?}", input);
+        assert_eq!(relexed_range, expected_relexed_range, "{:?}", input);
+        assert_eq!(lexemes.input_length(), input.len());
+    }
+    assert!(lexemes.is_empty());
+}
+
+#[test]
+fn test_relexing_skipped_lexemes() {
+    let mut builder = LexicalAnalyzerBuilder::new(expression_lexeme_descriptors("}"));
+    builder.set_skipped_lexeme_types(vec!["space"]);
+    let lexical_analyzer = builder.build();
+
+    let mut lexemes = lexical_analyzer.analyze_for_relexing(b"a + b");
+    assert_eq!(lexemes.len(), 3);
+    assert_eq!(lexemes.get(1), Some((&Lexeme::new("plus", "+"), 2..3)));
+
+    // Replacing "b" with "bc d"
+    let relexed_range = lexical_analyzer.relex(&mut lexemes, 4..5, b"a + bc d");
+    let reanalyzed = lexical_analyzer.analyze_for_relexing(b"a + bc d");
+    assert_eq!(list_lexemes(&lexemes), list_lexemes(&reanalyzed));
+    assert_eq!(
+        list_lexemes(&lexemes),
+        vec![
+            (&Lexeme::new("name", "a"), 0..1),
+            (&Lexeme::new("plus", "+"), 2..3),
+            (&Lexeme::new("name", "bc"), 4..6),
+            (&Lexeme::new("name", "d"), 7..8),
+        ],
+    );
+    assert_eq!(relexed_range, 2..4);
+}
+
+#[test]
+fn test_relexing_unrecognized_input() {
+    let lexical_analyzer = string_modes_lexical_analyzer();
+    let mut lexemes = lexical_analyzer.analyze_for_relexing(b"a + b");
+
+    // Inserting "#!" before "b", which match no lexeme
+    let relexed_range = lexical_analyzer.relex(&mut lexemes, 4..4, b"a + #!b");
+    let reanalyzed = lexical_analyzer.analyze_for_relexing(b"a + #!b");
+    assert_eq!(list_lexemes(&lexemes), list_lexemes(&reanalyzed));
+    assert_eq!(
+        list_lexemes(&lexemes),
+        vec![
+            (&Lexeme::new("name", "a"), 0..1),
+            (&Lexeme::new("space", " "), 1..2),
+            (&Lexeme::new("plus", "+"), 2..3),
+            (&Lexeme::new("space", " "), 3..4),
+            (&Lexeme::new("name", "b"), 6..7),
+        ],
+    );
+    // The space before "b" is relexed, since its identification read past it
+    assert_eq!(relexed_range, 3..4);
+    assert_eq!(lexemes.unrecognized_ranges().collect::<Vec<_>>(), vec![4..6]);
+
+    // Deleting "#"
+    let relexed_range = lexical_analyzer.relex(&mut lexemes, 4..5, b"a + !b");
+    let reanalyzed = lexical_analyzer.analyze_for_relexing(b"a + !b");
+    assert_eq!(list_lexemes(&lexemes), list_lexemes(&reanalyzed));
+    assert_eq!(lexemes.get(4), Some((&Lexeme::new("name", "b"), 5..6)));
+    assert_eq!(relexed_range, 3..4);
+    assert_eq!(lexemes.unrecognized_ranges().collect::<Vec<_>>(), vec![4..5]);
+}
+
+#[test]
+fn test_relexing_unterminated_nested_region() {
+    let lexical_analyzer = nested_comments_lexical_analyzer(DfaConstruction::Eager);
+    let mut input = String::from("a b c");
+    let mut lexemes = lexical_analyzer.analyze_for_relexing(input.as_bytes());
+    let edits: Vec<(Range<usize>, &str, Vec<Range<usize>>)> = vec![
+        // Opening a comment, which the input ends inside of
+        (2..2, "/* ", vec![2..8]),
+        // Closing it
+        (8..8, " */", vec![]),
+        // Opening another, nested in it
+        (5..5, "/*", vec![2..13]),
+    ];
+    for (edited_range, replacement, expected_unrecognized_ranges) in edits {
+        input.replace_range(edited_range.clone(), replacement);
+        lexical_analyzer.relex(&mut lexemes, edited_range, input.as_bytes());
+        let reanalyzed = lexical_analyzer.analyze_for_relexing(input.as_bytes());
+        assert_eq!(list_lexemes(&lexemes), list_lexemes(&reanalyzed), "{:?}", input);
+        assert_eq!(
+            lexemes.unrecognized_ranges().collect::<Vec<_>>(),
+            expected_unrecognized_ranges,
+            "{:?}",
+            input,
+        );
+    }
+    assert_eq!(
+        list_lexemes(&lexemes)[..2],
+        [(&Lexeme::new("name", "a"), 0..1), (&Lexeme::new("space", " "), 1..2)],
+    );
+}
+
+#[test]
+#[should_panic(expected = "out of the bounds")]
+fn test_relexing_out_of_bounds() {
+    let lexical_analyzer = string_modes_lexical_analyzer();
+    let mut lexemes = lexical_analyzer.analyze_for_relexing(b"a + b");
+    lexical_analyzer.relex(&mut lexemes, 4..6, b"a + c");
+}
+
 #[test]
 #[should_panic]
 fn test_lexical_error() {
@@ -141,3 +1458,225 @@ fn test_lexical_error() {
         .analyze(&mut ByteArrayReader::from_string("++-+".to_string()))
         .collect::<Vec<Lexeme<()>>>();
 }
+
+#[test]
+fn test_dot_export() {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new(
+            TestLexemeType::Integer,
+            Regex::plus_from(Regex::character_range('0', '9')),
+        ),
+        LexemeDescriptor::new(
+            TestLexemeType::WhiteSpace,
+            Regex::concat(vec![Regex::line_start(), Regex::white_space()]),
+        ),
+    ]);
+    builder.set_dfa_construction(DfaConstruction::Lazy { cache_capacity: 16 });
+    let lexical_analyzer = builder.build();
+
+    let nfa_dot = lexical_analyzer.nfa_to_dot(LexerMode::INITIAL);
+    assert!(nfa_dot.starts_with("digraph {\n"));
+    assert!(nfa_dot.contains("    start -> 0;\n"));
+    assert!(nfa_dot.contains("[label=\"ε\"]"));
+    assert!(nfa_dot.contains("[label=\"^\"]"));
+    assert_eq!(nfa_dot.matches("[label=\"0-9\"]").count(), 2);
+
+    // The anchors' self-loops are omitted, and bytes are compacted into ranges
+    let dfa_dot = lexical_analyzer.minimized_dfa_to_dot(LexerMode::INITIAL);
+    assert_eq!(dfa_dot.matches(" -> ").count(), 6);
+    assert_eq!(dfa_dot.matches("[label=\"^\"]").count(), 1);
+    assert_eq!(dfa_dot.matches("[label=\"0-9\"]").count(), 3);
+    assert!(dfa_dot.contains("[label=\"\\\\x09-\\\\x0D, \\\\x20\"]"));
+    assert!(dfa_dot.contains("\\nInteger\"]"));
+    assert!(dfa_dot.contains("\\nWhiteSpace\"]"));
+}
+
+#[test]
+fn test_trace_hook() {
+    let mut builder = LexicalAnalyzerBuilder::new(vec![
+        LexemeDescriptor::new(
+            "heading",
+            Regex::concat(vec![Regex::line_start(), Regex::single_char('#')]),
+        ),
+        LexemeDescriptor::special_char("hash", '#'),
+        LexemeDescriptor::keyword("hashes", "###"),
+    ]);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let trace = events.clone();
+    builder.set_trace_hook(move |event| {
+        trace.lock().unwrap().push(match event {
+            TraceEvent::ScanStart { position, .. } => format!("start {}", position),
+            TraceEvent::Step {
+                symbol, next_state, ..
+            } => format!("step {} {}", *symbol as char, next_state.is_some()),
+            TraceEvent::AnchorFed { anchor, .. } => format!("anchor {:?}", anchor),
+            TraceEvent::Accept {
+                lexeme_type, length, ..
+            } => format!("accept {} {}", lexeme_type, length),
+            TraceEvent::Rewind {
+                scanned_length,
+                accepted_length,
+            } => format!("rewind {} {}", scanned_length, accepted_length),
+        })
+    });
+    let lexical_analyzer = builder.build();
+
+    let lexemes: Vec<Lexeme<&str>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice("##"))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![Lexeme::new("heading", "#"), Lexeme::new("hash", "#")]
+    );
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            "start 0",
+            "anchor LineStart",
+            "step # true",
+            "accept heading 1",
+            "step # true",
+            "rewind 2 1",
+            "start 1",
+            "step # true",
+            "accept hash 1",
+            "start 2",
+        ]
+    );
+}
+
+#[test]
+fn test_highlighter() {
+    let mut builder = LexicalAnalyzerBuilder::new(lexeme_descriptors());
+    builder.set_end_of_input_lexeme(TestLexemeType::SemiColon);
+    let lexical_analyzer = builder.build();
+    let mut highlighter = Highlighter::new(&lexical_analyzer);
+    highlighter.set_class(TestLexemeType::If, "keyword");
+    highlighter.set_class(TestLexemeType::While, "keyword");
+    highlighter.set_class(TestLexemeType::Integer, "number");
+    highlighter.set_ansi_style("number", "32");
+
+    // Unrecognized symbols are kept verbatim, even if they're not ASCII
+    let text = "if x<é>&  12;";
+    assert_eq!(
+        highlighter.to_html(text),
+        "<span class=\"keyword\">if</span> x&lt;é&gt;&amp;  <span class=\"number\">12</span>;"
+    );
+    assert_eq!(highlighter.to_ansi(text), "if x<é>&  \x1b[32m12\x1b[0m;");
+    assert_eq!(highlighter.to_html(""), "");
+    // So is an unterminated nested region
+    let lexical_analyzer = nested_comments_lexical_analyzer(DfaConstruction::Eager);
+    let mut highlighter = Highlighter::new(&lexical_analyzer);
+    highlighter.set_class("comment", "comment");
+    assert_eq!(
+        highlighter.to_html("a /**/ /* b"),
+        "a <span class=\"comment\">/**/</span> /* b",
+    );
+}
+
+#[test]
+fn test_highlighting_split_characters() {
+    let lexical_analyzer = LexicalAnalyzer::new(vec![
+        LexemeDescriptor::new("name", Regex::plus_from(Regex::character_range('a', 'z'))),
+        LexemeDescriptor::new("other", Regex::any_symbol()),
+    ]);
+    let mut highlighter = Highlighter::new(&lexical_analyzer);
+    highlighter.set_class("other", "other");
+    highlighter.set_ansi_style("other", "31");
+
+    // Each byte of a non-ASCII character is an "other" lexeme of its own
+    assert_eq!(highlighter.to_html("é"), "<span class=\"other\">é</span>");
+    assert_eq!(
+        highlighter.to_html("aλb€"),
+        "a<span class=\"other\">λ</span>b<span class=\"other\">€</span>",
+    );
+    assert_eq!(highlighter.to_ansi("é!"), "\x1b[31mé\x1b[0m\x1b[31m!\x1b[0m");
+}
+
+const TEST_SPEC: &str = r#"
+# Comments, and blank lines, are ignored
+
+digit = [0-9]
+integer = {digit}+
+
+"if" => IF priority 1
+[a-z]+ => NAME
+{integer} => INTEGER
+[\ \n]+ => WHITE_SPACE skip
+"/*" => COMMENT_START skip push COMMENT
+
+mode COMMENT
+"*/" => COMMENT_END skip pop
+[^*]+|\* => COMMENT_TEXT skip
+"#;
+
+#[test]
+fn test_lexer_spec() {
+    let spec = LexerSpec::parse(TEST_SPEC).unwrap();
+    assert_eq!(spec.mode("INITIAL"), Some(LexerMode::INITIAL));
+    assert_eq!(spec.lexeme_descriptors(LexerMode::INITIAL)[0].priority(), 1);
+    assert!(spec.mode("COMMENT").is_some());
+    assert_eq!(spec.mode("STRING"), None);
+
+    let input = "if x /* a * b */ 12";
+    let lexical_analyzer = spec.into_builder().build();
+    let lexemes: Vec<Lexeme<String>> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice(input))
+        .collect();
+    assert_eq!(
+        lexemes,
+        vec![
+            Lexeme::new(String::from("IF"), "if"),
+            Lexeme::new(String::from("NAME"), "x"),
+            Lexeme::new(String::from("INTEGER"), "12"),
+        ]
+    );
+
+    let (spec, names) = LexerSpec::parse(TEST_SPEC).unwrap().into_indexed();
+    assert_eq!(
+        names,
+        vec![
+            "IF",
+            "NAME",
+            "INTEGER",
+            "WHITE_SPACE",
+            "COMMENT_START",
+            "COMMENT_END",
+            "COMMENT_TEXT"
+        ]
+    );
+    let lexical_analyzer = spec.into_builder().build();
+    let lexeme_types: Vec<usize> = lexical_analyzer
+        .analyze(&mut ByteArrayReader::from_string_slice(input))
+        .map(|lexeme| lexeme.lexeme_type)
+        .collect();
+    assert_eq!(lexeme_types, vec![0, 1, 2]);
+}
+
+#[test]
+fn test_lexer_spec_errors() {
+    let error = |source: &str| LexerSpec::parse(source).err().unwrap();
+    assert_eq!(
+        error("a => A\nb = {c}"),
+        SpecError {
+            line: 2,
+            message: String::from("undefined name \"c\""),
+        }
+    );
+    assert_eq!(error("a => A push B").to_string(), "line 1: undeclared mode \"B\"");
+    assert_eq!(error("\n\na => A shout").line, 3);
+    assert_eq!(error("\"a => A").line, 1);
+    assert_eq!(error("a b c").line, 1);
+    assert_eq!(error("a => A pop push B").line, 1);
+    assert_eq!(
+        error("a+ => A\na* => B"),
+        SpecError {
+            line: 2,
+            message: String::from("pattern matches the empty string"),
+        }
+    );
+    assert_eq!(error("digits = [0-9]*\n\n{digits} => A").line, 3);
+
+    // Definitions may match the empty string
+    assert!(LexerSpec::parse("digits = [0-9]*\n[0-9]{digits} => A").is_ok());
+}
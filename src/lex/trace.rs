@@ -0,0 +1,123 @@
+use crate::lex::{Anchor, LexerMode};
+
+/// A step in the analysis of some input by a [LexicalAnalyzer](crate::lex::LexicalAnalyzer),
+/// reported to its trace hook (see
+/// [LexicalAnalyzerBuilder::set_trace_hook](crate::lex::LexicalAnalyzerBuilder::set_trace_hook)).
+///
+/// The states of the analyzer's DFA are identified by their indices, which match the nodes of
+/// [LexicalAnalyzer::minimized_dfa_to_dot](crate::lex::LexicalAnalyzer::minimized_dfa_to_dot),
+/// unless the DFA is constructed lazily (in which case they're only meaningful within a single
+/// scan).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceEvent<LexemeType, Symbol = u8> {
+    /// The analyzer started scanning for the next lexeme.
+    ScanStart {
+        /// The analyzer's current mode.
+        mode: LexerMode,
+
+        /// The offset of the lexeme from the beginning of the input, in symbols (bytes, for
+        /// text).
+        position: usize,
+    },
+
+    /// The DFA moved by the next symbol of the input.
+    Step {
+        /// The state the DFA moved from.
+        state: usize,
+
+        /// The symbol read.
+        symbol: Symbol,
+
+        /// The state the DFA moved to, or `None` if it had no transition by the symbol, which
+        /// ends the scan.
+        next_state: Option<usize>,
+    },
+
+    /// The DFA was fed an anchor whose assertion holds at the current position of the input.
+    AnchorFed {
+        /// The state the DFA moved from.
+        state: usize,
+
+        /// The anchor asserted.
+        anchor: Anchor,
+
+        /// The state the DFA moved to.
+        next_state: usize,
+    },
+
+    /// The DFA reached an accepting state, and the prefix scanned so far is the longest lexeme
+    /// found yet.
+    Accept {
+        /// The accepting state.
+        state: usize,
+
+        /// The type of the lexeme, chosen among the matching types by their priority.
+        lexeme_type: LexemeType,
+
+        /// The length of the lexeme.
+        length: usize,
+    },
+
+    /// The scan ended past the last accepting state, so the input is rewound back to the end of
+    /// the lexeme it accepted.
+    Rewind {
+        /// The number of symbols scanned.
+        scanned_length: usize,
+
+        /// The length of the accepted lexeme.
+        accepted_length: usize,
+    },
+}
+
+impl<Symbol> TraceEvent<usize, Symbol> {
+    // Events are reported by the scan with the labels of the accepting states, which are the
+    // indices of the lexeme descriptors they match
+    pub(super) fn map_label<LexemeType>(
+        self,
+        lexeme_types: &[LexemeType],
+    ) -> TraceEvent<LexemeType, Symbol>
+    where
+        LexemeType: Clone,
+    {
+        match self {
+            TraceEvent::ScanStart { mode, position } => TraceEvent::ScanStart { mode, position },
+            TraceEvent::Step {
+                state,
+                symbol,
+                next_state,
+            } => TraceEvent::Step {
+                state,
+                symbol,
+                next_state,
+            },
+            TraceEvent::AnchorFed {
+                state,
+                anchor,
+                next_state,
+            } => TraceEvent::AnchorFed {
+                state,
+                anchor,
+                next_state,
+            },
+            TraceEvent::Accept {
+                state,
+                lexeme_type,
+                length,
+            } => TraceEvent::Accept {
+                state,
+                lexeme_type: lexeme_types[lexeme_type].clone(),
+                length,
+            },
+            TraceEvent::Rewind {
+                scanned_length,
+                accepted_length,
+            } => TraceEvent::Rewind {
+                scanned_length,
+                accepted_length,
+            },
+        }
+    }
+}
+
+pub(super) type TraceHook<LexemeType, Symbol> =
+Box<dyn Fn(&TraceEvent<LexemeType, Symbol>) + Send + Sync>;
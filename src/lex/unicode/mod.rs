@@ -0,0 +1,339 @@
+use crate::lex::Regex;
+
+mod tables;
+mod utf8;
+
+/// A Unicode general category, classifying characters by their primary usage (see
+/// [UAX #44](https://www.unicode.org/reports/tr44/#General_Category_Values)).
+///
+/// Patterns matching the characters of a category are created by [Regex::general_category].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GeneralCategory {
+    /// `Lu`: an uppercase letter.
+    UppercaseLetter,
+
+    /// `Ll`: a lowercase letter.
+    LowercaseLetter,
+
+    /// `Lt`: a digraphic character, with first part uppercase.
+    TitlecaseLetter,
+
+    /// `Lm`: a modifier letter.
+    ModifierLetter,
+
+    /// `Lo`: other letters, including syllables and ideographs.
+    OtherLetter,
+
+    /// `Mn`: a nonspacing combining mark (zero advance width).
+    NonspacingMark,
+
+    /// `Mc`: a spacing combining mark (positive advance width).
+    SpacingMark,
+
+    /// `Me`: an enclosing combining mark.
+    EnclosingMark,
+
+    /// `Nd`: a decimal digit.
+    DecimalNumber,
+
+    /// `Nl`: a letterlike numeric character.
+    LetterNumber,
+
+    /// `No`: a numeric character of other type.
+    OtherNumber,
+
+    /// `Pc`: a connecting punctuation mark, like a tie.
+    ConnectorPunctuation,
+
+    /// `Pd`: a dash or hyphen punctuation mark.
+    DashPunctuation,
+
+    /// `Ps`: an opening punctuation mark (of a pair).
+    OpenPunctuation,
+
+    /// `Pe`: a closing punctuation mark (of a pair).
+    ClosePunctuation,
+
+    /// `Pi`: an initial quotation mark.
+    InitialPunctuation,
+
+    /// `Pf`: a final quotation mark.
+    FinalPunctuation,
+
+    /// `Po`: a punctuation mark of other type.
+    OtherPunctuation,
+
+    /// `Sm`: a symbol of mathematical use.
+    MathSymbol,
+
+    /// `Sc`: a currency sign.
+    CurrencySymbol,
+
+    /// `Sk`: a non-letterlike modifier symbol.
+    ModifierSymbol,
+
+    /// `So`: a symbol of other type.
+    OtherSymbol,
+
+    /// `Zs`: a space character (of various non-zero widths).
+    SpaceSeparator,
+
+    /// `Zl`: U+2028 LINE SEPARATOR only.
+    LineSeparator,
+
+    /// `Zp`: U+2029 PARAGRAPH SEPARATOR only.
+    ParagraphSeparator,
+
+    /// `Cc`: a C0 or C1 control code.
+    Control,
+
+    /// `Cf`: a format control character.
+    Format,
+
+    /// `Cs`: a surrogate code point.
+    ///
+    /// Surrogates have no UTF-8 encoding, so patterns of this category match nothing.
+    Surrogate,
+
+    /// `Co`: a private-use character.
+    PrivateUse,
+
+    /// `Cn`: a reserved unassigned code point or a noncharacter.
+    Unassigned,
+}
+
+impl GeneralCategory {
+    fn table(&self) -> &'static [(char, char)] {
+        match self {
+            GeneralCategory::UppercaseLetter => tables::UPPERCASE_LETTER,
+            GeneralCategory::LowercaseLetter => tables::LOWERCASE_LETTER,
+            GeneralCategory::TitlecaseLetter => tables::TITLECASE_LETTER,
+            GeneralCategory::ModifierLetter => tables::MODIFIER_LETTER,
+            GeneralCategory::OtherLetter => tables::OTHER_LETTER,
+            GeneralCategory::NonspacingMark => tables::NONSPACING_MARK,
+            GeneralCategory::SpacingMark => tables::SPACING_MARK,
+            GeneralCategory::EnclosingMark => tables::ENCLOSING_MARK,
+            GeneralCategory::DecimalNumber => tables::DECIMAL_NUMBER,
+            GeneralCategory::LetterNumber => tables::LETTER_NUMBER,
+            GeneralCategory::OtherNumber => tables::OTHER_NUMBER,
+            GeneralCategory::ConnectorPunctuation => tables::CONNECTOR_PUNCTUATION,
+            GeneralCategory::DashPunctuation => tables::DASH_PUNCTUATION,
+            GeneralCategory::OpenPunctuation => tables::OPEN_PUNCTUATION,
+            GeneralCategory::ClosePunctuation => tables::CLOSE_PUNCTUATION,
+            GeneralCategory::InitialPunctuation => tables::INITIAL_PUNCTUATION,
+            GeneralCategory::FinalPunctuation => tables::FINAL_PUNCTUATION,
+            GeneralCategory::OtherPunctuation => tables::OTHER_PUNCTUATION,
+            GeneralCategory::MathSymbol => tables::MATH_SYMBOL,
+            GeneralCategory::CurrencySymbol => tables::CURRENCY_SYMBOL,
+            GeneralCategory::ModifierSymbol => tables::MODIFIER_SYMBOL,
+            GeneralCategory::OtherSymbol => tables::OTHER_SYMBOL,
+            GeneralCategory::SpaceSeparator => tables::SPACE_SEPARATOR,
+            GeneralCategory::LineSeparator => tables::LINE_SEPARATOR,
+            GeneralCategory::ParagraphSeparator => tables::PARAGRAPH_SEPARATOR,
+            GeneralCategory::Control => tables::CONTROL,
+            GeneralCategory::Format => tables::FORMAT,
+            GeneralCategory::Surrogate => &[],
+            GeneralCategory::PrivateUse => tables::PRIVATE_USE,
+            GeneralCategory::Unassigned => tables::UNASSIGNED,
+        }
+    }
+}
+
+/// Patterns of Unicode character classes, matching the UTF-8 encodings of their characters.
+///
+/// The character tables are embedded in the crate, and follow version 14.0.0 of the Unicode
+/// Character Database. As these patterns are large, analyzers using them are built much faster by
+/// [DfaConstruction::Eager](crate::lex::DfaConstruction::Eager) or
+/// [DfaConstruction::Lazy](crate::lex::DfaConstruction::Lazy) than by
+/// [DfaConstruction::Derivatives](crate::lex::DfaConstruction::Derivatives).
+impl Regex {
+    /// Creates a pattern that matches a single character with the `XID_Start` property: one that
+    /// may begin an identifier, according to
+    /// [UAX #31](https://www.unicode.org/reports/tr31/).
+    ///
+    /// Note that the underscore is not included, though many languages allow identifiers to
+    /// start with it.
+    ///
+    /// # Example
+    ///
+    /// Identifiers, as defined by Rust:
+    ///
+    /// ```rust
+    /// # use syntax_parser_generator::lex::Regex;
+    /// let identifier = Regex::concat(vec![
+    ///     Regex::union(vec![Regex::xid_start(), Regex::single_char('_')]),
+    ///     Regex::star_from(Regex::xid_continue()),
+    /// ]);
+    /// ```
+    pub fn xid_start() -> Regex {
+        utf8::ranges_to_regex(tables::XID_START)
+    }
+
+    /// Creates a pattern that matches a single character with the `XID_Continue` property: one
+    /// that may follow the first character of an identifier, according to
+    /// [UAX #31](https://www.unicode.org/reports/tr31/).
+    pub fn xid_continue() -> Regex {
+        utf8::ranges_to_regex(tables::XID_CONTINUE)
+    }
+
+    /// Creates a pattern that matches a single character with the Unicode `White_Space` property.
+    ///
+    /// Unlike [Regex::white_space], this includes non-ASCII spaces and line breaks, such as
+    /// U+00A0 NO-BREAK SPACE and U+2028 LINE SEPARATOR.
+    pub fn unicode_white_space() -> Regex {
+        utf8::ranges_to_regex(tables::WHITE_SPACE)
+    }
+
+    /// Creates a pattern that matches a single character of the specified general category.
+    pub fn general_category(category: GeneralCategory) -> Regex {
+        utf8::ranges_to_regex(category.table())
+    }
+}
+
+// Creates a pattern that matches the UTF-8 encoding of a single character in any of the specified
+// ranges (inclusive)
+pub(super) fn character_ranges(ranges: &[(char, char)]) -> Regex {
+    utf8::ranges_to_regex(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automata::dfa::Dfa;
+    use crate::automata::nfa::Nfa;
+    use crate::handles::specials::AutomaticallyHandled;
+    use crate::lex::automaton::LexerSymbol;
+    use crate::lex::patterns;
+    use crate::lex::symbol_classes::SymbolClasses;
+
+    use super::*;
+
+    // The DFA consumes the classes of the symbols
+    type ClassifiedDfa = (Dfa<LexerSymbol<u8>, ()>, SymbolClasses<u8>);
+
+    fn create_dfa_for_regex(pattern: Regex) -> ClassifiedDfa {
+        let symbol_classes = SymbolClasses::new(&[pattern.clone()]);
+        let mut nfa = Nfa::new();
+        let (start, end) = pattern.build_into_nfa(&mut nfa, &symbol_classes);
+        nfa.label(end, Some(()));
+        nfa.set_initial_state(start);
+        let dfa = nfa
+            .compile_to_dfa(|labels| if labels.is_empty() { None } else { Some(()) })
+            .minimize();
+        (dfa, symbol_classes)
+    }
+
+    fn is_string_in((dfa, symbol_classes): &ClassifiedDfa, data: &str) -> bool {
+        let classes = data.bytes().map(|x| symbol_classes.classify(x));
+        match dfa.scan(classes.map(|class| LexerSymbol::Input(class).handle())) {
+            None => false,
+            Some(end_state) => dfa.get_label(end_state).is_some(),
+        }
+    }
+
+    #[test]
+    fn test_xid() {
+        let start = create_dfa_for_regex(Regex::xid_start());
+        let cont = create_dfa_for_regex(Regex::xid_continue());
+        for character in ["a", "Z", "é", "λ", "ж", "中", "𝔘"] {
+            assert!(is_string_in(&start, character), "{character}");
+            assert!(is_string_in(&cont, character), "{character}");
+        }
+        for character in ["0", "٣", "_", "\u{301}"] {
+            assert!(!is_string_in(&start, character), "{character}");
+            assert!(is_string_in(&cont, character), "{character}");
+        }
+        for character in ["", " ", "-", "€", "ab", "😀"] {
+            assert!(!is_string_in(&start, character), "{character}");
+            assert!(!is_string_in(&cont, character), "{character}");
+        }
+    }
+
+    #[test]
+    fn test_unicode_white_space() {
+        let dfa = create_dfa_for_regex(Regex::unicode_white_space());
+        for character in [" ", "\t", "\n", "\u{85}", "\u{A0}", "\u{2003}", "\u{2028}", "\u{3000}"] {
+            assert!(is_string_in(&dfa, character), "{character:?}");
+        }
+        for character in ["", "a", "\u{200B}", "  "] {
+            assert!(!is_string_in(&dfa, character), "{character:?}");
+        }
+    }
+
+    #[test]
+    fn test_general_category() {
+        let dfa = create_dfa_for_regex(Regex::general_category(GeneralCategory::CurrencySymbol));
+        for character in ["$", "€", "£", "₪"] {
+            assert!(is_string_in(&dfa, character), "{character}");
+        }
+        for character in ["a", "1", "%"] {
+            assert!(!is_string_in(&dfa, character), "{character}");
+        }
+
+        assert_eq!(
+            Regex::general_category(GeneralCategory::Surrogate),
+            Regex::union(vec![]),
+        );
+    }
+
+    #[test]
+    fn test_unicode_identifier() {
+        let dfa = create_dfa_for_regex(patterns::unicode_identifier());
+        for identifier in ["x", "_a1", "\u{E9}t\u{E9}", "\u{3BB}1", "\u{4E2D}\u{6587}"] {
+            assert!(is_string_in(&dfa, identifier), "{identifier}");
+        }
+        for identifier in ["", "1a", "a-b", "\u{2080}", "a\u{20AC}"] {
+            assert!(!is_string_in(&dfa, identifier), "{identifier}");
+        }
+    }
+
+    #[test]
+    fn test_categories_cover_all_characters() {
+        let categories = [
+            GeneralCategory::UppercaseLetter,
+            GeneralCategory::LowercaseLetter,
+            GeneralCategory::TitlecaseLetter,
+            GeneralCategory::ModifierLetter,
+            GeneralCategory::OtherLetter,
+            GeneralCategory::NonspacingMark,
+            GeneralCategory::SpacingMark,
+            GeneralCategory::EnclosingMark,
+            GeneralCategory::DecimalNumber,
+            GeneralCategory::LetterNumber,
+            GeneralCategory::OtherNumber,
+            GeneralCategory::ConnectorPunctuation,
+            GeneralCategory::DashPunctuation,
+            GeneralCategory::OpenPunctuation,
+            GeneralCategory::ClosePunctuation,
+            GeneralCategory::InitialPunctuation,
+            GeneralCategory::FinalPunctuation,
+            GeneralCategory::OtherPunctuation,
+            GeneralCategory::MathSymbol,
+            GeneralCategory::CurrencySymbol,
+            GeneralCategory::ModifierSymbol,
+            GeneralCategory::OtherSymbol,
+            GeneralCategory::SpaceSeparator,
+            GeneralCategory::LineSeparator,
+            GeneralCategory::ParagraphSeparator,
+            GeneralCategory::Control,
+            GeneralCategory::Format,
+            GeneralCategory::Surrogate,
+            GeneralCategory::PrivateUse,
+            GeneralCategory::Unassigned,
+        ];
+        let mut ranges: Vec<(char, char)> = categories
+            .iter()
+            .flat_map(|category| category.table().iter().copied())
+            .collect();
+        ranges.sort();
+
+        let mut next_value = 0;
+        for (start, end) in ranges {
+            if next_value == 0xD800 {
+                next_value = 0xE000;
+            }
+            assert_eq!(start as u32, next_value);
+            next_value = end as u32 + 1;
+        }
+        assert_eq!(next_value, 0x110000);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::lex::Regex;
+
+// A sequence of byte ranges, matching the UTF-8 encodings of some range of characters
+type ByteRanges = Vec<(u8, u8)>;
+
+const MAX_SCALAR_VALUE_BY_LENGTH: [u32; 4] = [0x7F, 0x7FF, 0xFFFF, 0x10FFFF];
+const SURROGATES_START: u32 = 0xD800;
+const SURROGATES_END: u32 = 0xDFFF;
+
+/// Compiles a set of character ranges into a pattern matching their UTF-8 encodings.
+///
+/// The encodings are organized as a trie, so that sequences sharing a common prefix share the
+/// corresponding part of the pattern (and thus, of the automata built from it).
+pub(super) fn ranges_to_regex(ranges: &[(char, char)]) -> Regex {
+    let mut sequences = Vec::new();
+    for &(start, end) in ranges {
+        split_by_length(start as u32, end as u32, &mut sequences);
+    }
+    build_trie(&sequences)
+}
+
+// Split a range of scalar values into ranges whose characters have the same encoded length
+fn split_by_length(start: u32, end: u32, sequences: &mut Vec<ByteRanges>) {
+    let mut start = start;
+    for max_value in MAX_SCALAR_VALUE_BY_LENGTH {
+        if start > end {
+            break;
+        }
+        if start <= max_value {
+            let sub_range_end = end.min(max_value);
+            split_by_surrogates(start, sub_range_end, sequences);
+            start = sub_range_end + 1;
+        }
+    }
+}
+
+// Surrogates are not valid characters, and have no UTF-8 encoding
+fn split_by_surrogates(start: u32, end: u32, sequences: &mut Vec<ByteRanges>) {
+    if start < SURROGATES_START && end >= SURROGATES_START {
+        split_by_continuation_bytes(start, SURROGATES_START - 1, sequences);
+        split_by_surrogates(SURROGATES_START, end, sequences);
+    } else if start <= SURROGATES_END && end >= SURROGATES_START {
+        if end > SURROGATES_END {
+            split_by_continuation_bytes(SURROGATES_END + 1, end, sequences);
+        }
+    } else {
+        split_by_continuation_bytes(start, end, sequences);
+    }
+}
+
+// Split a range of same-length characters into ranges whose encodings are exactly described by a
+// sequence of byte ranges (that is, each byte may independently take any value in its range)
+fn split_by_continuation_bytes(start: u32, end: u32, sequences: &mut Vec<ByteRanges>) {
+    for continuation_bytes in 1..encoded_length(start) {
+        let mask = (1u32 << (6 * continuation_bytes)) - 1;
+        if start & !mask != end & !mask {
+            if start & mask != 0 {
+                split_by_continuation_bytes(start, start | mask, sequences);
+                split_by_continuation_bytes((start | mask) + 1, end, sequences);
+                return;
+            }
+            if end & mask != mask {
+                split_by_continuation_bytes(start, (end & !mask) - 1, sequences);
+                split_by_continuation_bytes(end & !mask, end, sequences);
+                return;
+            }
+        }
+    }
+    let start_bytes = encode(start);
+    let end_bytes = encode(end);
+    sequences.push(start_bytes.into_iter().zip(end_bytes).collect());
+}
+
+fn encoded_length(value: u32) -> usize {
+    MAX_SCALAR_VALUE_BY_LENGTH
+        .iter()
+        .position(|&max_value| value <= max_value)
+        .expect("Scalar values never exceed U+10FFFF")
+        + 1
+}
+
+fn encode(value: u32) -> Vec<u8> {
+    let character = char::from_u32(value).expect("Surrogates should have been filtered out");
+    let mut buffer = [0; 4];
+    character.encode_utf8(&mut buffer).bytes().collect()
+}
+
+// Merge sequences sharing the same leading byte range into a single branch of the pattern
+fn build_trie(sequences: &[ByteRanges]) -> Regex {
+    let mut branches: Vec<((u8, u8), Vec<ByteRanges>)> = Vec::new();
+    for sequence in sequences {
+        let Some((&first, rest)) = sequence.split_first() else {
+            continue;
+        };
+        match branches.iter_mut().find(|(range, _)| *range == first) {
+            Some((_, suffixes)) => suffixes.push(rest.to_vec()),
+            None => branches.push((first, vec![rest.to_vec()])),
+        }
+    }
+
+    Regex::union(
+        branches
+            .into_iter()
+            .map(|((start, end), suffixes)| {
+                let byte_range = Regex::union((start..=end).map(Regex::symbol).collect());
+                if suffixes.iter().all(Vec::is_empty) {
+                    byte_range
+                } else {
+                    Regex::concat(vec![byte_range, build_trie(&suffixes)])
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequences_of(start: char, end: char) -> Vec<ByteRanges> {
+        let mut sequences = Vec::new();
+        split_by_length(start as u32, end as u32, &mut sequences);
+        sequences
+    }
+
+    #[test]
+    fn test_ascii_range() {
+        assert_eq!(sequences_of('a', 'z'), vec![vec![(b'a', b'z')]]);
+    }
+
+    #[test]
+    fn test_range_across_lengths() {
+        assert_eq!(
+            sequences_of('\u{70}', '\u{800}'),
+            vec![
+                vec![(0x70, 0x7F)],
+                vec![(0xC2, 0xDF), (0x80, 0xBF)],
+                vec![(0xE0, 0xE0), (0xA0, 0xA0), (0x80, 0x80)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_continuation_bytes() {
+        assert_eq!(
+            sequences_of('\u{3B1}', '\u{3C9}'),
+            vec![vec![(0xCE, 0xCE), (0xB1, 0xBF)], vec![(0xCF, 0xCF), (0x80, 0x89)]]
+        );
+    }
+
+    #[test]
+    fn test_surrogates_skipped() {
+        assert_eq!(
+            sequences_of('\u{D7FF}', '\u{E000}'),
+            vec![
+                vec![(0xED, 0xED), (0x9F, 0x9F), (0xBF, 0xBF)],
+                vec![(0xEE, 0xEE), (0x80, 0x80), (0x80, 0x80)],
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::handles::specials::AutomaticallyHandled;
-use crate::lex::Lexeme;
+use crate::lex::{DynamicLexemeType, Lexeme, LexemeTypeRegistry};
 use crate::parsing::lr_parser::rules::Associativity;
 use crate::parsing::translator::build::SyntaxDirectedTranslatorBuilder;
 use crate::parsing::translator::sdt::SyntaxDirectedTranslator;
@@ -376,3 +376,44 @@ fn test_failing_calculator_translator() {
     // 5 * (1))
     assert_eq!(calc.translate(&mut c, vec![].into_iter()), None,);
 }
+
+#[test]
+fn test_dynamic_lexeme_types() {
+    let mut registry = LexemeTypeRegistry::new();
+    let integer = registry.intern("INTEGER");
+    let star = registry.intern("STAR");
+    assert_eq!(registry.intern("INTEGER"), integer);
+    assert_eq!(registry.get_by_serial(1), Some(star.clone()));
+    assert_eq!(registry.get("PLUS"), None);
+    assert_eq!(format!("{:?}", star), "STAR");
+    assert_ne!(LexemeTypeRegistry::new().intern("INTEGER"), integer);
+
+    let mut builder = SyntaxDirectedTranslatorBuilder::new();
+    builder.new_nonterminal("expression");
+    builder.set_start_nonterminal("expression");
+    builder.dub_lexeme_type(integer.clone(), "INTEGER");
+    builder.dub_lexeme_type(star.clone(), "*");
+    builder.set_leaf_satellite_builder("INTEGER", Context::parse_integer);
+    builder.set_default_leaf_satellite_builder(|_, _| None);
+    builder.register_rule("expression", vec!["INTEGER"], Context::integer_to_expr);
+    builder.register_rule(
+        "expression",
+        vec!["expression", "*", "INTEGER"],
+        Context::multiplication,
+    );
+    let translator: SyntaxDirectedTranslator<DynamicLexemeType, Context, Satellite> =
+        builder.build();
+
+    assert_eq!(
+        translator.translate(
+            &mut Context,
+            vec![
+                Lexeme::new(integer.clone(), "6"),
+                Lexeme::new(star, "*"),
+                Lexeme::new(integer, "7"),
+            ]
+                .into_iter(),
+        ),
+        Some(Some(42))
+    );
+}
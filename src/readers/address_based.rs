@@ -12,6 +12,15 @@ pub trait AddressSpace<T> {
     fn read_at(&self, address: usize) -> Option<T>;
 }
 
+impl<T> AddressSpace<T> for Vec<T>
+where
+    T: Clone,
+{
+    fn read_at(&self, address: usize) -> Option<T> {
+        self.get(address).cloned()
+    }
+}
+
 /// A [Reader] implementation that's based on an [AddressSpace] of the read items.
 pub struct AddressBasedReader<T, AddressSpaceType>
 where
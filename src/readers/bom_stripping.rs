@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use crate::readers::Reader;
+use crate::readers::read_buffer::{consume_next, ReadBuffer};
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// A [Reader] adapter that skips the UTF-8 byte order mark (BOM) at the beginning of the bytes
+/// read by another reader, if there is one.
+///
+/// The wrapped reader is read sequentially, and only the bytes that may still be rewound to are
+/// buffered. Offsets into the adapted input can be mapped back to the wrapped reader's input with
+/// [BomStrippingReader::original_offset].
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::readers::{BomStrippingReader, ByteArrayReader, Reader};
+/// let mut reader = BomStrippingReader::new(ByteArrayReader::from_string_slice("\u{FEFF}ab"));
+/// assert_eq!(reader.read_next(), Some(b'a'));
+/// assert_eq!(reader.original_offset(0), 3);
+/// ```
+pub struct BomStrippingReader<ReaderType> {
+    buffer: ReadBuffer<u8>,
+    source: BomStrippingSource<ReaderType>,
+}
+
+// Produces the adapted bytes sequentially
+struct BomStrippingSource<ReaderType> {
+    reader: ReaderType,
+
+    // The bytes read while looking for a BOM, that turned out not to be one
+    pending_bytes: VecDeque<u8>,
+
+    // Whether a BOM was found, once the beginning of the input was read
+    has_bom: Option<bool>,
+}
+
+impl<ReaderType> BomStrippingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    /// Creates a new adapter of the bytes read by `reader`.
+    pub fn new(reader: ReaderType) -> Self {
+        Self {
+            buffer: ReadBuffer::new(),
+            source: BomStrippingSource {
+                reader,
+                pending_bytes: VecDeque::new(),
+                has_bom: None,
+            },
+        }
+    }
+
+    /// Maps an offset into the adapted input to the corresponding offset into the wrapped reader's
+    /// input.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        match self.source.has_bom {
+            Some(true) => offset + UTF8_BOM.len(),
+            _ => offset,
+        }
+    }
+
+    /// Gets the wrapped reader.
+    pub fn inner(&self) -> &ReaderType {
+        &self.source.reader
+    }
+}
+
+impl<ReaderType> BomStrippingSource<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn produce_next(&mut self) -> Option<u8> {
+        if self.has_bom.is_none() {
+            for _ in 0..UTF8_BOM.len() {
+                match consume_next(&mut self.reader) {
+                    Some(byte) => self.pending_bytes.push_back(byte),
+                    None => break,
+                }
+            }
+            let has_bom = self.pending_bytes == UTF8_BOM;
+            if has_bom {
+                self.pending_bytes.clear();
+            }
+            self.has_bom = Some(has_bom);
+        }
+        self.pending_bytes
+            .pop_front()
+            .or_else(|| consume_next(&mut self.reader))
+    }
+}
+
+impl<ReaderType> Reader<u8> for BomStrippingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn read_next(&mut self) -> Option<u8> {
+        self.buffer.read_next(|| self.source.produce_next())
+    }
+
+    fn set_head(&mut self) {
+        self.buffer.set_head()
+    }
+
+    fn set_tail(&mut self) {
+        self.buffer.set_tail()
+    }
+
+    fn move_cursor_to_tail(&mut self) {
+        self.buffer.move_cursor_to_tail()
+    }
+
+    fn get_sequence(&self) -> impl Iterator<Item=u8> {
+        self.buffer.get_sequence()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::readers::ByteArrayReader;
+
+    use super::*;
+
+    #[test]
+    fn test_bom_stripping() {
+        let mut reader = BomStrippingReader::new(ByteArrayReader::from_string_slice("\u{FEFF}ab"));
+        assert_eq!(reader.read_next(), Some(b'a'));
+        reader.set_tail();
+        assert_eq!(reader.read_next(), Some(b'b'));
+        assert_eq!(reader.read_next(), None);
+        assert_eq!(reader.get_sequence().collect::<Vec<u8>>(), b"a");
+        reader.restart_from_tail();
+        assert_eq!(reader.read_next(), Some(b'b'));
+        assert_eq!(reader.original_offset(1), 4);
+    }
+
+    #[test]
+    fn test_no_bom() {
+        let mut reader = BomStrippingReader::new(ByteArrayReader::from_string_slice("\u{FE}"));
+        assert_eq!(reader.read_next(), Some(0xC3));
+        assert_eq!(reader.read_next(), Some(0xBE));
+        assert_eq!(reader.read_next(), None);
+        assert_eq!(reader.original_offset(1), 1);
+    }
+}
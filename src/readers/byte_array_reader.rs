@@ -5,9 +5,9 @@ pub struct ByteArrayAddressSpace {
 }
 
 impl ByteArrayAddressSpace {
-    fn from_string(data: String) -> Self {
+    fn from_bytes(data: Vec<u8>) -> Self {
         ByteArrayAddressSpace {
-            data: data.into_bytes().into_boxed_slice(),
+            data: data.into_boxed_slice(),
         }
     }
 }
@@ -26,7 +26,14 @@ impl ByteArrayReader {
     /// Creates a new [Reader](crate::readers::Reader) for accessing the sequence of bytes in a
     /// given [String].
     pub fn from_string(data: String) -> ByteArrayReader {
-        let address_space = ByteArrayAddressSpace::from_string(data);
+        Self::from_bytes(data.into_bytes())
+    }
+
+    /// Creates a new [Reader](crate::readers::Reader) for accessing a given sequence of bytes,
+    /// which may be text in any encoding (see
+    /// [TranscodingReader](crate::readers::TranscodingReader)).
+    pub fn from_bytes(data: Vec<u8>) -> ByteArrayReader {
+        let address_space = ByteArrayAddressSpace::from_bytes(data);
         AddressBasedReader::raw_new(address_space)
     }
 
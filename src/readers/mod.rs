@@ -6,9 +6,16 @@
 //! API.
 
 pub use address_based::{AddressBasedReader, AddressSpace};
+pub use bom_stripping::BomStrippingReader;
 pub use byte_array_reader::ByteArrayReader;
+pub use newline_normalizing::NewlineNormalizingReader;
 pub use reader::Reader;
+pub use transcoding::{Encoding, TranscodingReader};
 
 mod reader;
 mod address_based;
 mod byte_array_reader;
+mod bom_stripping;
+mod newline_normalizing;
+mod read_buffer;
+mod transcoding;
@@ -0,0 +1,160 @@
+use crate::readers::Reader;
+use crate::readers::read_buffer::{consume_next, ReadBuffer};
+
+/// A [Reader] adapter that normalizes the line breaks in the bytes read by another reader,
+/// replacing each `\r\n` and each lone `\r` by a single `\n`.
+///
+/// The wrapped reader is read sequentially, and only the bytes that may still be rewound to are
+/// buffered. Offsets into the adapted input can be mapped back to the wrapped reader's input with
+/// [NewlineNormalizingReader::original_offset], which requires keeping the offset of every
+/// replaced `\r\n`.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::readers::{ByteArrayReader, NewlineNormalizingReader, Reader};
+/// let mut reader = NewlineNormalizingReader::new(ByteArrayReader::from_string_slice("a\r\nb\rc"));
+/// let mut normalized = Vec::new();
+/// while let Some(byte) = reader.read_next() {
+///     normalized.push(byte);
+/// }
+/// assert_eq!(normalized, b"a\nb\nc");
+/// assert_eq!(reader.original_offset(2), 3);
+/// ```
+pub struct NewlineNormalizingReader<ReaderType> {
+    buffer: ReadBuffer<u8>,
+    source: NewlineNormalizingSource<ReaderType>,
+}
+
+// Produces the adapted bytes sequentially
+struct NewlineNormalizingSource<ReaderType> {
+    reader: ReaderType,
+
+    // A byte read while looking for the `\n` following a `\r`, that turned out not to be one
+    pending_byte: Option<u8>,
+
+    // The number of bytes produced so far
+    produced_count: usize,
+
+    // The (ascending) adapted offsets of the `\n`s that replaced a `\r\n`
+    replaced_crlf_offsets: Vec<usize>,
+}
+
+impl<ReaderType> NewlineNormalizingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    /// Creates a new adapter of the bytes read by `reader`.
+    pub fn new(reader: ReaderType) -> Self {
+        Self {
+            buffer: ReadBuffer::new(),
+            source: NewlineNormalizingSource {
+                reader,
+                pending_byte: None,
+                produced_count: 0,
+                replaced_crlf_offsets: Vec::new(),
+            },
+        }
+    }
+
+    /// Maps an offset into the adapted input to the corresponding offset into the wrapped reader's
+    /// input.
+    ///
+    /// Only offsets of bytes that were already read are mapped accurately.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let replaced_crlf_count = self
+            .source
+            .replaced_crlf_offsets
+            .partition_point(|&crlf_offset| crlf_offset < offset);
+        offset + replaced_crlf_count
+    }
+
+    /// Gets the wrapped reader.
+    pub fn inner(&self) -> &ReaderType {
+        &self.source.reader
+    }
+}
+
+impl<ReaderType> NewlineNormalizingSource<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn produce_next(&mut self) -> Option<u8> {
+        let byte = match self.pending_byte.take() {
+            Some(byte) => byte,
+            None => consume_next(&mut self.reader)?,
+        };
+        let byte = if byte == b'\r' {
+            match consume_next(&mut self.reader) {
+                Some(b'\n') => self.replaced_crlf_offsets.push(self.produced_count),
+                next_byte => self.pending_byte = next_byte,
+            }
+            b'\n'
+        } else {
+            byte
+        };
+        self.produced_count += 1;
+        Some(byte)
+    }
+}
+
+impl<ReaderType> Reader<u8> for NewlineNormalizingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn read_next(&mut self) -> Option<u8> {
+        self.buffer.read_next(|| self.source.produce_next())
+    }
+
+    fn set_head(&mut self) {
+        self.buffer.set_head()
+    }
+
+    fn set_tail(&mut self) {
+        self.buffer.set_tail()
+    }
+
+    fn move_cursor_to_tail(&mut self) {
+        self.buffer.move_cursor_to_tail()
+    }
+
+    fn get_sequence(&self) -> impl Iterator<Item=u8> {
+        self.buffer.get_sequence()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::readers::ByteArrayReader;
+
+    use super::*;
+
+    #[test]
+    fn test_newline_normalization() {
+        let mut reader =
+            NewlineNormalizingReader::new(ByteArrayReader::from_string_slice("a\r\n\r\rb\r"));
+        let mut normalized = Vec::new();
+        while let Some(byte) = reader.read_next() {
+            normalized.push(byte);
+        }
+        assert_eq!(normalized, b"a\n\n\nb\n");
+        assert_eq!(
+            (0..6).map(|offset| reader.original_offset(offset)).collect::<Vec<usize>>(),
+            vec![0, 1, 3, 4, 5, 6],
+        );
+    }
+
+    #[test]
+    fn test_rewinding() {
+        let mut reader =
+            NewlineNormalizingReader::new(ByteArrayReader::from_string_slice("a\r\nb"));
+        reader.read_next();
+        reader.set_tail();
+        reader.read_next();
+        reader.read_next();
+        reader.move_cursor_to_tail();
+        assert_eq!(reader.read_next(), Some(b'\n'));
+        reader.set_tail();
+        assert_eq!(reader.get_sequence().collect::<Vec<u8>>(), b"a\n");
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+use crate::readers::Reader;
+
+/// Maintains the `head`, `cursor` and `tail` pointers of a [Reader] over
+/// items that are produced sequentially, and can't be read again (such as items transformed from
+/// another reader).
+///
+/// Produced items are buffered from `head` onwards, so that the cursor can be moved back to the
+/// tail.
+pub(super) struct ReadBuffer<T> {
+    items: VecDeque<T>,
+
+    // The address of the first buffered item
+    start_address: usize,
+
+    head_address: usize,
+    tail_address: usize,
+    cursor_address: usize,
+}
+
+impl<T> ReadBuffer<T>
+where
+    T: Clone,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            items: VecDeque::new(),
+            start_address: 0,
+            head_address: 0,
+            tail_address: 0,
+            cursor_address: 0,
+        }
+    }
+
+    /// Reads the item pointed by `cursor`, producing it with `produce_next` if it wasn't produced
+    /// yet.
+    pub(super) fn read_next(&mut self, produce_next: impl FnOnce() -> Option<T>) -> Option<T> {
+        let index = self.cursor_address - self.start_address;
+        let item = match self.items.get(index) {
+            Some(item) => item.clone(),
+            None => {
+                let item = produce_next()?;
+                self.items.push_back(item.clone());
+                item
+            }
+        };
+        self.cursor_address += 1;
+        Some(item)
+    }
+
+    pub(super) fn set_head(&mut self) {
+        self.head_address = self.cursor_address;
+        while self.start_address < self.head_address {
+            self.items.pop_front();
+            self.start_address += 1;
+        }
+    }
+
+    pub(super) fn set_tail(&mut self) {
+        self.tail_address = self.cursor_address;
+    }
+
+    pub(super) fn move_cursor_to_tail(&mut self) {
+        self.cursor_address = self.tail_address;
+    }
+
+    pub(super) fn get_sequence(&self) -> impl Iterator<Item=T> + '_ {
+        let head_index = self.head_address - self.start_address;
+        let tail_index = self.tail_address - self.start_address;
+        self.items.range(head_index..tail_index).cloned()
+    }
+}
+
+// Reads the next item of a reader that's consumed sequentially, and releases the reader's
+// resources associated with the items before it
+pub(super) fn consume_next<T>(reader: &mut impl Reader<T>) -> Option<T> {
+    let item = reader.read_next();
+    reader.set_tail();
+    reader.set_head();
+    item
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::readers::Reader;
+use crate::readers::read_buffer::{consume_next, ReadBuffer};
+
+const HIGH_SURROGATES: Range<u16> = 0xD800..0xDC00;
+const LOW_SURROGATES: Range<u16> = 0xDC00..0xE000;
+
+/// A character encoding that can be transcoded to UTF-8 by a [TranscodingReader].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-16, little-endian, without a byte order mark.
+    ///
+    /// Unpaired surrogates, and a trailing odd byte, are transcoded to the replacement character
+    /// (`U+FFFD`).
+    Utf16Le,
+
+    /// ISO-8859-1, where each byte encodes the code point of its value.
+    Latin1,
+}
+
+impl Encoding {
+    // The number of bytes encoding an ASCII character
+    fn ascii_length(&self) -> usize {
+        match self {
+            Encoding::Utf16Le => 2,
+            Encoding::Latin1 => 1,
+        }
+    }
+}
+
+/// A [Reader] adapter that transcodes the text read by another reader from some [Encoding] to
+/// UTF-8, on the fly.
+///
+/// This allows analyzing text in other encodings with a
+/// [LexicalAnalyzer](crate::lex::LexicalAnalyzer), which operates on UTF-8 bytes. Offsets into the
+/// transcoded input (such as the ones in [Span](crate::lex::Span)s) can be mapped back to the
+/// wrapped reader's input with [TranscodingReader::original_offset], which requires keeping the
+/// offsets of every non-ASCII character.
+///
+/// The wrapped reader is read sequentially, and only the bytes that may still be rewound to are
+/// buffered.
+///
+/// # Example
+///
+/// ```rust
+/// # use syntax_parser_generator::readers::{ByteArrayReader, Encoding, Reader, TranscodingReader};
+/// let utf16_text = ByteArrayReader::from_bytes(vec![0xE9, 0x00, b'a', 0x00]);
+/// let mut reader = TranscodingReader::new(utf16_text, Encoding::Utf16Le);
+/// let mut transcoded = Vec::new();
+/// while let Some(byte) = reader.read_next() {
+///     transcoded.push(byte);
+/// }
+/// assert_eq!(String::from_utf8(transcoded).unwrap(), "\u{E9}a");
+/// assert_eq!(reader.original_offset(2), 2);
+/// ```
+pub struct TranscodingReader<ReaderType> {
+    buffer: ReadBuffer<u8>,
+    source: TranscodingSource<ReaderType>,
+}
+
+// Produces the transcoded bytes sequentially
+struct TranscodingSource<ReaderType> {
+    reader: ReaderType,
+    encoding: Encoding,
+
+    // The UTF-8 bytes of the last decoded character that weren't produced yet
+    pending_bytes: VecDeque<u8>,
+
+    // A UTF-16 code unit read while looking for a low surrogate, that turned out not to be one,
+    // along with the number of bytes it was read from
+    pending_unit: Option<(u16, usize)>,
+
+    produced_count: usize,
+    consumed_count: usize,
+
+    // The (ascending) transcoded and original offsets that follow each non-ASCII character
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl<ReaderType> TranscodingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    /// Creates a new adapter of the text read by `reader`, encoded in `encoding`.
+    pub fn new(reader: ReaderType, encoding: Encoding) -> Self {
+        Self {
+            buffer: ReadBuffer::new(),
+            source: TranscodingSource {
+                reader,
+                encoding,
+                pending_bytes: VecDeque::new(),
+                pending_unit: None,
+                produced_count: 0,
+                consumed_count: 0,
+                checkpoints: Vec::new(),
+            },
+        }
+    }
+
+    /// Maps an offset into the transcoded input, at a character boundary, to the corresponding
+    /// offset into the wrapped reader's input.
+    ///
+    /// Only offsets of bytes that were already read are mapped accurately.
+    pub fn original_offset(&self, offset: usize) -> usize {
+        let checkpoints = &self.source.checkpoints;
+        let preceding_checkpoints_count =
+            checkpoints.partition_point(|&(checkpoint_offset, _)| checkpoint_offset <= offset);
+        let (checkpoint_offset, checkpoint_original_offset) = match preceding_checkpoints_count {
+            0 => (0, 0),
+            count => checkpoints[count - 1],
+        };
+        checkpoint_original_offset
+            + (offset - checkpoint_offset) * self.source.encoding.ascii_length()
+    }
+
+    /// Gets the wrapped reader.
+    pub fn inner(&self) -> &ReaderType {
+        &self.source.reader
+    }
+}
+
+impl<ReaderType> TranscodingSource<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn produce_next(&mut self) -> Option<u8> {
+        if self.pending_bytes.is_empty() {
+            let character = self.decode_next()?;
+            let mut utf8_bytes = [0; 4];
+            let utf8_bytes = character.encode_utf8(&mut utf8_bytes).as_bytes();
+            self.pending_bytes.extend(utf8_bytes);
+            if !character.is_ascii() {
+                self.checkpoints.push((
+                    self.produced_count + utf8_bytes.len(),
+                    self.consumed_count,
+                ));
+            }
+        }
+        self.produced_count += 1;
+        self.pending_bytes.pop_front()
+    }
+
+    // Decodes the next character from the wrapped reader
+    fn decode_next(&mut self) -> Option<char> {
+        match self.encoding {
+            Encoding::Latin1 => {
+                let byte = self.consume_byte()?;
+                Some(char::from(byte))
+            }
+            Encoding::Utf16Le => {
+                let unit = match self.pending_unit.take() {
+                    Some((unit, length)) => {
+                        self.consumed_count += length;
+                        unit
+                    }
+                    None => self.consume_unit()?,
+                };
+                if !HIGH_SURROGATES.contains(&unit) {
+                    let character = char::from_u32(unit as u32);
+                    return Some(character.unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                let consumed_count = self.consumed_count;
+                match self.consume_unit() {
+                    Some(next_unit) if LOW_SURROGATES.contains(&next_unit) => {
+                        let high_bits = (unit - HIGH_SURROGATES.start) as u32;
+                        let low_bits = (next_unit - LOW_SURROGATES.start) as u32;
+                        char::from_u32(0x10000 + (high_bits << 10) + low_bits)
+                    }
+                    next_unit => {
+                        if let Some(next_unit) = next_unit {
+                            // The unit is decoded on its own, so it isn't counted as consumed yet
+                            let length = self.consumed_count - consumed_count;
+                            self.consumed_count = consumed_count;
+                            self.pending_unit = Some((next_unit, length));
+                        }
+                        Some(char::REPLACEMENT_CHARACTER)
+                    }
+                }
+            }
+        }
+    }
+
+    fn consume_byte(&mut self) -> Option<u8> {
+        let byte = consume_next(&mut self.reader)?;
+        self.consumed_count += 1;
+        Some(byte)
+    }
+
+    // Reads a UTF-16LE code unit, replacing a trailing odd byte by the replacement character
+    fn consume_unit(&mut self) -> Option<u16> {
+        let low_byte = self.consume_byte()?;
+        match self.consume_byte() {
+            Some(high_byte) => Some(u16::from_le_bytes([low_byte, high_byte])),
+            None => Some(0xFFFD),
+        }
+    }
+}
+
+impl<ReaderType> Reader<u8> for TranscodingReader<ReaderType>
+where
+    ReaderType: Reader<u8>,
+{
+    fn read_next(&mut self) -> Option<u8> {
+        self.buffer.read_next(|| self.source.produce_next())
+    }
+
+    fn set_head(&mut self) {
+        self.buffer.set_head()
+    }
+
+    fn set_tail(&mut self) {
+        self.buffer.set_tail()
+    }
+
+    fn move_cursor_to_tail(&mut self) {
+        self.buffer.move_cursor_to_tail()
+    }
+
+    fn get_sequence(&self) -> impl Iterator<Item=u8> {
+        self.buffer.get_sequence()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::readers::ByteArrayReader;
+
+    use super::*;
+
+    fn transcode(
+        data: Vec<u8>,
+        encoding: Encoding,
+    ) -> (String, TranscodingReader<ByteArrayReader>) {
+        let mut reader = TranscodingReader::new(ByteArrayReader::from_bytes(data), encoding);
+        let mut transcoded = Vec::new();
+        while let Some(byte) = reader.read_next() {
+            transcoded.push(byte);
+        }
+        (String::from_utf8(transcoded).unwrap(), reader)
+    }
+
+    #[test]
+    fn test_latin1() {
+        let (transcoded, reader) = transcode(vec![b'a', 0xE9, b'b', 0xFF], Encoding::Latin1);
+        assert_eq!(transcoded, "a\u{E9}b\u{FF}");
+        assert_eq!(
+            [0, 1, 3, 4, 6].map(|offset| reader.original_offset(offset)),
+            [0, 1, 2, 3, 4],
+        );
+    }
+
+    #[test]
+    fn test_utf16le() {
+        let text = "a\u{E9}\u{1F600}b";
+        let data = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let (transcoded, reader) = transcode(data, Encoding::Utf16Le);
+        assert_eq!(transcoded, text);
+        assert_eq!(
+            [0, 1, 3, 7, 8].map(|offset| reader.original_offset(offset)),
+            [0, 2, 4, 8, 10],
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf16le() {
+        // An unpaired high surrogate, followed by `a`, and an odd trailing byte
+        let (transcoded, reader) =
+            transcode(vec![0x00, 0xD8, b'a', 0x00, b'b'], Encoding::Utf16Le);
+        assert_eq!(transcoded, "\u{FFFD}a\u{FFFD}");
+        assert_eq!(
+            [0, 3, 4, 7].map(|offset| reader.original_offset(offset)),
+            [0, 2, 4, 5],
+        );
+    }
+
+    #[test]
+    fn test_unpaired_surrogate_before_odd_byte() {
+        let (transcoded, reader) = transcode(vec![0x00, 0xD8, b'A'], Encoding::Utf16Le);
+        assert_eq!(transcoded, "\u{FFFD}\u{FFFD}");
+        assert_eq!(
+            [0, 3, 6].map(|offset| reader.original_offset(offset)),
+            [0, 2, 3],
+        );
+    }
+}